@@ -0,0 +1,218 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {Mathf, Vec3f};
+use rect::ray_slab;
+
+use std::fmt;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The axis aligned bounding box struct
+///
+/// This struct is the 3D counterpart to the rect, and is defined by its minimum and maximum
+/// corners. It is commonly used for culling, and broad-phase collision detection.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct Aabb {
+
+    // Public
+    /// The minimum corner
+    pub min : Vec3f,
+    /// The maximum corner
+    pub max : Vec3f
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Aabb {
+
+    /// Creates a new box with default values
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Aabb;
+    /// let aabb = Aabb::new ();
+    /// ```
+    pub fn new () -> Aabb {
+
+        Aabb {min : Vec3f::new (),
+              max : Vec3f::new ()}
+    }
+
+/*================================================================================================*/
+
+    /// Creates a box from its center point and half of its size.
+    pub fn from_center (center : &Vec3f, half_size : &Vec3f) -> Aabb {
+
+        Aabb {min : *center - *half_size,
+              max : *center + *half_size}
+    }
+
+/*================================================================================================*/
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union (lhs : &Aabb, rhs : &Aabb) -> Aabb {
+
+        Aabb {min : Vec3f {x : Mathf::min (lhs.min.x, rhs.min.x),
+                           y : Mathf::min (lhs.min.y, rhs.min.y),
+                           z : Mathf::min (lhs.min.z, rhs.min.z)},
+              max : Vec3f {x : Mathf::max (lhs.max.x, rhs.max.x),
+                           y : Mathf::max (lhs.max.y, rhs.max.y),
+                           z : Mathf::max (lhs.max.z, rhs.max.z)}}
+    }
+}
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Aabb {
+
+    /// Returns the center point of the box.
+    pub fn center (&self) -> Vec3f {
+
+        (self.min + self.max) * 0.5
+    }
+
+/*================================================================================================*/
+
+    /// Returns the size of the box.
+    pub fn size (&self) -> Vec3f {
+
+        self.max - self.min
+    }
+
+/*================================================================================================*/
+
+    /// Returns the surface area of the box.
+    pub fn surface_area (&self) -> f32 {
+
+        let size = self.size ();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a point lies inside the box (faces included).
+    pub fn contains_point (&self, point : &Vec3f) -> bool {
+
+        point.x >= self.min.x && point.x <= self.max.x &&
+        point.y >= self.min.y && point.y <= self.max.y &&
+        point.z >= self.min.z && point.z <= self.max.z
+    }
+
+/*================================================================================================*/
+
+    /// Checks if another box lies completely inside this box.
+    pub fn contains_aabb (&self, other : &Aabb) -> bool {
+
+        self.contains_point (&other.min) && self.contains_point (&other.max)
+    }
+
+/*================================================================================================*/
+
+    /// Checks if two boxes overlap (touching faces count as overlapping).
+    pub fn intersects (&self, other : &Aabb) -> bool {
+
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+        self.min.y <= other.max.y && self.max.y >= other.min.y &&
+        self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+/*================================================================================================*/
+
+    /// Returns a copy of the box grown by a margin on every side.
+    pub fn expand (&self, margin : f32) -> Aabb {
+
+        Aabb {min : self.min - margin,
+              max : self.max + margin}
+    }
+
+/*================================================================================================*/
+
+    /// Returns the squared distance from a point to the closest point of the box.
+    ///
+    /// Points inside the box have a distance of zero.
+    pub fn distance_squared (&self, point : &Vec3f) -> f32 {
+
+        let mut distance = 0.0;
+
+        for axis in 0..3 {
+
+            let delta = Mathf::max (Mathf::max (self.min[axis] - point[axis], 0.0),
+                                    point[axis] - self.max[axis]);
+            distance += delta * delta;
+        }
+
+        distance
+    }
+
+/*================================================================================================*/
+
+    /// Intersects a ray with the box.
+    ///
+    /// Returns the distance along the ray at which it enters the box (zero if the origin is
+    /// inside), or `None` if the ray misses or the hit is further than `max_distance`.
+    pub fn ray_intersection (&self, origin : &Vec3f, direction : &Vec3f, max_distance : f32)
+        -> Option <f32> {
+
+        let mut t_min = 0.0;
+        let mut t_max = max_distance;
+
+        for axis in 0..3 {
+
+            if !ray_slab (origin[axis], direction[axis], self.min[axis], self.max[axis],
+                          &mut t_min, &mut t_max) {
+                return None;
+            }
+        }
+
+        Some (t_min)
+    }
+}
+
+/*================================================================================================*/
+/*------OPERATOR OVERLOADS------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl PartialEq for Aabb {
+
+    // Equal to operator
+    fn eq (&self, rhs : &Aabb) -> bool {
+
+        self.min == rhs.min &&
+        self.max == rhs.max
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Aabb {
+
+    // Formats the box as "min x, min y, min z, max x, max y, max z"
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        fmt::Display::fmt (&self.min, f)?;
+        f.write_str (", ")?;
+        fmt::Display::fmt (&self.max, f)
+    }
+}
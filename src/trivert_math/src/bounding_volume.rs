@@ -0,0 +1,241 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {Aabb, Rect, Vec2f, Vec3f};
+
+/*================================================================================================*/
+/*------TRAITS------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The bounding volume trait
+///
+/// It abstracts over rects and boxes, allowing the spatial structures (grids, loose trees and
+/// bounding volume hierarchies) to work in both 2D and 3D.
+pub trait BoundingVolume : Copy {
+
+    /// The point type of the space the volume lives in
+    type Point : Copy;
+
+    /// Returns the smallest volume containing both volumes.
+    fn merge (&self, other : &Self) -> Self;
+
+    /// Checks if two volumes overlap.
+    fn overlaps (&self, other : &Self) -> bool;
+
+    /// Checks if another volume lies completely inside this volume.
+    fn encloses (&self, other : &Self) -> bool;
+
+    /// Returns a copy of the volume grown by a margin on every side.
+    fn grow (&self, margin : f32) -> Self;
+
+    /// Returns a copy of the volume scaled about its center.
+    fn scale (&self, factor : f32) -> Self;
+
+    /// Returns the cost of the volume used by tree building heuristics.
+    ///
+    /// This is the perimeter for rects and the surface area for boxes.
+    fn cost (&self) -> f32;
+
+    /// Splits the volume into equal quadrants (2D) or octants (3D).
+    fn subdivide (&self) -> Vec <Self>;
+
+    /// Returns the volume covering only a point.
+    fn from_point (point : &Self::Point) -> Self;
+
+    /// Returns the minimum and maximum corners of the volume as coordinates on three axes.
+    ///
+    /// Rects have a third coordinate of zero.
+    fn extents (&self) -> ([f32; 3], [f32; 3]);
+
+    /// Returns the squared distance from a point to the closest point of the volume.
+    fn point_distance_squared (&self, point : &Self::Point) -> f32;
+
+    /// Returns the distance along a ray at which it enters the volume, if it does at all.
+    fn ray_distance (&self, origin : &Self::Point, direction : &Self::Point, max_distance : f32)
+        -> Option <f32>;
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl BoundingVolume for Rect {
+
+    type Point = Vec2f;
+
+    // Union of two rects
+    fn merge (&self, other : &Rect) -> Rect {
+
+        Rect::union (self, other)
+    }
+
+    // Overlap test
+    fn overlaps (&self, other : &Rect) -> bool {
+
+        self.intersects (other)
+    }
+
+    // Containment test
+    fn encloses (&self, other : &Rect) -> bool {
+
+        self.contains_rect (other)
+    }
+
+    // Grows the rect by a margin
+    fn grow (&self, margin : f32) -> Rect {
+
+        self.expand (margin)
+    }
+
+    // Scales the rect about its center
+    fn scale (&self, factor : f32) -> Rect {
+
+        let size = self.size * factor;
+
+        Rect {position : self.center () - size * 0.5,
+              size}
+    }
+
+    // Perimeter of the rect
+    fn cost (&self) -> f32 {
+
+        2.0 * (self.size.x + self.size.y)
+    }
+
+    // Splits the rect into quadrants
+    fn subdivide (&self) -> Vec <Rect> {
+
+        let size = self.size * 0.5;
+
+        (0..4).map (|i| Rect {position : self.position + Vec2f {x : (i & 1) as f32,
+                                                                 y : (i >> 1) as f32} * size,
+                              size}).collect ()
+    }
+
+    // Empty rect at the point
+    fn from_point (point : &Vec2f) -> Rect {
+
+        Rect {position : *point,
+              size     : Vec2f::new ()}
+    }
+
+    // Corners of the rect, on a flat third axis
+    fn extents (&self) -> ([f32; 3], [f32; 3]) {
+
+        let (min, max) = (self.min (), self.max ());
+
+        ([min.x, min.y, 0.0], [max.x, max.y, 0.0])
+    }
+
+    // Distance to a point
+    fn point_distance_squared (&self, point : &Vec2f) -> f32 {
+
+        self.distance_squared (point)
+    }
+
+    // Ray intersection
+    fn ray_distance (&self, origin : &Vec2f, direction : &Vec2f, max_distance : f32)
+        -> Option <f32> {
+
+        self.ray_intersection (origin, direction, max_distance)
+    }
+}
+
+/*================================================================================================*/
+
+impl BoundingVolume for Aabb {
+
+    type Point = Vec3f;
+
+    // Union of two boxes
+    fn merge (&self, other : &Aabb) -> Aabb {
+
+        Aabb::union (self, other)
+    }
+
+    // Overlap test
+    fn overlaps (&self, other : &Aabb) -> bool {
+
+        self.intersects (other)
+    }
+
+    // Containment test
+    fn encloses (&self, other : &Aabb) -> bool {
+
+        self.contains_aabb (other)
+    }
+
+    // Grows the box by a margin
+    fn grow (&self, margin : f32) -> Aabb {
+
+        self.expand (margin)
+    }
+
+    // Scales the box about its center
+    fn scale (&self, factor : f32) -> Aabb {
+
+        Aabb::from_center (&self.center (), &(self.size () * (factor * 0.5)))
+    }
+
+    // Surface area of the box
+    fn cost (&self) -> f32 {
+
+        self.surface_area ()
+    }
+
+    // Splits the box into octants
+    fn subdivide (&self) -> Vec <Aabb> {
+
+        let size = self.size () * 0.5;
+
+        (0..8).map (|i| {
+
+            let min = self.min + Vec3f {x : (i & 1) as f32,
+                                        y : ((i >> 1) & 1) as f32,
+                                        z : (i >> 2) as f32} * size;
+
+            Aabb {min,
+                  max : min + size}
+
+        }).collect ()
+    }
+
+    // Empty box at the point
+    fn from_point (point : &Vec3f) -> Aabb {
+
+        Aabb {min : *point,
+              max : *point}
+    }
+
+    // Corners of the box
+    fn extents (&self) -> ([f32; 3], [f32; 3]) {
+
+        ([self.min.x, self.min.y, self.min.z], [self.max.x, self.max.y, self.max.z])
+    }
+
+    // Distance to a point
+    fn point_distance_squared (&self, point : &Vec3f) -> f32 {
+
+        self.distance_squared (point)
+    }
+
+    // Ray intersection
+    fn ray_distance (&self, origin : &Vec3f, direction : &Vec3f, max_distance : f32)
+        -> Option <f32> {
+
+        self.ray_intersection (origin, direction, max_distance)
+    }
+}
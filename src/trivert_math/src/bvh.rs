@@ -0,0 +1,600 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use BoundingVolume;
+
+use std::cmp;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The bounding volume hierarchy struct
+///
+/// A dynamic tree of bounding volumes (rects or boxes), used for broad-phase collision,
+/// ray casts and culling of moving objects. Leaves store "fat" bounds grown by a margin,
+/// so small movements do not require the tree to be updated. The tree is kept balanced
+/// using rotations as items are inserted and removed.
+///
+/// # Examples
+/// ```
+/// # use trivert_math::{Aabb, Bvh, Vec3f};
+/// let mut bvh  = Bvh::new (0.1);
+/// let size     = Vec3f {x : 1.0, y : 1.0, z : 1.0};
+/// let crate_id = bvh.insert (Aabb::from_center (&Vec3f::new (), &size), "crate");
+///
+/// let hit = bvh.ray_cast_first (&Vec3f {x : -5.0, y : 0.0, z : 0.0}, &Vec3f::right (), 100.0);
+/// assert_eq! (hit.map (|(handle, _)| handle), Some (crate_id));
+/// ```
+pub struct Bvh<B : BoundingVolume, T> {
+
+    // Private
+    nodes  : Vec <BvhNode<B, T>>,
+    free   : Vec <usize>,
+    root   : Option <usize>,
+    margin : f32,
+    count  : usize
+}
+
+/*================================================================================================*/
+
+// A node of the tree (either a leaf holding an item, or a branch with two children)
+struct BvhNode<B, T> {
+
+    bounds : B,
+    parent : Option <usize>,
+    left   : usize,
+    right  : usize,
+    height : usize,
+    leaf   : Option <(B, T)>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<B : BoundingVolume, T> Bvh<B, T> {
+
+    /// Creates a new, empty tree.
+    ///
+    /// The margin is how far the stored bounds of each item are grown, larger values make
+    /// updates cheaper for fast moving objects at the cost of less precise queries.
+    pub fn new (margin : f32) -> Bvh<B, T> {
+
+        Bvh {nodes  : Vec::new (),
+             free   : Vec::new (),
+             root   : None,
+             margin,
+             count  : 0}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Inserts an item, returning a handle used to refer to it later.
+    pub fn insert (&mut self, bounds : B, value : T) -> usize {
+
+        let leaf = self.allocate (BvhNode {bounds : bounds.grow (self.margin),
+                                           parent : None,
+                                           left   : 0,
+                                           right  : 0,
+                                           height : 0,
+                                           leaf   : Some ((bounds, value))});
+
+        self.insert_leaf (leaf);
+        self.count += 1;
+        leaf
+    }
+
+/*================================================================================================*/
+
+    /// Removes an item, returning its value if the handle was valid.
+    pub fn remove (&mut self, handle : usize) -> Option <T> {
+
+        self.bounds (handle)?;
+        self.remove_leaf (handle);
+        self.count -= 1;
+
+        let value = self.nodes[handle].leaf.take ().map (|(_, value)| value);
+        self.free.push (handle);
+        value
+    }
+
+/*================================================================================================*/
+
+    /// Moves an item to new bounds.
+    ///
+    /// Returns true if the item moved outside of its fat bounds and had to be reinserted,
+    /// or false if the tree did not change (or the handle is invalid).
+    pub fn update (&mut self, handle : usize, bounds : B) -> bool {
+
+        match self.nodes.get_mut (handle).and_then (|node| node.leaf.as_mut ()) {
+
+            Some (leaf) => leaf.0 = bounds,
+            None        => return false
+        }
+
+        if self.nodes[handle].bounds.encloses (&bounds) {
+            return false;
+        }
+
+        self.remove_leaf (handle);
+        self.nodes[handle].bounds = bounds.grow (self.margin);
+        self.insert_leaf (handle);
+        true
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item.
+    pub fn get (&self, handle : usize) -> Option <&T> {
+
+        self.nodes.get (handle).and_then (|node| node.leaf.as_ref ()).map (|leaf| &leaf.1)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item mutably.
+    pub fn get_mut (&mut self, handle : usize) -> Option <&mut T> {
+
+        self.nodes.get_mut (handle).and_then (|node| node.leaf.as_mut ()).map (|leaf| &mut leaf.1)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the bounds of an item (as given, without the margin).
+    pub fn bounds (&self, handle : usize) -> Option <B> {
+
+        self.nodes.get (handle).and_then (|node| node.leaf.as_ref ()).map (|leaf| leaf.0)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the handles of all items whose bounds overlap a region.
+    pub fn query (&self, region : &B) -> Vec <usize> {
+
+        let mut results = Vec::new ();
+        let mut stack   = self.root.into_iter ().collect::<Vec <usize>> ();
+
+        while let Some (index) = stack.pop () {
+
+            let node = &self.nodes[index];
+
+            if !node.bounds.overlaps (region) {
+                continue;
+            }
+
+            match node.leaf {
+
+                Some ((ref bounds, _)) => if bounds.overlaps (region) {
+                    results.push (index);
+                },
+
+                None => {
+                    stack.push (node.left);
+                    stack.push (node.right);
+                }
+            }
+        }
+
+        results
+    }
+
+/*================================================================================================*/
+
+    /// Casts a ray through the tree, returning every item hit sorted by distance.
+    ///
+    /// Each hit is the item handle, and the distance along the ray at which it enters the
+    /// item bounds. The direction does not need to be normalized, in which case distances are
+    /// measured in multiples of its length.
+    pub fn ray_cast (&self, origin : &B::Point, direction : &B::Point, max_distance : f32)
+        -> Vec <(usize, f32)> {
+
+        let mut results = Vec::new ();
+        let mut stack   = self.root.into_iter ().collect::<Vec <usize>> ();
+
+        while let Some (index) = stack.pop () {
+
+            let node = &self.nodes[index];
+
+            if node.bounds.ray_distance (origin, direction, max_distance).is_none () {
+                continue;
+            }
+
+            match node.leaf {
+
+                Some ((ref bounds, _)) => {
+
+                    if let Some (distance) = bounds.ray_distance (origin, direction, max_distance) {
+                        results.push ((index, distance));
+                    }
+                },
+
+                None => {
+                    stack.push (node.left);
+                    stack.push (node.right);
+                }
+            }
+        }
+
+        results.sort_by (|lhs, rhs| lhs.1.partial_cmp (&rhs.1).unwrap_or (cmp::Ordering::Equal));
+        results
+    }
+
+/*================================================================================================*/
+
+    /// Casts a ray through the tree, returning the closest item hit.
+    ///
+    /// This is faster than `ray_cast`, as branches further than the closest hit are skipped.
+    pub fn ray_cast_first (&self, origin : &B::Point, direction : &B::Point, max_distance : f32)
+        -> Option <(usize, f32)> {
+
+        let mut closest = None;
+        let mut max     = max_distance;
+        let mut stack   = self.root.into_iter ().collect::<Vec <usize>> ();
+
+        while let Some (index) = stack.pop () {
+
+            let node = &self.nodes[index];
+
+            if node.bounds.ray_distance (origin, direction, max).is_none () {
+                continue;
+            }
+
+            match node.leaf {
+
+                Some ((ref bounds, _)) => {
+
+                    if let Some (distance) = bounds.ray_distance (origin, direction, max) {
+                        closest = Some ((index, distance));
+                        max     = distance;
+                    }
+                },
+
+                None => {
+                    stack.push (node.left);
+                    stack.push (node.right);
+                }
+            }
+        }
+
+        closest
+    }
+
+/*================================================================================================*/
+
+    /// Finds the item closest to a point.
+    ///
+    /// Returns the item handle, and the squared distance from the point to its bounds
+    /// (zero if the point is inside).
+    pub fn nearest (&self, point : &B::Point) -> Option <(usize, f32)> {
+
+        let mut closest : Option <(usize, f32)> = None;
+        let mut stack   = self.root.into_iter ().collect::<Vec <usize>> ();
+
+        while let Some (index) = stack.pop () {
+
+            let node  = &self.nodes[index];
+            let bound = closest.map_or (f32::INFINITY, |(_, distance)| distance);
+
+            if node.bounds.point_distance_squared (point) >= bound {
+                continue;
+            }
+
+            match node.leaf {
+
+                Some ((ref bounds, _)) => {
+
+                    let distance = bounds.point_distance_squared (point);
+
+                    if distance < bound {
+                        closest = Some ((index, distance));
+                    }
+                },
+
+                None => {
+
+                    // Visit the closer child first, so more of the other branch can be skipped
+                    let left  = self.nodes[node.left].bounds.point_distance_squared (point);
+                    let right = self.nodes[node.right].bounds.point_distance_squared (point);
+
+                    if left < right {
+                        stack.push (node.right);
+                        stack.push (node.left);
+                    }
+
+                    else {
+                        stack.push (node.left);
+                        stack.push (node.right);
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of items in the tree.
+    pub fn len (&self) -> usize {
+
+        self.count
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the tree contains no items.
+    pub fn is_empty (&self) -> bool {
+
+        self.count == 0
+    }
+
+/*================================================================================================*/
+
+    /// Returns the height of the tree (zero when empty or holding a single item).
+    pub fn height (&self) -> usize {
+
+        self.root.map_or (0, |root| self.nodes[root].height)
+    }
+
+/*================================================================================================*/
+
+    /// Removes all items from the tree.
+    pub fn clear (&mut self) {
+
+        self.nodes.clear ();
+        self.free.clear ();
+        self.root  = None;
+        self.count = 0;
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Stores a node, reusing a free slot if there is one
+    fn allocate (&mut self, node : BvhNode<B, T>) -> usize {
+
+        match self.free.pop () {
+
+            Some (index) => {
+                self.nodes[index] = node;
+                index
+            },
+
+            None => {
+                self.nodes.push (node);
+                self.nodes.len () - 1
+            }
+        }
+    }
+
+/*================================================================================================*/
+
+    // Links a leaf into the tree, next to the sibling which increases the total cost the least
+    fn insert_leaf (&mut self, leaf : usize) {
+
+        let root = match self.root {
+
+            Some (root) => root,
+            None        => {
+                self.root = Some (leaf);
+                self.nodes[leaf].parent = None;
+                return;
+            }
+        };
+
+        let leaf_bounds = self.nodes[leaf].bounds;
+        let mut index   = root;
+
+        // Descend the tree using the surface area heuristic
+        while self.nodes[index].leaf.is_none () {
+
+            let node        = &self.nodes[index];
+            let cost        = node.bounds.cost ();
+            let merged_cost = node.bounds.merge (&leaf_bounds).cost ();
+
+            // Cost of creating a new parent here, and the cost pushed onto the children
+            let sibling_cost     = 2.0 * merged_cost;
+            let inheritance_cost = 2.0 * (merged_cost - cost);
+
+            let child_cost = |child : usize| {
+
+                let child  = &self.nodes[child];
+                let merged = child.bounds.merge (&leaf_bounds).cost ();
+
+                match child.leaf {
+                    Some (_) => merged + inheritance_cost,
+                    None     => merged - child.bounds.cost () + inheritance_cost
+                }
+            };
+
+            let left_cost  = child_cost (node.left);
+            let right_cost = child_cost (node.right);
+
+            if sibling_cost < left_cost && sibling_cost < right_cost {
+                break;
+            }
+
+            index = if left_cost < right_cost {node.left} else {node.right};
+        }
+
+        // Create a new parent for the sibling and the leaf
+        let sibling    = index;
+        let old_parent = self.nodes[sibling].parent;
+        let bounds     = self.nodes[sibling].bounds.merge (&leaf_bounds);
+        let height     = self.nodes[sibling].height + 1;
+        let parent     = self.allocate (BvhNode {bounds,
+                                                 parent : old_parent,
+                                                 left   : sibling,
+                                                 right  : leaf,
+                                                 height,
+                                                 leaf   : None});
+
+        self.nodes[sibling].parent = Some (parent);
+        self.nodes[leaf].parent    = Some (parent);
+
+        match old_parent {
+
+            Some (old_parent) => self.replace_child (old_parent, sibling, parent),
+            None              => self.root = Some (parent)
+        }
+
+        self.refit (Some (parent));
+    }
+
+/*================================================================================================*/
+
+    // Unlinks a leaf from the tree, replacing its parent with its sibling
+    fn remove_leaf (&mut self, leaf : usize) {
+
+        if self.root == Some (leaf) {
+            self.root = None;
+            return;
+        }
+
+        let parent = match self.nodes[leaf].parent {
+
+            Some (parent) => parent,
+            None          => return
+        };
+
+        let grand_parent = self.nodes[parent].parent;
+        let sibling      = if self.nodes[parent].left == leaf {self.nodes[parent].right}
+                           else {self.nodes[parent].left};
+
+        self.nodes[sibling].parent = grand_parent;
+        self.free.push (parent);
+
+        match grand_parent {
+
+            Some (grand_parent) => {
+                self.replace_child (grand_parent, parent, sibling);
+                self.refit (Some (grand_parent));
+            },
+
+            None => self.root = Some (sibling)
+        }
+    }
+
+/*================================================================================================*/
+
+    // Points a parent at a new child in place of an old one
+    fn replace_child (&mut self, parent : usize, old_child : usize, new_child : usize) {
+
+        if self.nodes[parent].left == old_child {
+            self.nodes[parent].left = new_child;
+        }
+
+        else {
+            self.nodes[parent].right = new_child;
+        }
+    }
+
+/*================================================================================================*/
+
+    // Walks up the tree from a node, rebalancing and recomputing bounds and heights
+    fn refit (&mut self, mut index : Option <usize>) {
+
+        while let Some (node) = index {
+
+            let node  = self.balance (node);
+            let left  = self.nodes[node].left;
+            let right = self.nodes[node].right;
+
+            self.nodes[node].bounds = self.nodes[left].bounds.merge (&self.nodes[right].bounds);
+            self.nodes[node].height = 1 + cmp::max (self.nodes[left].height,
+                                                    self.nodes[right].height);
+
+            index = self.nodes[node].parent;
+        }
+    }
+
+/*================================================================================================*/
+
+    // Performs a rotation if a branch is unbalanced, returning the new root of the subtree
+    fn balance (&mut self, a : usize) -> usize {
+
+        if self.nodes[a].leaf.is_some () || self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let b = self.nodes[a].left;
+        let c = self.nodes[a].right;
+        let b_height = self.nodes[b].height;
+        let c_height = self.nodes[c].height;
+
+        if c_height > b_height + 1 {
+            self.rotate (a, c, true)
+        }
+
+        else if b_height > c_height + 1 {
+            self.rotate (a, b, false)
+        }
+
+        else {
+            a
+        }
+    }
+
+/*================================================================================================*/
+
+    // Rotates a child up to take the place of its parent
+    //
+    // `child_is_right` tells which side of the parent the child is on. The taller of the
+    // child's own children stays with it, and the shorter one is handed to the old parent.
+    fn rotate (&mut self, parent : usize, child : usize, child_is_right : bool) -> usize {
+
+        let f = self.nodes[child].left;
+        let g = self.nodes[child].right;
+        let other = if child_is_right {self.nodes[parent].left} else {self.nodes[parent].right};
+
+        // Swap the parent and child
+        let grand_parent = self.nodes[parent].parent;
+
+        self.nodes[child].left    = parent;
+        self.nodes[child].parent  = grand_parent;
+        self.nodes[parent].parent = Some (child);
+
+        match grand_parent {
+
+            Some (grand_parent) => self.replace_child (grand_parent, parent, child),
+            None                => self.root = Some (child)
+        }
+
+        // Keep the taller grandchild on the child, and give the other to the parent
+        let (keep, give) = if self.nodes[f].height > self.nodes[g].height {(f, g)} else {(g, f)};
+
+        self.nodes[child].right = keep;
+        self.nodes[give].parent = Some (parent);
+
+        if child_is_right {
+            self.nodes[parent].right = give;
+        }
+
+        else {
+            self.nodes[parent].left = give;
+        }
+
+        self.nodes[parent].bounds = self.nodes[other].bounds.merge (&self.nodes[give].bounds);
+        self.nodes[parent].height = 1 + cmp::max (self.nodes[other].height,
+                                                  self.nodes[give].height);
+
+        child
+    }
+}
@@ -0,0 +1,102 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::error::Error;
+use std::fmt;
+use std::num::ParseFloatError;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The parse error enum
+///
+/// It is returned when parsing a math type from a string fails.
+#[derive (Clone, Debug, PartialEq)]
+pub enum ParseError {
+
+    /// The string contained the wrong number of comma separated components
+    ComponentCount {
+
+        /// The number of components the type requires
+        expected : usize,
+        /// The number of components found in the string
+        found    : usize
+    },
+
+    /// A component could not be parsed as a float
+    InvalidFloat (ParseFloatError)
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// Parses a comma separated list of floats (e.g. "1.0, 2.5, 3.0") into an array.
+///
+/// Used by the `FromStr` implementations of the math types.
+pub fn parse_components<A> (string : &str) -> Result <A, ParseError>
+    where A : Default + AsMut <[f32]> {
+
+    let mut components = A::default ();
+    let expected       = components.as_mut ().len ();
+    let found          = string.split (',').count ();
+
+    if found != expected {
+        return Err (ParseError::ComponentCount {expected, found});
+    }
+
+    for (component, part) in components.as_mut ().iter_mut ().zip (string.split (',')) {
+        *component = part.trim ().parse ().map_err (ParseError::InvalidFloat)?;
+    }
+
+    Ok (components)
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for ParseError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            ParseError::ComponentCount {expected, found} =>
+                write! (f, "expected {} components, found {}", expected, found),
+
+            ParseError::InvalidFloat (ref error) =>
+                write! (f, "invalid component: {}", error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ParseError {
+
+    // Returns the underlying error
+    fn source (&self) -> Option <&(dyn Error + 'static)> {
+
+        match *self {
+
+            ParseError::ComponentCount {..}    => None,
+            ParseError::InvalidFloat (ref err) => Some (err)
+        }
+    }
+}
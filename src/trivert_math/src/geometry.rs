@@ -0,0 +1,648 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {Mathf, Vec2f, Vec3f};
+
+use std::cmp::Ordering;
+use std::f32;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The winding enum
+///
+/// It describes the order in which the vertices of a polygon are specified.
+/// Winding is measured in a Y-up coordinate system.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Winding {
+
+    /// Vertices are specified in clockwise order
+    Clockwise,
+    /// Vertices are specified in counter-clockwise order
+    CounterClockwise
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The geometry utility struct
+///
+/// It contains algorithms operating on polygons and point sets, such as triangulation,
+/// convex hulls, and polygon simplification. Polygons are given as slices of vertices, and
+/// are implicitly closed (the last vertex connects back to the first).
+#[derive (Copy, Clone, Debug)]
+pub struct Geometry;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Geometry {
+
+    /// Returns the signed area of a polygon.
+    ///
+    /// The area is positive for counter-clockwise polygons, and negative for clockwise ones.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::{Geometry, Vec2f};
+    /// let square = [Vec2f {x : 0.0, y : 0.0}, Vec2f {x : 2.0, y : 0.0},
+    ///               Vec2f {x : 2.0, y : 2.0}, Vec2f {x : 0.0, y : 2.0}];
+    ///
+    /// assert_eq! (Geometry::polygon_area (&square), 4.0);
+    /// ```
+    pub fn polygon_area (polygon : &[Vec2f]) -> f32 {
+
+        let mut area = 0.0;
+
+        for (i, a) in polygon.iter ().enumerate () {
+
+            let b = &polygon[(i + 1) % polygon.len ()];
+            area += a.x * b.y - b.x * a.y;
+        }
+
+        area * 0.5
+    }
+
+/*================================================================================================*/
+
+    /// Returns the winding order of a polygon.
+    ///
+    /// Degenerate polygons (with no area) are reported as counter-clockwise.
+    pub fn winding (polygon : &[Vec2f]) -> Winding {
+
+        if Geometry::polygon_area (polygon) < 0.0 {
+            return Winding::Clockwise;
+        }
+
+        Winding::CounterClockwise
+    }
+
+/*================================================================================================*/
+
+    /// Returns the centroid (center of mass) of a polygon.
+    ///
+    /// Degenerate polygons (with no area) return the average of their vertices.
+    pub fn polygon_centroid (polygon : &[Vec2f]) -> Vec2f {
+
+        let mut centroid = Vec2f::new ();
+        let mut area     = 0.0;
+
+        for (i, a) in polygon.iter ().enumerate () {
+
+            let b     = &polygon[(i + 1) % polygon.len ()];
+            let cross = a.x * b.y - b.x * a.y;
+
+            centroid += (*a + *b) * cross;
+            area     += cross;
+        }
+
+        if area == 0.0 {
+            return polygon.iter ().sum::<Vec2f> () / Mathf::max (polygon.len () as f32, 1.0);
+        }
+
+        centroid / (3.0 * area)
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a point lies inside a polygon.
+    ///
+    /// Works with any simple polygon regardless of winding, using the even-odd rule.
+    pub fn point_in_polygon (point : &Vec2f, polygon : &[Vec2f]) -> bool {
+
+        let mut inside = false;
+
+        for (i, a) in polygon.iter ().enumerate () {
+
+            let b = &polygon[(i + polygon.len () - 1) % polygon.len ()];
+
+            if (a.y > point.y) != (b.y > point.y) &&
+               point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+
+                inside = !inside;
+            }
+        }
+
+        inside
+    }
+
+/*================================================================================================*/
+
+    /// Triangulates a simple polygon using ear clipping.
+    ///
+    /// Returns triangles as indices into the polygon, in counter-clockwise order regardless of
+    /// the winding of the input. Polygons with fewer than three vertices produce no triangles.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::{Geometry, Vec2f};
+    /// let square = [Vec2f {x : 0.0, y : 0.0}, Vec2f {x : 2.0, y : 0.0},
+    ///               Vec2f {x : 2.0, y : 2.0}, Vec2f {x : 0.0, y : 2.0}];
+    ///
+    /// assert_eq! (Geometry::triangulate (&square).len (), 2);
+    /// ```
+    pub fn triangulate (polygon : &[Vec2f]) -> Vec <[usize; 3]> {
+
+        ear_clip (polygon, oriented_ring (polygon, 0, polygon.len (), Winding::CounterClockwise))
+    }
+
+/*================================================================================================*/
+
+    /// Triangulates a simple polygon containing holes.
+    ///
+    /// Each hole is connected to the outer polygon through a pair of bridge edges, and the
+    /// result is then ear clipped. Holes must lie inside the outer polygon and not overlap each
+    /// other, but may use either winding.
+    ///
+    /// Returns the combined vertices (the outer polygon, followed by each hole in order) and
+    /// the triangles as indices into them.
+    pub fn triangulate_with_holes (polygon : &[Vec2f], holes : &[&[Vec2f]])
+        -> (Vec <Vec2f>, Vec <[usize; 3]>) {
+
+        let mut vertices = polygon.to_vec ();
+        let mut ring     = oriented_ring (polygon, 0, polygon.len (), Winding::CounterClockwise);
+        let mut hole_rings = Vec::new ();
+
+        for hole in holes {
+
+            let offset = vertices.len ();
+            vertices.extend_from_slice (hole);
+
+            if hole.len () >= 3 {
+                hole_rings.push (oriented_ring (hole, offset, hole.len (), Winding::Clockwise));
+            }
+        }
+
+        // Bridge holes from right to left, so earlier bridges never cross later holes
+        let max_x = |hole : &Vec <usize>| {
+            hole.iter ().map (|&i| vertices[i].x).fold (f32::NEG_INFINITY, f32::max)
+        };
+
+        hole_rings.sort_by (|a, b| max_x (b).partial_cmp (&max_x (a)).unwrap_or (Ordering::Equal));
+
+        for hole in hole_rings {
+            bridge_hole (&vertices, &mut ring, &hole);
+        }
+
+        let triangles = ear_clip (&vertices, ring);
+        (vertices, triangles)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the convex hull of a set of points.
+    ///
+    /// The hull is returned in counter-clockwise order, with collinear points removed.
+    pub fn convex_hull (points : &[Vec2f]) -> Vec <Vec2f> {
+
+        let mut sorted = points.to_vec ();
+        sorted.sort_by (|a, b| (a.x, a.y).partial_cmp (&(b.x, b.y)).unwrap_or (Ordering::Equal));
+        sorted.dedup ();
+
+        if sorted.len () < 3 {
+            return sorted;
+        }
+
+        // Andrew's monotone chain, building the lower then upper hull
+        let mut hull : Vec <Vec2f> = Vec::with_capacity (sorted.len () * 2);
+
+        for _ in 0..2 {
+
+            let start = hull.len ();
+
+            for point in &sorted {
+
+                while hull.len () >= start + 2 &&
+                      cross_2d (&hull[hull.len () - 2], &hull[hull.len () - 1], point) <= 0.0 {
+                    hull.pop ();
+                }
+
+                hull.push (*point);
+            }
+
+            // The last point of each chain is the first point of the next
+            hull.pop ();
+            sorted.reverse ();
+        }
+
+        hull
+    }
+
+/*================================================================================================*/
+
+    /// Returns the convex hull of a set of points in 3D.
+    ///
+    /// The hull is returned as triangles indexing into the points, wound counter-clockwise
+    /// when viewed from outside. Returns no triangles if the points are all coplanar.
+    pub fn convex_hull_3d (points : &[Vec3f]) -> Vec <[usize; 3]> {
+
+        let (mut faces, epsilon) = match initial_tetrahedron (points) {
+
+            Some (result) => result,
+            None          => return Vec::new ()
+        };
+
+        // Incrementally add each point, replacing the faces it can see
+        for (index, point) in points.iter ().enumerate () {
+
+            let visible : Vec <bool> = faces.iter ().map (|face| {
+                face_distance (points, face, point) > epsilon
+            }).collect ();
+
+            if !visible.contains (&true) {
+                continue;
+            }
+
+            // The horizon is made of visible edges whose neighbouring face is not visible
+            let mut edges = Vec::new ();
+
+            for (face, _) in faces.iter ().zip (&visible).filter (|&(_, &visible)| visible) {
+                edges.extend_from_slice (&[(face[0], face[1]),
+                                           (face[1], face[2]),
+                                           (face[2], face[0])]);
+            }
+
+            let horizon : Vec <(usize, usize)> = edges.iter ().cloned ().filter (|&(a, b)| {
+                !edges.contains (&(b, a))
+            }).collect ();
+
+            let mut visible = visible.into_iter ();
+            faces.retain (|_| !visible.next ().unwrap_or (false));
+            faces.extend (horizon.into_iter ().map (|(a, b)| [a, b, index]));
+        }
+
+        faces
+    }
+
+/*================================================================================================*/
+
+    /// Simplifies a polyline using the Ramer-Douglas-Peucker algorithm.
+    ///
+    /// Vertices closer than the tolerance to the simplified line are removed.
+    /// The first and last vertices are always kept.
+    pub fn simplify (polyline : &[Vec2f], tolerance : f32) -> Vec <Vec2f> {
+
+        if polyline.len () < 3 {
+            return polyline.to_vec ();
+        }
+
+        let mut keep  = vec! [false; polyline.len ()];
+        let mut stack = vec! [(0, polyline.len () - 1)];
+
+        keep[0]                  = true;
+        keep[polyline.len () - 1] = true;
+
+        while let Some ((start, end)) = stack.pop () {
+
+            let mut farthest = (0, 0.0);
+
+            for i in start + 1..end {
+
+                let distance = segment_distance (&polyline[i], &polyline[start], &polyline[end]);
+
+                if distance > farthest.1 {
+                    farthest = (i, distance);
+                }
+            }
+
+            if farthest.1 > tolerance {
+
+                keep[farthest.0] = true;
+                stack.push ((start, farthest.0));
+                stack.push ((farthest.0, end));
+            }
+        }
+
+        polyline.iter ().zip (keep).filter (|&(_, keep)| keep).map (|(point, _)| *point).collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Simplifies a closed polygon using the Ramer-Douglas-Peucker algorithm.
+    ///
+    /// The polygon is split at the vertex furthest from the first, and each half simplified.
+    pub fn simplify_polygon (polygon : &[Vec2f], tolerance : f32) -> Vec <Vec2f> {
+
+        if polygon.len () < 4 {
+            return polygon.to_vec ();
+        }
+
+        let distance = |i : usize| Vec2f::distance (&polygon[0], &polygon[i]);
+        let split    = (1..polygon.len ()).fold (0, |best, i| {
+            if distance (i) > distance (best) {i} else {best}
+        });
+
+        let mut second = polygon[split..].to_vec ();
+        second.push (polygon[0]);
+
+        let mut result = Geometry::simplify (&polygon[..split + 1], tolerance);
+        result.pop ();
+        result.extend (Geometry::simplify (&second, tolerance));
+        result.pop ();
+        result
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns the z component of the cross product of (b - a) and (c - b)
+fn cross_2d (a : &Vec2f, b : &Vec2f, c : &Vec2f) -> f32 {
+
+    (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x)
+}
+
+/*================================================================================================*/
+
+// Returns the distance from a point to a line segment
+fn segment_distance (point : &Vec2f, start : &Vec2f, end : &Vec2f) -> f32 {
+
+    let segment    = *end - *start;
+    let sqr_length = Vec2f::dot (&segment, &segment);
+
+    if sqr_length == 0.0 {
+        return Vec2f::distance (point, start);
+    }
+
+    let t = Mathf::clamp (Vec2f::dot (&(*point - *start), &segment) / sqr_length, 0.0, 1.0);
+    Vec2f::distance (point, &(*start + segment * t))
+}
+
+/*================================================================================================*/
+
+// Checks if a point lies inside (or on the edge of) a triangle of either winding
+fn point_in_triangle (point : &Vec2f, a : &Vec2f, b : &Vec2f, c : &Vec2f) -> bool {
+
+    let d0 = cross_2d (a, b, point);
+    let d1 = cross_2d (b, c, point);
+    let d2 = cross_2d (c, a, point);
+
+    (d0 >= 0.0 && d1 >= 0.0 && d2 >= 0.0) || (d0 <= 0.0 && d1 <= 0.0 && d2 <= 0.0)
+}
+
+/*================================================================================================*/
+
+// Checks if a point lies within the interior angle of a counter-clockwise ring at a vertex
+fn in_wedge (prev : &Vec2f, vertex : &Vec2f, next : &Vec2f, point : &Vec2f) -> bool {
+
+    let left_of_prev = cross_2d (prev, vertex, point) > 0.0;
+    let left_of_next = cross_2d (vertex, next, point) > 0.0;
+
+    if cross_2d (prev, vertex, next) >= 0.0 {
+        return left_of_prev && left_of_next;
+    }
+
+    left_of_prev || left_of_next
+}
+
+/*================================================================================================*/
+
+// Builds a list of vertex indices (starting from an offset) in the requested winding
+fn oriented_ring (polygon : &[Vec2f], offset : usize, count : usize, winding : Winding)
+    -> Vec <usize> {
+
+    let mut ring : Vec <usize> = (offset..offset + count).collect ();
+
+    if Geometry::winding (polygon) != winding {
+        ring.reverse ();
+    }
+
+    ring
+}
+
+/*================================================================================================*/
+
+// Splices a (clockwise) hole into a (counter-clockwise) ring through a pair of bridge edges
+//
+// Uses the method from David Eberly's "Triangulation by Ear Clipping": a ray is cast to the
+// right of the rightmost hole vertex, and the nearest mutually visible ring vertex is found.
+fn bridge_hole (vertices : &[Vec2f], ring : &mut Vec <usize>, hole : &[usize]) {
+
+    let hole_start = (0..hole.len ()).fold (0, |best, i| {
+        if vertices[hole[i]].x > vertices[hole[best]].x {i} else {best}
+    });
+
+    let m = vertices[hole[hole_start]];
+
+    // Find the closest ring edge hit by a ray going right from the hole
+    let mut closest : Option <(f32, usize)> = None;
+
+    for k in 0..ring.len () {
+
+        let a = vertices[ring[k]];
+        let b = vertices[ring[(k + 1) % ring.len ()]];
+
+        // Only upward edges can be hit from inside a counter-clockwise ring, which also picks
+        // the correct side of any bridges already made to other holes
+        if !(a.y <= m.y && m.y < b.y) {
+            continue;
+        }
+
+        let x = a.x + (m.y - a.y) * (b.x - a.x) / (b.y - a.y);
+
+        if x >= m.x && closest.is_none_or (|(best, _)| x < best) {
+            closest = Some ((x, k));
+        }
+    }
+
+    let (hit_x, edge) = match closest {
+
+        Some (closest) => closest,
+        None           => return
+    };
+
+    // Candidate is the endpoint of the hit edge furthest along the ray
+    let hit       = Vec2f {x : hit_x, y : m.y};
+    let next      = (edge + 1) % ring.len ();
+    let mut best  = if vertices[ring[edge]].x > vertices[ring[next]].x {edge} else {next};
+    let candidate = vertices[ring[best]];
+
+    // Any ring vertex inside the triangle (m, hit, candidate) may block visibility,
+    // in which case the one with the smallest angle to the ray is used instead
+    if candidate != hit {
+
+        let mut best_angle = f32::INFINITY;
+
+        for (k, &index) in ring.iter ().enumerate () {
+
+            let point = vertices[index];
+
+            if point == candidate || point.x < m.x ||
+               !point_in_triangle (&point, &m, &hit, &candidate) {
+                continue;
+            }
+
+            // Bridged vertices appear twice, so check this is the side facing the hole
+            let prev = vertices[ring[(k + ring.len () - 1) % ring.len ()]];
+            let next = vertices[ring[(k + 1) % ring.len ()]];
+
+            if !in_wedge (&prev, &point, &next, &m) {
+                continue;
+            }
+
+            let delta = point - m;
+            let angle = delta.y.abs ().atan2 (delta.x);
+
+            if angle < best_angle {
+                best_angle = angle;
+                best       = k;
+            }
+        }
+    }
+
+    // ring[..=best], hole from its start around back to its start, then ring[best..]
+    let mut spliced = Vec::with_capacity (ring.len () + hole.len () + 2);
+
+    spliced.extend_from_slice (&ring[..best + 1]);
+    spliced.extend (hole[hole_start..].iter ().chain (&hole[..hole_start + 1]));
+    spliced.extend_from_slice (&ring[best..]);
+
+    *ring = spliced;
+}
+
+/*================================================================================================*/
+
+// Triangulates a counter-clockwise ring of vertex indices by repeatedly clipping ears
+fn ear_clip (vertices : &[Vec2f], mut ring : Vec <usize>) -> Vec <[usize; 3]> {
+
+    let mut triangles = Vec::new ();
+
+    if ring.len () < 3 {
+        return triangles;
+    }
+
+    let mut i        = 0;
+    let mut attempts = 0;
+
+    while ring.len () > 3 {
+
+        let count = ring.len ();
+        let prev  = ring[(i + count - 1) % count];
+        let curr  = ring[i];
+        let next  = ring[(i + 1) % count];
+
+        if is_ear (vertices, &ring, prev, curr, next) {
+
+            triangles.push ([prev, curr, next]);
+            ring.remove (i);
+            attempts = 0;
+        }
+
+        else if attempts > count {
+
+            // No ear could be found (the polygon is degenerate or self-intersecting), so
+            // force progress by clipping the current vertex, skipping it if it has no area
+            if cross_2d (&vertices[prev], &vertices[curr], &vertices[next]) != 0.0 {
+                triangles.push ([prev, curr, next]);
+            }
+
+            ring.remove (i);
+            attempts = 0;
+        }
+
+        else {
+
+            i        += 1;
+            attempts += 1;
+        }
+
+        i %= ring.len ();
+    }
+
+    if cross_2d (&vertices[ring[0]], &vertices[ring[1]], &vertices[ring[2]]) != 0.0 {
+        triangles.push ([ring[0], ring[1], ring[2]]);
+    }
+
+    triangles
+}
+
+/*================================================================================================*/
+
+// Checks if a vertex forms an ear (a convex corner with no other vertex inside its triangle)
+fn is_ear (vertices : &[Vec2f], ring : &[usize], prev : usize, curr : usize, next : usize) -> bool {
+
+    let (a, b, c) = (vertices[prev], vertices[curr], vertices[next]);
+
+    if cross_2d (&a, &b, &c) <= 0.0 {
+        return false;
+    }
+
+    // Vertices duplicated by hole bridges share positions with the triangle, so are skipped
+    !ring.iter ().any (|&index| {
+
+        let point = vertices[index];
+
+        point != a && point != b && point != c && point_in_triangle (&point, &a, &b, &c)
+    })
+}
+
+/*================================================================================================*/
+
+// Returns the signed distance of a point from the plane of a face
+fn face_distance (points : &[Vec3f], face : &[usize; 3], point : &Vec3f) -> f32 {
+
+    let a      = points[face[0]];
+    let normal = Vec3f::cross (&(points[face[1]] - a), &(points[face[2]] - a));
+
+    Vec3f::dot (&Vec3f::normalize (&normal), &(*point - a))
+}
+
+/*================================================================================================*/
+
+// Finds four non-coplanar points to start the 3D hull from
+//
+// Returns the outward facing faces of the tetrahedron, and a distance tolerance scaled to
+// the size of the point set.
+fn initial_tetrahedron (points : &[Vec3f]) -> Option <(Vec <[usize; 3]>, f32)> {
+
+    if points.len () < 4 {
+        return None;
+    }
+
+    let farthest = |score : &dyn Fn (&Vec3f) -> f32| {
+        (0..points.len ()).fold (0, |best, i| {
+            if score (&points[i]) > score (&points[best]) {i} else {best}
+        })
+    };
+
+    let a = farthest (&|point| -point.x);
+    let b = farthest (&|point| Vec3f::distance (point, &points[a]));
+    let epsilon = Vec3f::distance (&points[a], &points[b]) * 1.0e-5;
+
+    let ab = points[b] - points[a];
+    let c  = farthest (&|point| Vec3f::length (&Vec3f::cross (&ab, &(*point - points[a]))));
+
+    let normal = Vec3f::normalize (&Vec3f::cross (&ab, &(points[c] - points[a])));
+    let d      = farthest (&|point| Vec3f::dot (&normal, &(*point - points[a])).abs ());
+
+    if epsilon == 0.0 || Vec3f::dot (&normal, &(points[d] - points[a])).abs () <= epsilon {
+        return None;
+    }
+
+    // Orient the base so that the fourth point is behind it
+    let base = if Vec3f::dot (&normal, &(points[d] - points[a])) > 0.0 {[a, c, b]}
+               else {[a, b, c]};
+
+    Some ((vec! [base,
+                 [base[0], base[2], d],
+                 [base[2], base[1], d],
+                 [base[1], base[0], d]],
+           epsilon))
+}
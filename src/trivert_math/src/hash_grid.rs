@@ -0,0 +1,368 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use BoundingVolume;
+
+use std::collections::HashMap;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The hash grid struct
+///
+/// A uniform grid of square (2D) or cubic (3D) cells for broad-phase collision detection, where
+/// each item is stored in every cell its bounds overlap. Only occupied cells are stored, so the
+/// grid is unbounded.
+///
+/// It works best when items are of similar size to the cells. Items covering a very large
+/// number of cells are kept in a separate list instead, which every query checks.
+pub struct HashGrid<B : BoundingVolume, T> {
+
+    // Private
+    cell_size : f32,
+    cells     : HashMap <[i32; 3], Vec <usize>>,
+    large     : Vec <usize>,
+    items     : Vec <Option <GridItem<B, T>>>,
+    free      : Vec <usize>
+}
+
+/*================================================================================================*/
+
+// An item stored in the grid
+struct GridItem<B, T> {
+
+    bounds : B,
+    value  : T
+}
+
+/*================================================================================================*/
+
+// An inclusive range of cells
+#[derive (Copy, Clone, PartialEq, Eq)]
+struct CellRange {
+
+    min : [i32; 3],
+    max : [i32; 3]
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Number of cells an item can cover before it is kept in the large item list
+const MAX_ITEM_CELLS : u64 = 1024;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<B : BoundingVolume, T> HashGrid<B, T> {
+
+    /// Creates a new, empty grid with the given cell size.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::{Aabb, HashGrid, Rect};
+    /// let grid  : HashGrid <Rect, u32> = HashGrid::new (64.0);
+    /// let space : HashGrid <Aabb, u32> = HashGrid::new (8.0);
+    /// ```
+    pub fn new (cell_size : f32) -> HashGrid<B, T> {
+
+        assert! (cell_size > 0.0, "HashGrid cell size must be positive");
+
+        HashGrid {cell_size,
+                  cells     : HashMap::new (),
+                  large     : Vec::new (),
+                  items     : Vec::new (),
+                  free      : Vec::new ()}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Inserts an item, returning a handle used to refer to it later.
+    pub fn insert (&mut self, bounds : B, value : T) -> usize {
+
+        let item   = GridItem {bounds, value};
+        let handle = match self.free.pop () {
+
+            Some (handle) => {
+                self.items[handle] = Some (item);
+                handle
+            },
+
+            None => {
+                self.items.push (Some (item));
+                self.items.len () - 1
+            }
+        };
+
+        self.add_to_cells (handle, &bounds);
+        handle
+    }
+
+/*================================================================================================*/
+
+    /// Removes an item, returning its value if the handle was valid.
+    pub fn remove (&mut self, handle : usize) -> Option <T> {
+
+        let item = self.items.get_mut (handle).and_then (|item| item.take ())?;
+
+        self.remove_from_cells (handle, &item.bounds);
+        self.free.push (handle);
+
+        Some (item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Moves an item to new bounds.
+    ///
+    /// Returns false if the handle is invalid.
+    pub fn update (&mut self, handle : usize, bounds : B) -> bool {
+
+        let old_bounds = match self.bounds (handle) {
+
+            Some (old_bounds) => old_bounds,
+            None              => return false
+        };
+
+        // Only touch the cells if the item has moved into different ones
+        if self.cell_range (&old_bounds) != self.cell_range (&bounds) {
+
+            self.remove_from_cells (handle, &old_bounds);
+            self.add_to_cells (handle, &bounds);
+        }
+
+        if let Some (item) = self.items[handle].as_mut () {
+            item.bounds = bounds;
+        }
+
+        true
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item.
+    pub fn get (&self, handle : usize) -> Option <&T> {
+
+        self.items.get (handle).and_then (|item| item.as_ref ()).map (|item| &item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item mutably.
+    pub fn get_mut (&mut self, handle : usize) -> Option <&mut T> {
+
+        self.items.get_mut (handle).and_then (|item| item.as_mut ()).map (|item| &mut item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the bounds of an item.
+    pub fn bounds (&self, handle : usize) -> Option <B> {
+
+        self.items.get (handle).and_then (|item| item.as_ref ()).map (|item| item.bounds)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the handles of all items whose bounds overlap a region.
+    ///
+    /// Regions covering more cells than are occupied check the occupied cells instead, so even
+    /// unbounded regions are cheap to query.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::{HashGrid, Rect, Vec2f};
+    /// let mut grid = HashGrid::new (16.0);
+    /// let handle   = grid.insert (Rect {position : Vec2f {x : 40.0, y : -8.0},
+    ///                                   size     : Vec2f {x : 4.0,  y : 4.0}}, "crate");
+    ///
+    /// // Reaches from the lowest coordinate out to infinity
+    /// let everything = Rect {position : Vec2f {x : -f32::MAX,     y : -f32::MAX},
+    ///                        size     : Vec2f {x : f32::INFINITY, y : f32::INFINITY}};
+    ///
+    /// assert_eq! (grid.query (&everything), vec! [handle]);
+    /// assert! (grid.query (&Rect {position : Vec2f::new (), size : Vec2f::new ()}).is_empty ());
+    /// ```
+    pub fn query (&self, region : &B) -> Vec <usize> {
+
+        let range       = self.cell_range (region);
+        let mut results = Vec::new ();
+
+        let mut check = |cell : &Vec <usize>| {
+            results.extend (cell.iter ().cloned ().filter (|&handle| {
+                self.bounds (handle).is_some_and (|bounds| bounds.overlaps (region))
+            }));
+        };
+
+        check (&self.large);
+
+        if range.count () > self.cells.len () as u64 {
+
+            for (_, cell) in self.cells.iter ().filter (|&(key, _)| range.contains (key)) {
+                check (cell);
+            }
+
+        } else {
+
+            for key in range.cells () {
+
+                if let Some (cell) = self.cells.get (&key) {
+                    check (cell);
+                }
+            }
+        }
+
+        // Items spanning several cells are found more than once
+        results.sort ();
+        results.dedup ();
+        results
+    }
+
+/*================================================================================================*/
+
+    /// Returns the handles of all items whose bounds contain a point.
+    pub fn query_point (&self, point : &B::Point) -> Vec <usize> {
+
+        self.query (&B::from_point (point))
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of items in the grid.
+    pub fn len (&self) -> usize {
+
+        self.items.len () - self.free.len ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the grid contains no items.
+    pub fn is_empty (&self) -> bool {
+
+        self.len () == 0
+    }
+
+/*================================================================================================*/
+
+    /// Removes all items from the grid.
+    pub fn clear (&mut self) {
+
+        self.cells.clear ();
+        self.large.clear ();
+        self.items.clear ();
+        self.free.clear ();
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Returns the range of cells covered by some bounds, clamped to the cells which can be keyed
+    fn cell_range (&self, bounds : &B) -> CellRange {
+
+        let (min, max) = bounds.extents ();
+        let cell       = |value : f32| (value / self.cell_size).floor () as i32;
+
+        CellRange {min : [cell (min[0]), cell (min[1]), cell (min[2])],
+                   max : [cell (max[0]), cell (max[1]), cell (max[2])]}
+    }
+
+/*================================================================================================*/
+
+    // Adds an item to every cell its bounds cover, or to the large items if there are too many
+    fn add_to_cells (&mut self, handle : usize, bounds : &B) {
+
+        let range = self.cell_range (bounds);
+
+        if range.count () > MAX_ITEM_CELLS {
+            self.large.push (handle);
+            return;
+        }
+
+        for key in range.cells () {
+            self.cells.entry (key).or_default ().push (handle);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Removes an item from every cell its bounds cover, dropping cells which become empty
+    fn remove_from_cells (&mut self, handle : usize, bounds : &B) {
+
+        let range = self.cell_range (bounds);
+
+        if range.count () > MAX_ITEM_CELLS {
+            self.large.retain (|&other| other != handle);
+            return;
+        }
+
+        for key in range.cells () {
+
+            let is_empty = match self.cells.get_mut (&key) {
+
+                Some (cell) => {
+                    cell.retain (|&other| other != handle);
+                    cell.is_empty ()
+                },
+
+                None => false
+            };
+
+            if is_empty {
+                self.cells.remove (&key);
+            }
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl CellRange {
+
+    // Returns the number of cells in the range, saturating for huge ranges
+    fn count (&self) -> u64 {
+
+        (0..3).fold (1, |count : u64, axis| {
+            let length = (self.max[axis] as i64 - self.min[axis] as i64 + 1).max (0) as u64;
+            count.saturating_mul (length)
+        })
+    }
+
+/*================================================================================================*/
+
+    // Checks if a cell is in the range
+    fn contains (&self, key : &[i32; 3]) -> bool {
+
+        (0..3).all (|axis| self.min[axis] <= key[axis] && key[axis] <= self.max[axis])
+    }
+
+/*================================================================================================*/
+
+    // Returns every cell in the range
+    fn cells (self) -> impl Iterator <Item = [i32; 3]> {
+
+        (self.min[0]..=self.max[0]).flat_map (move |x| {
+            (self.min[1]..=self.max[1]).flat_map (move |y| {
+                (self.min[2]..=self.max[2]).map (move |z| [x, y, z])
+            })
+        })
+    }
+}
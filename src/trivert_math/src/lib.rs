@@ -35,6 +35,13 @@ pub static PI       : f32 = 3.141592;
 pub static HALF_PI  : f32 = 1.570796;
 
 // Private modules
+mod aabb;
+mod bounding_volume;
+mod bvh;
+mod error;
+mod geometry;
+mod hash_grid;
+mod loose_tree;
 mod mathf;
 mod rect;
 mod vec2f;
@@ -42,8 +49,15 @@ mod vec3f;
 mod vec4f;
 
 // Public module exports
+pub use self::aabb::Aabb;
+pub use self::bounding_volume::BoundingVolume;
+pub use self::bvh::Bvh;
+pub use self::error::ParseError;
+pub use self::geometry::{Geometry, Winding};
+pub use self::hash_grid::HashGrid;
+pub use self::loose_tree::{LooseTree, Octree, Quadtree};
 pub use self::mathf::Mathf;
 pub use self::rect::Rect;
-pub use self::vec2f::Vec2f;
-pub use self::vec3f::Vec3f;
-pub use self::vec4f::Vec4f;
+pub use self::vec2f::{Vec2f, Vec2fKey};
+pub use self::vec3f::{Vec3f, Vec3fKey};
+pub use self::vec4f::{Vec4f, Vec4fKey};
@@ -0,0 +1,335 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {Aabb, BoundingVolume, Rect};
+
+use std::mem;
+
+/*================================================================================================*/
+/*------TYPES-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// A loose quadtree, for partitioning 2D space.
+pub type Quadtree<T> = LooseTree <Rect, T>;
+
+/// A loose octree, for partitioning 3D space.
+pub type Octree<T> = LooseTree <Aabb, T>;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The loose tree struct
+///
+/// A region tree which recursively subdivides space into quadrants (2D) or octants (3D).
+/// Each node's bounds are enlarged by a looseness factor, so items are always stored in a
+/// single node and never need splitting, even when they straddle cell boundaries.
+/// Nodes are only split once they hold more than a handful of items.
+///
+/// It is normally used through the `Quadtree` and `Octree` aliases.
+pub struct LooseTree<B : BoundingVolume, T> {
+
+    // Private
+    nodes     : Vec <TreeNode<B>>,
+    items     : Vec <Option <TreeItem<B, T>>>,
+    free      : Vec <usize>,
+    looseness : f32,
+    max_depth : usize
+}
+
+/*================================================================================================*/
+
+// A node of the tree
+struct TreeNode<B> {
+
+    loose_bounds : B,
+    cell_bounds  : B,
+    depth        : usize,
+    children     : Option <(usize, usize)>,
+    items        : Vec <usize>
+}
+
+/*================================================================================================*/
+
+// An item stored in the tree
+struct TreeItem<B, T> {
+
+    bounds : B,
+    value  : T,
+    node   : usize
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Number of items a node holds before it is split
+const SPLIT_THRESHOLD : usize = 8;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<B : BoundingVolume, T> LooseTree<B, T> {
+
+    /// Creates a new tree covering the given bounds, with a looseness factor of 2.
+    ///
+    /// Items outside of the bounds can still be inserted, but are kept in the root node.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::{Quadtree, Rect, Vec2f};
+    /// let world = Rect {position : Vec2f::new (), size : Vec2f {x : 1024.0, y : 1024.0}};
+    /// let tree : Quadtree <u32> = Quadtree::new (world, 6);
+    /// ```
+    pub fn new (bounds : B, max_depth : usize) -> LooseTree<B, T> {
+
+        LooseTree::with_looseness (bounds, max_depth, 2.0)
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new tree covering the given bounds, with a custom looseness factor.
+    ///
+    /// A looseness of 1 gives a regular (tight) tree, larger values allow items to be
+    /// stored deeper in the tree at the cost of more overlap between nodes.
+    pub fn with_looseness (bounds : B, max_depth : usize, looseness : f32) -> LooseTree<B, T> {
+
+        assert! (looseness >= 1.0, "LooseTree looseness must be at least 1");
+
+        LooseTree {nodes     : vec! [TreeNode {loose_bounds : bounds.scale (looseness),
+                                               cell_bounds  : bounds,
+                                               depth        : 0,
+                                               children     : None,
+                                               items        : Vec::new ()}],
+                   items     : Vec::new (),
+                   free      : Vec::new (),
+                   looseness,
+                   max_depth}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Inserts an item, returning a handle used to refer to it later.
+    pub fn insert (&mut self, bounds : B, value : T) -> usize {
+
+        let item   = TreeItem {bounds, value, node : 0};
+        let handle = match self.free.pop () {
+
+            Some (handle) => {
+                self.items[handle] = Some (item);
+                handle
+            },
+
+            None => {
+                self.items.push (Some (item));
+                self.items.len () - 1
+            }
+        };
+
+        self.place (0, handle);
+        handle
+    }
+
+/*================================================================================================*/
+
+    /// Removes an item, returning its value if the handle was valid.
+    pub fn remove (&mut self, handle : usize) -> Option <T> {
+
+        let item = self.items.get_mut (handle).and_then (|item| item.take ())?;
+
+        self.nodes[item.node].items.retain (|&other| other != handle);
+        self.free.push (handle);
+
+        Some (item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Moves an item to new bounds.
+    ///
+    /// Returns false if the handle is invalid.
+    pub fn update (&mut self, handle : usize, bounds : B) -> bool {
+
+        let node = match self.items.get_mut (handle).and_then (|item| item.as_mut ()) {
+
+            Some (item) => {
+                item.bounds = bounds;
+                item.node
+            },
+
+            None => return false
+        };
+
+        // Items which still fit their leaf node can stay where they are
+        let node_ref = &self.nodes[node];
+
+        if node_ref.children.is_none () && node_ref.loose_bounds.encloses (&bounds) {
+            return true;
+        }
+
+        self.nodes[node].items.retain (|&other| other != handle);
+        self.place (0, handle);
+        true
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item.
+    pub fn get (&self, handle : usize) -> Option <&T> {
+
+        self.items.get (handle).and_then (|item| item.as_ref ()).map (|item| &item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an item mutably.
+    pub fn get_mut (&mut self, handle : usize) -> Option <&mut T> {
+
+        self.items.get_mut (handle).and_then (|item| item.as_mut ()).map (|item| &mut item.value)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the bounds of an item.
+    pub fn bounds (&self, handle : usize) -> Option <B> {
+
+        self.items.get (handle).and_then (|item| item.as_ref ()).map (|item| item.bounds)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the handles of all items whose bounds overlap a region.
+    pub fn query (&self, region : &B) -> Vec <usize> {
+
+        let mut results = Vec::new ();
+        let mut stack   = vec! [0];
+
+        while let Some (node) = stack.pop () {
+
+            let node = &self.nodes[node];
+
+            // The root is always visited, as it holds items outside of the tree bounds
+            if node.depth > 0 && !node.loose_bounds.overlaps (region) {
+                continue;
+            }
+
+            results.extend (node.items.iter ().cloned ().filter (|&handle| {
+                self.bounds (handle).is_some_and (|bounds| bounds.overlaps (region))
+            }));
+
+            if let Some ((first, count)) = node.children {
+                stack.extend (first..first + count);
+            }
+        }
+
+        results
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of items in the tree.
+    pub fn len (&self) -> usize {
+
+        self.items.len () - self.free.len ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the tree contains no items.
+    pub fn is_empty (&self) -> bool {
+
+        self.len () == 0
+    }
+
+/*================================================================================================*/
+
+    /// Removes all items from the tree, and collapses it back to a single node.
+    pub fn clear (&mut self) {
+
+        self.nodes.truncate (1);
+        self.nodes[0].children = None;
+        self.nodes[0].items.clear ();
+        self.items.clear ();
+        self.free.clear ();
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Stores an item in the deepest node below `node` whose loose bounds enclose it
+    fn place (&mut self, mut node : usize, handle : usize) {
+
+        let bounds = match self.bounds (handle) {
+
+            Some (bounds) => bounds,
+            None          => return
+        };
+
+        while let Some ((first, count)) = self.nodes[node].children {
+
+            match (first..first + count).find (|&child| {
+                self.nodes[child].loose_bounds.encloses (&bounds)
+            }) {
+
+                Some (child) => node = child,
+                None         => break
+            }
+        }
+
+        self.nodes[node].items.push (handle);
+
+        if let Some (item) = self.items[handle].as_mut () {
+            item.node = node;
+        }
+
+        let node_ref = &self.nodes[node];
+
+        if node_ref.children.is_none () && node_ref.items.len () > SPLIT_THRESHOLD &&
+           node_ref.depth < self.max_depth {
+
+            self.split (node);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Creates the children of a node, and pushes its items down into them where possible
+    fn split (&mut self, node : usize) {
+
+        let first = self.nodes.len ();
+        let cells = self.nodes[node].cell_bounds.subdivide ();
+        let depth = self.nodes[node].depth + 1;
+
+        self.nodes[node].children = Some ((first, cells.len ()));
+
+        for cell in cells {
+
+            self.nodes.push (TreeNode {loose_bounds : cell.scale (self.looseness),
+                                       cell_bounds  : cell,
+                                       depth,
+                                       children     : None,
+                                       items        : Vec::new ()});
+        }
+
+        for handle in mem::take (&mut self.nodes[node].items) {
+            self.place (node, handle);
+        }
+    }
+}
@@ -138,3 +138,19 @@ impl Mathf {
         start + (end - start) * percentage
     }
 }
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// Returns the bits of a float, with negative zero mapped to positive zero.
+///
+/// Used by the vector hash keys, so that values which compare equal also hash equally.
+pub fn canonical_bits (value : f32) -> u32 {
+
+    if value == 0.0 {
+        return 0;
+    }
+
+    value.to_bits ()
+}
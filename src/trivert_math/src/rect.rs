@@ -14,7 +14,11 @@
 // limitations under the License.
 /*================================================================================================*/
 
-use Vec2f;
+use {Mathf, Vec2f};
+use error::{parse_components, ParseError};
+
+use std::fmt;
+use std::str::FromStr;
 
 /*================================================================================================*/
 /*------STRUCTS-----------------------------------------------------------------------------------*/
@@ -25,7 +29,7 @@ use Vec2f;
 /// This struct represents a rectangle, and contains both size and position.
 /// It is commonly used for things such as defining window size, and basic
 /// bounding box colliion detection.
-#[derive (Copy, Clone, Default)]
+#[derive (Copy, Clone, Debug, Default)]
 pub struct Rect {
 
     // Public
@@ -35,41 +39,167 @@ pub struct Rect {
     pub size     : Vec2f
 }
 
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Rect {
+
+    /// Creates a new rect with default values
+    ///
+    /// # Examples
+    /// ```
+    /// let rect = Rect::new ();
+    pub fn new () -> Rect {
+
+        Rect {position : Vec2f::new (),
+              size     : Vec2f::new ()}
+    }
+
+/*================================================================================================*/
+
+    /// Creates a rect from its minimum and maximum corners.
+    pub fn from_min_max (min : &Vec2f, max : &Vec2f) -> Rect {
+
+        Rect {position : *min,
+              size     : *max - *min}
+    }
+
+/*================================================================================================*/
+
+    /// Returns the smallest rect containing both rects.
+    pub fn union (lhs : &Rect, rhs : &Rect) -> Rect {
+
+        let min = Vec2f {x : Mathf::min (lhs.min ().x, rhs.min ().x),
+                         y : Mathf::min (lhs.min ().y, rhs.min ().y)};
+        let max = Vec2f {x : Mathf::max (lhs.max ().x, rhs.max ().x),
+                         y : Mathf::max (lhs.max ().y, rhs.max ().y)};
+
+        Rect::from_min_max (&min, &max)
+    }
+}
+
 /*================================================================================================*/
 /*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
 /*================================================================================================*/
 
 impl Rect {
 
-    /// Formats the rect as a string
+    /// Returns the minimum corner of the rect (its position).
+    pub fn min (&self) -> Vec2f {
+
+        self.position
+    }
+
+/*================================================================================================*/
+
+    /// Returns the maximum corner of the rect.
+    pub fn max (&self) -> Vec2f {
+
+        self.position + self.size
+    }
+
+/*================================================================================================*/
+
+    /// Returns the center point of the rect.
+    pub fn center (&self) -> Vec2f {
+
+        self.position + self.size * 0.5
+    }
+
+/*================================================================================================*/
+
+    /// Returns the area of the rect.
+    pub fn area (&self) -> f32 {
+
+        self.size.x * self.size.y
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a point lies inside the rect (edges included).
+    pub fn contains_point (&self, point : &Vec2f) -> bool {
+
+        let max = self.max ();
+
+        point.x >= self.position.x && point.x <= max.x &&
+        point.y >= self.position.y && point.y <= max.y
+    }
+
+/*================================================================================================*/
+
+    /// Checks if another rect lies completely inside this rect.
+    pub fn contains_rect (&self, other : &Rect) -> bool {
+
+        self.contains_point (&other.min ()) && self.contains_point (&other.max ())
+    }
+
+/*================================================================================================*/
+
+    /// Checks if two rects overlap (touching edges count as overlapping).
     ///
     /// # Examples
     /// ```
-    /// let rect = Rect {position : Vec2f {x : 10.0, y : 10.0},
-    ///                  size     : Vec2f {x : 800.0, y : 600.0}};
+    /// # use trivert_math::{Rect, Vec2f};
+    /// let a = Rect {position : Vec2f {x : 0.0, y : 0.0}, size : Vec2f {x : 2.0, y : 2.0}};
+    /// let b = Rect {position : Vec2f {x : 1.0, y : 1.0}, size : Vec2f {x : 2.0, y : 2.0}};
     ///
-    /// println! {"Rect = {}", rect.to_string ()};
+    /// assert! (a.intersects (&b));
     /// ```
-    /// ```c
-    /// Output : Rect = 10.0, 10.0, 800.0, 600.0
-    pub fn to_string (&self) -> String {
+    pub fn intersects (&self, other : &Rect) -> bool {
 
-        format! ("{}, {}", self.position.to_string (), self.size.to_string ())
+        let (min, max)             = (self.min (), self.max ());
+        let (other_min, other_max) = (other.min (), other.max ());
+
+        min.x <= other_max.x && max.x >= other_min.x &&
+        min.y <= other_max.y && max.y >= other_min.y
     }
 
 /*================================================================================================*/
-/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+
+    /// Returns a copy of the rect grown by a margin on every side.
+    pub fn expand (&self, margin : f32) -> Rect {
+
+        Rect {position : self.position - margin,
+              size     : self.size + margin * 2.0}
+    }
+
 /*================================================================================================*/
 
-    /// Creates a new rect with default values
+    /// Returns the squared distance from a point to the closest point of the rect.
     ///
-    /// # Examples
-    /// ```
-    /// let rect = Rect::new ();
-    pub fn new () -> Rect {
+    /// Points inside the rect have a distance of zero.
+    pub fn distance_squared (&self, point : &Vec2f) -> f32 {
 
-        Rect {position : Vec2f::new (),
-              size     : Vec2f::new ()}
+        let (min, max) = (self.min (), self.max ());
+        let dx = Mathf::max (Mathf::max (min.x - point.x, 0.0), point.x - max.x);
+        let dy = Mathf::max (Mathf::max (min.y - point.y, 0.0), point.y - max.y);
+
+        dx * dx + dy * dy
+    }
+
+/*================================================================================================*/
+
+    /// Intersects a ray with the rect.
+    ///
+    /// Returns the distance along the ray at which it enters the rect (zero if the origin is
+    /// inside), or `None` if the ray misses or the hit is further than `max_distance`.
+    pub fn ray_intersection (&self, origin : &Vec2f, direction : &Vec2f, max_distance : f32)
+        -> Option <f32> {
+
+        let (min, max) = (self.min (), self.max ());
+        let mut t_min  = 0.0;
+        let mut t_max  = max_distance;
+
+        for axis in 0..2 {
+
+            if !ray_slab (origin[axis], direction[axis], min[axis], max[axis],
+                          &mut t_min, &mut t_max) {
+                return None;
+            }
+        }
+
+        Some (t_min)
     }
 }
 
@@ -95,3 +225,62 @@ impl PartialEq for Rect {
         self.size     != rhs.size
     }
 }
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Rect {
+
+    // Formats the rect as "x, y, width, height", honouring any requested precision
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        fmt::Display::fmt (&self.position, f)?;
+        f.write_str (", ")?;
+        fmt::Display::fmt (&self.size, f)
+    }
+}
+
+/*================================================================================================*/
+
+impl FromStr for Rect {
+
+    type Err = ParseError;
+
+    // Parses a rect from the "x, y, width, height" format produced by Display
+    fn from_str (string : &str) -> Result <Rect, ParseError> {
+
+        let components : [f32; 4] = parse_components (string)?;
+
+        Ok (Rect {position : Vec2f {x : components[0], y : components[1]},
+                  size     : Vec2f {x : components[2], y : components[3]}})
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// Clips a ray against a single axis of a bounding box (the slab test).
+///
+/// Narrows the `t_min`..`t_max` range, returning false if it becomes empty.
+pub fn ray_slab (origin : f32, direction : f32, min : f32, max : f32,
+                 t_min : &mut f32, t_max : &mut f32) -> bool {
+
+    if direction == 0.0 {
+        return origin >= min && origin <= max;
+    }
+
+    let inverse = 1.0 / direction;
+    let mut t0  = (min - origin) * inverse;
+    let mut t1  = (max - origin) * inverse;
+
+    if t0 > t1 {
+        ::std::mem::swap (&mut t0, &mut t1);
+    }
+
+    *t_min = Mathf::max (*t_min, t0);
+    *t_max = Mathf::min (*t_max, t1);
+
+    *t_min <= *t_max
+}
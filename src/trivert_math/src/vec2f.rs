@@ -15,9 +15,16 @@
 /*================================================================================================*/
 
 use Mathf;
+use error::{parse_components, ParseError};
+use mathf::canonical_bits;
 
+use std::array::IntoIter;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
 use std::ops::*;
 use std::cmp::PartialEq;
+use std::str::FromStr;
 
 /*================================================================================================*/
 /*------STRUCTS-----------------------------------------------------------------------------------*/
@@ -27,7 +34,10 @@ use std::cmp::PartialEq;
 ///
 /// It is used mainly for 2D releated mathematics (e.g. texture and UV coordinates).
 /// 32-bit floats are used for the values.
-#[derive (Copy, Clone, Default)]
+///
+/// Components are indexed with `usize` (from `vec[0]` for x to `vec[1]` for y), which panics
+/// when the index is out of range, while `get` returns `None` instead.
+#[derive (Copy, Clone, Debug, Default)]
 pub struct Vec2f {
 
     // Public
@@ -37,24 +47,48 @@ pub struct Vec2f {
     pub y : f32
 }
 
+/*================================================================================================*/
+
+/// A hashable wrapper around Vec2f
+///
+/// It compares and hashes the bit patterns of the components, allowing vectors to be used as
+/// keys in hash maps and sets. Positive and negative zero are treated as equal, while other
+/// values (including NaN) are only equal if their bits match exactly.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct Vec2fKey (pub Vec2f);
+
 /*================================================================================================*/
 /*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
 /*================================================================================================*/
 
 impl Vec2f {
 
-    /// Formats the vector as a string.
+    /// Returns the component at the given index, or `None` if the index is out of range.
     ///
     /// # Examples
     /// ```
-    /// let vec = Vec2f {x : 10.0, y : 5.0};
-    /// println! ("Vector = {}", vec.to_string ());
+    /// # use trivert_math::Vec2f;
+    /// let vec = Vec2f::new ();
+    ///
+    /// assert_eq! (vec.get (0), Some (0.0));
+    /// assert_eq! (vec.get (2), None);
     /// ```
-    /// ```c
-    /// Output : Vector = 10.0, 5.0
-    pub fn to_string (&self) -> String {
+    pub fn get (&self, index : usize) -> Option <f32> {
+
+        match index {
+
+            0 => Some (self.x),
+            1 => Some (self.y),
+            _ => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns an iterator over the components of the vector.
+    pub fn iter (&self) -> IntoIter <f32, 2> {
 
-        format! ("{}, {}", self.x, self.y)
+        IntoIterator::into_iter ([self.x, self.y])
     }
 
 /*================================================================================================*/
@@ -155,18 +189,186 @@ impl Vec2f {
 /*================================================================================================*/
 
     /// Get a normalized vector.
+    ///
+    /// Returns a zero vector if the vector cannot be normalized.
     pub fn normalize (vector : &Vec2f) -> Vec2f {
 
+        Vec2f::normalize_or_zero (vector)
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or `None` if the vector cannot be normalized.
+    ///
+    /// Normalizing fails if the vector has no length, or if the result would not be finite
+    /// (e.g. the vector contains NaN or infinite components).
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Vec2f;
+    /// assert! (Vec2f::try_normalize (&Vec2f::new ()).is_none ());
+    /// ```
+    pub fn try_normalize (vector : &Vec2f) -> Option <Vec2f> {
+
         let length = Vec2f::length (vector);
 
-        if length != 0.0 {
+        if length != 0.0 && length.is_finite () {
+
+            return Some (Vec2f {x : vector.x / length,
+                                y : vector.y / length})
+        }
+
+        None
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or a zero vector if the vector cannot be normalized.
+    pub fn normalize_or_zero (vector : &Vec2f) -> Vec2f {
+
+        Vec2f::try_normalize (vector).unwrap_or_default ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns a vector perpendicular to the given vector.
+    ///
+    /// The vector is rotated 90 degrees counter-clockwise, and keeps its length.
+    pub fn perpendicular (vector : &Vec2f) -> Vec2f {
+
+        Vec2f {x : -vector.y,
+               y : vector.x}
+    }
+
+/*================================================================================================*/
+
+    /// Reflects a direction off the surface defined by a normal.
+    ///
+    /// The normal is expected to be normalized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Vec2f;
+    /// let dir = Vec2f {x : 1.0, y : -1.0};
+    /// println! ("Reflected = {}", Vec2f::reflect (&dir, &Vec2f::up ()).to_string ());
+    /// ```
+    /// ```c
+    /// Output : Reflected = 1, 1
+    /// ```
+    pub fn reflect (direction : &Vec2f, normal : &Vec2f) -> Vec2f {
+
+        *direction - *normal * (2.0 * Vec2f::dot (direction, normal))
+    }
+
+/*================================================================================================*/
+
+    /// Refracts a direction through the surface defined by a normal.
+    ///
+    /// Both the direction and normal are expected to be normalized, and `eta` is the ratio of
+    /// the indices of refraction. Returns a zero vector on total internal reflection.
+    pub fn refract (direction : &Vec2f, normal : &Vec2f, eta : f32) -> Vec2f {
+
+        let dot = Vec2f::dot (direction, normal);
+        let k   = 1.0 - eta * eta * (1.0 - dot * dot);
+
+        if k < 0.0 {
+            return Vec2f::new ();
+        }
+
+        *direction * eta - *normal * (eta * dot + k.sqrt ())
+    }
+
+/*================================================================================================*/
+
+    /// Projects a vector onto another vector.
+    ///
+    /// Returns a zero vector if the vector being projected onto has no length.
+    pub fn project (vector : &Vec2f, onto : &Vec2f) -> Vec2f {
+
+        let sqr_length = Vec2f::dot (onto, onto);
 
-            return Vec2f {x : vector.x / length,
-                          y : vector.y / length}
+        if sqr_length != 0.0 {
+            return *onto * (Vec2f::dot (vector, onto) / sqr_length);
         }
 
         Vec2f::new ()
     }
+
+/*================================================================================================*/
+
+    /// Returns the component of a vector perpendicular to another vector.
+    pub fn reject (vector : &Vec2f, onto : &Vec2f) -> Vec2f {
+
+        *vector - Vec2f::project (vector, onto)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the unsigned angle between two vectors in radians.
+    ///
+    /// The result is in the range [0, PI]. Returns zero if either vector has no length.
+    pub fn angle (from : &Vec2f, to : &Vec2f) -> f32 {
+
+        let lengths = Vec2f::length (from) * Vec2f::length (to);
+
+        if lengths != 0.0 {
+            return Mathf::clamp (Vec2f::dot (from, to) / lengths, -1.0, 1.0).acos ();
+        }
+
+        0.0
+    }
+
+/*================================================================================================*/
+
+    /// Returns the signed angle between two vectors in radians.
+    ///
+    /// The result is in the range [-PI, PI], and is positive when `to` lies counter-clockwise
+    /// of `from`.
+    pub fn signed_angle (from : &Vec2f, to : &Vec2f) -> f32 {
+
+        let cross = from.x * to.y - from.y * to.x;
+        cross.atan2 (Vec2f::dot (from, to))
+    }
+
+/*================================================================================================*/
+
+    /// Returns a copy of the vector with its length clamped to a maximum.
+    pub fn clamp_magnitude (vector : &Vec2f, max_length : f32) -> Vec2f {
+
+        let length = Vec2f::length (vector);
+
+        if length > max_length && length != 0.0 {
+            return *vector * (max_length / length);
+        }
+
+        *vector
+    }
+
+/*================================================================================================*/
+
+    /// Spherically interpolates between two directions.
+    ///
+    /// The angle and the length are interpolated separately, so the result travels along an arc
+    /// rather than a straight line. The percentage is clamped between 0 and 1.
+    pub fn slerp (start : &Vec2f, end : &Vec2f, percentage : f32) -> Vec2f {
+
+        let percentage   = Mathf::clamp (percentage, 0.0, 1.0);
+        let start_length = Vec2f::length (start);
+        let end_length   = Vec2f::length (end);
+
+        // Fall back to linear interpolation for zero length vectors
+        if start_length == 0.0 || end_length == 0.0 {
+            return Vec2f::lerp (start, end, percentage);
+        }
+
+        let angle  = Vec2f::signed_angle (start, end) * percentage;
+        let length = Mathf::lerp (start_length, end_length, percentage);
+        let dir    = *start / start_length;
+        let (sin, cos) = angle.sin_cos ();
+
+        Vec2f {x : dir.x * cos - dir.y * sin,
+               y : dir.x * sin + dir.y * cos} * length
+    }
 }
 
 /*================================================================================================*/
@@ -416,34 +618,270 @@ impl PartialEq for Vec2f {
 
 /*================================================================================================*/
 
-impl Index <u8> for Vec2f {
+impl Index <usize> for Vec2f {
 
     type Output = f32;
 
     // Index operator (immutable)
-    fn index (&self, index : u8) -> &f32 {
+    fn index (&self, index : usize) -> &f32 {
 
         match index {
 
             0 => &self.x,
             1 => &self.y,
-            _ => unreachable! ("Index out of range for Vec2f")
+            _ => panic! ("Index out of range for Vec2f: {}", index)
         }
     }
 }
 
 /*================================================================================================*/
 
-impl IndexMut <u8> for Vec2f {
+impl IndexMut <usize> for Vec2f {
 
     // Index operator (mutable)
-    fn index_mut (&mut self, index : u8) -> &mut f32 {
+    fn index_mut (&mut self, index : usize) -> &mut f32 {
 
         match index {
 
             0 => &mut self.x,
             1 => &mut self.y,
-            _ => unreachable! ("Index out of range for Vec2f")
+            _ => panic! ("Index out of range for Vec2f: {}", index)
         }
     }
 }
+
+/*================================================================================================*/
+
+impl Add <Vec2f> for f32 {
+
+    type Output = Vec2f;
+
+    // Addition operator (f32 on the left)
+    fn add (self, rhs : Vec2f) -> Vec2f {
+
+        Vec2f {x : self + rhs.x,
+               y : self + rhs.y}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sub <Vec2f> for f32 {
+
+    type Output = Vec2f;
+
+    // Subtraction operator (f32 on the left)
+    fn sub (self, rhs : Vec2f) -> Vec2f {
+
+        Vec2f {x : self - rhs.x,
+               y : self - rhs.y}
+    }
+}
+
+/*================================================================================================*/
+
+impl Mul <Vec2f> for f32 {
+
+    type Output = Vec2f;
+
+    // Multiplication operator (f32 on the left)
+    fn mul (self, rhs : Vec2f) -> Vec2f {
+
+        Vec2f {x : self * rhs.x,
+               y : self * rhs.y}
+    }
+}
+
+/*================================================================================================*/
+
+impl Div <Vec2f> for f32 {
+
+    type Output = Vec2f;
+
+    // Division operator (f32 on the left)
+    fn div (self, rhs : Vec2f) -> Vec2f {
+
+        Vec2f {x : self / rhs.x,
+               y : self / rhs.y}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sum for Vec2f {
+
+    // Sum of an iterator of vectors (component-wise)
+    fn sum<I> (iter : I) -> Vec2f where I : Iterator <Item = Vec2f> {
+
+        iter.fold (Vec2f {x : 0.0,
+                          y : 0.0}, |acc, vector| acc + vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Sum <&'a Vec2f> for Vec2f {
+
+    // Sum of an iterator of vector references (component-wise)
+    fn sum<I> (iter : I) -> Vec2f where I : Iterator <Item = &'a Vec2f> {
+
+        iter.cloned ().sum ()
+    }
+}
+
+/*================================================================================================*/
+
+impl Product for Vec2f {
+
+    // Product of an iterator of vectors (component-wise)
+    fn product<I> (iter : I) -> Vec2f where I : Iterator <Item = Vec2f> {
+
+        iter.fold (Vec2f {x : 1.0,
+                          y : 1.0}, |acc, vector| acc * vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Product <&'a Vec2f> for Vec2f {
+
+    // Product of an iterator of vector references (component-wise)
+    fn product<I> (iter : I) -> Vec2f where I : Iterator <Item = &'a Vec2f> {
+
+        iter.cloned ().product ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Vec2f {
+
+    // Formats the vector as "x, y", honouring any requested precision
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match f.precision () {
+
+            Some (precision) => write! (f, "{:.*}, {:.*}", precision, self.x, precision, self.y),
+            None             => write! (f, "{}, {}", self.x, self.y)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl FromStr for Vec2f {
+
+    type Err = ParseError;
+
+    // Parses a vector from the "x, y" format produced by Display
+    fn from_str (string : &str) -> Result <Vec2f, ParseError> {
+
+        let components : [f32; 2] = parse_components (string)?;
+
+        Ok (Vec2f {x : components[0],
+                   y : components[1]})
+    }
+}
+
+/*================================================================================================*/
+
+impl PartialEq for Vec2fKey {
+
+    // Equal to operator (bit-wise)
+    fn eq (&self, rhs : &Vec2fKey) -> bool {
+
+        canonical_bits (self.0.x) == canonical_bits (rhs.0.x) &&
+        canonical_bits (self.0.y) == canonical_bits (rhs.0.y)
+    }
+}
+
+/*================================================================================================*/
+
+impl Eq for Vec2fKey {}
+
+/*================================================================================================*/
+
+impl Hash for Vec2fKey {
+
+    // Hashes the bit patterns of the components
+    fn hash<H : Hasher> (&self, state : &mut H) {
+
+        canonical_bits (self.0.x).hash (state);
+        canonical_bits (self.0.y).hash (state);
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec2f> for Vec2fKey {
+
+    // Wraps a vector as a hash key
+    fn from (vector : Vec2f) -> Vec2fKey {
+
+        Vec2fKey (vector)
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl IntoIterator for Vec2f {
+
+    type Item     = f32;
+    type IntoIter = IntoIter <f32, 2>;
+
+    // Consuming iterator over the components
+    fn into_iter (self) -> IntoIter <f32, 2> {
+
+        self.iter ()
+    }
+}
+
+/*================================================================================================*/
+
+impl From <[f32; 2]> for Vec2f {
+
+    // Conversion from an array
+    fn from (array : [f32; 2]) -> Vec2f {
+
+        Vec2f {x : array[0],
+               y : array[1]}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec2f> for [f32; 2] {
+
+    // Conversion to an array
+    fn from (vector : Vec2f) -> [f32; 2] {
+
+        [vector.x, vector.y]
+    }
+}
+
+/*================================================================================================*/
+
+impl From <(f32, f32)> for Vec2f {
+
+    // Conversion from a tuple
+    fn from (tuple : (f32, f32)) -> Vec2f {
+
+        Vec2f {x : tuple.0,
+               y : tuple.1}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec2f> for (f32, f32) {
+
+    // Conversion to a tuple
+    fn from (vector : Vec2f) -> (f32, f32) {
+
+        (vector.x, vector.y)
+    }
+}
@@ -15,9 +15,16 @@
 /*================================================================================================*/
 
 use Mathf;
+use error::{parse_components, ParseError};
+use mathf::canonical_bits;
 
+use std::array::IntoIter;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
 use std::ops::*;
 use std::cmp::PartialEq;
+use std::str::FromStr;
 
 /*================================================================================================*/
 /*------STRUCTS-----------------------------------------------------------------------------------*/
@@ -28,7 +35,10 @@ use std::cmp::PartialEq;
 /// This is the most used out of the three vector structs.
 /// It is used for representing positions in 3D space (vertices, normals, positions, etc).
 /// 32-bit floats are used for the values.
-#[derive (Copy, Clone, Default)]
+///
+/// Components are indexed with `usize` (from `vec[0]` for x to `vec[2]` for z), which panics
+/// when the index is out of range, while `get` returns `None` instead.
+#[derive (Copy, Clone, Debug, Default)]
 pub struct Vec3f {
 
     // Public
@@ -40,24 +50,49 @@ pub struct Vec3f {
     pub z : f32
 }
 
+/*================================================================================================*/
+
+/// A hashable wrapper around Vec3f
+///
+/// It compares and hashes the bit patterns of the components, allowing vectors to be used as
+/// keys in hash maps and sets. Positive and negative zero are treated as equal, while other
+/// values (including NaN) are only equal if their bits match exactly.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct Vec3fKey (pub Vec3f);
+
 /*================================================================================================*/
 /*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
 /*================================================================================================*/
 
 impl Vec3f {
 
-    /// Formats the vector as a string.
+    /// Returns the component at the given index, or `None` if the index is out of range.
     ///
     /// # Examples
     /// ```
-    /// let vec = Vec3f {x : 10.0, y : 5.0, z : 1.0};
-    /// println! ("Vector = {}", vec.to_string ());
+    /// # use trivert_math::Vec3f;
+    /// let vec = Vec3f::new ();
+    ///
+    /// assert_eq! (vec.get (0), Some (0.0));
+    /// assert_eq! (vec.get (3), None);
     /// ```
-    /// ```c
-    /// Output : Vector = 10.0, 5.0, 1.0
-    pub fn to_string (&self) -> String {
+    pub fn get (&self, index : usize) -> Option <f32> {
+
+        match index {
 
-        format! ("{}, {}, {}", self.x, self.y, self.z)
+            0 => Some (self.x),
+            1 => Some (self.y),
+            2 => Some (self.z),
+            _ => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns an iterator over the components of the vector.
+    pub fn iter (&self) -> IntoIter <f32, 3> {
+
+        IntoIterator::into_iter ([self.x, self.y, self.z])
     }
 
 /*================================================================================================*/
@@ -141,9 +176,9 @@ impl Vec3f {
     /// Retuns the cross product of two vectors.
     pub fn cross (lhs : &Vec3f, rhs : &Vec3f) -> Vec3f {
 
-        Vec3f {x : lhs.y * rhs.z - rhs.y * lhs.z,
-               y : lhs.z * rhs.x - rhs.x * lhs.x,
-               z : lhs.x * rhs.y - rhs.x * lhs.y}
+        Vec3f {x : lhs.y * rhs.z - lhs.z * rhs.y,
+               y : lhs.z * rhs.x - lhs.x * rhs.z,
+               z : lhs.x * rhs.y - lhs.y * rhs.x}
     }
 
 /*================================================================================================*/
@@ -197,19 +232,231 @@ impl Vec3f {
 /*================================================================================================*/
 
     /// Get a normalized vector.
+    ///
+    /// Returns a zero vector if the vector cannot be normalized.
     pub fn normalize (vector : &Vec3f) -> Vec3f {
 
+        Vec3f::normalize_or_zero (vector)
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or `None` if the vector cannot be normalized.
+    ///
+    /// Normalizing fails if the vector has no length, or if the result would not be finite
+    /// (e.g. the vector contains NaN or infinite components).
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Vec3f;
+    /// assert! (Vec3f::try_normalize (&Vec3f::new ()).is_none ());
+    /// ```
+    pub fn try_normalize (vector : &Vec3f) -> Option <Vec3f> {
+
         let length = Vec3f::length (vector);
 
-        if length != 0.0 {
+        if length != 0.0 && length.is_finite () {
+
+            return Some (Vec3f {x : vector.x / length,
+                                y : vector.y / length,
+                                z : vector.z / length})
+        }
+
+        None
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or a zero vector if the vector cannot be normalized.
+    pub fn normalize_or_zero (vector : &Vec3f) -> Vec3f {
+
+        Vec3f::try_normalize (vector).unwrap_or_default ()
+    }
+
+/*================================================================================================*/
+
+    /// Reflects a direction off the surface defined by a normal.
+    ///
+    /// The normal is expected to be normalized.
+    pub fn reflect (direction : &Vec3f, normal : &Vec3f) -> Vec3f {
+
+        *direction - *normal * (2.0 * Vec3f::dot (direction, normal))
+    }
+
+/*================================================================================================*/
+
+    /// Refracts a direction through the surface defined by a normal.
+    ///
+    /// Both the direction and normal are expected to be normalized, and `eta` is the ratio of
+    /// the indices of refraction. Returns a zero vector on total internal reflection.
+    pub fn refract (direction : &Vec3f, normal : &Vec3f, eta : f32) -> Vec3f {
+
+        let dot = Vec3f::dot (direction, normal);
+        let k   = 1.0 - eta * eta * (1.0 - dot * dot);
+
+        if k < 0.0 {
+            return Vec3f::new ();
+        }
+
+        *direction * eta - *normal * (eta * dot + k.sqrt ())
+    }
+
+/*================================================================================================*/
+
+    /// Projects a vector onto another vector.
+    ///
+    /// Returns a zero vector if the vector being projected onto has no length.
+    pub fn project (vector : &Vec3f, onto : &Vec3f) -> Vec3f {
 
-            return Vec3f {x : vector.x / length,
-                          y : vector.y / length,
-                          z : vector.z / length}
+        let sqr_length = Vec3f::dot (onto, onto);
+
+        if sqr_length != 0.0 {
+            return *onto * (Vec3f::dot (vector, onto) / sqr_length);
         }
 
         Vec3f::new ()
     }
+
+/*================================================================================================*/
+
+    /// Returns the component of a vector perpendicular to another vector.
+    pub fn reject (vector : &Vec3f, onto : &Vec3f) -> Vec3f {
+
+        *vector - Vec3f::project (vector, onto)
+    }
+
+/*================================================================================================*/
+
+    /// Projects a vector onto the plane defined by a normal.
+    ///
+    /// The plane passes through the origin.
+    pub fn project_on_plane (vector : &Vec3f, plane_normal : &Vec3f) -> Vec3f {
+
+        Vec3f::reject (vector, plane_normal)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the component of a vector perpendicular to the plane defined by a normal.
+    pub fn reject_from_plane (vector : &Vec3f, plane_normal : &Vec3f) -> Vec3f {
+
+        Vec3f::project (vector, plane_normal)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the unsigned angle between two vectors in radians.
+    ///
+    /// The result is in the range [0, PI]. Returns zero if either vector has no length.
+    pub fn angle (from : &Vec3f, to : &Vec3f) -> f32 {
+
+        let lengths = Vec3f::length (from) * Vec3f::length (to);
+
+        if lengths != 0.0 {
+            return Mathf::clamp (Vec3f::dot (from, to) / lengths, -1.0, 1.0).acos ();
+        }
+
+        0.0
+    }
+
+/*================================================================================================*/
+
+    /// Returns the signed angle between two vectors in radians.
+    ///
+    /// The result is in the range [-PI, PI]. The sign is positive when the rotation from `from`
+    /// to `to` is counter-clockwise when looking down the axis towards the origin.
+    pub fn signed_angle (from : &Vec3f, to : &Vec3f, axis : &Vec3f) -> f32 {
+
+        let angle = Vec3f::angle (from, to);
+
+        if Vec3f::dot (axis, &Vec3f::cross (from, to)) < 0.0 {
+            return -angle;
+        }
+
+        angle
+    }
+
+/*================================================================================================*/
+
+    /// Returns a copy of the vector with its length clamped to a maximum.
+    pub fn clamp_magnitude (vector : &Vec3f, max_length : f32) -> Vec3f {
+
+        let length = Vec3f::length (vector);
+
+        if length > max_length && length != 0.0 {
+            return *vector * (max_length / length);
+        }
+
+        *vector
+    }
+
+/*================================================================================================*/
+
+    /// Builds an orthonormal basis from a normal.
+    ///
+    /// The normal is expected to be normalized. Returns two vectors which, along with the
+    /// normal, are all perpendicular to each other and of unit length.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Vec3f;
+    /// let (tangent, bitangent) = Vec3f::orthonormal_basis (&Vec3f::up ());
+    /// ```
+    pub fn orthonormal_basis (normal : &Vec3f) -> (Vec3f, Vec3f) {
+
+        // Branchless construction from Duff et al. "Building an Orthonormal Basis, Revisited"
+        let sign = 1.0f32.copysign (normal.z);
+        let a    = -1.0 / (sign + normal.z);
+        let b    = normal.x * normal.y * a;
+
+        (Vec3f {x : 1.0 + sign * normal.x * normal.x * a,
+                y : sign * b,
+                z : -sign * normal.x},
+         Vec3f {x : b,
+                y : sign + normal.y * normal.y * a,
+                z : -normal.y})
+    }
+
+/*================================================================================================*/
+
+    /// Spherically interpolates between two directions.
+    ///
+    /// The angle and the length are interpolated separately, so the result travels along an arc
+    /// rather than a straight line. The percentage is clamped between 0 and 1.
+    pub fn slerp (start : &Vec3f, end : &Vec3f, percentage : f32) -> Vec3f {
+
+        let percentage   = Mathf::clamp (percentage, 0.0, 1.0);
+        let start_length = Vec3f::length (start);
+        let end_length   = Vec3f::length (end);
+
+        // Fall back to linear interpolation for zero length vectors
+        if start_length == 0.0 || end_length == 0.0 {
+            return Vec3f::lerp (start, end, percentage);
+        }
+
+        let from = *start / start_length;
+        let to   = *end / end_length;
+        let mut axis = Vec3f::cross (&from, &to);
+
+        // Parallel or opposite directions have no defined rotation axis, so pick any
+        // axis perpendicular to the start direction
+        if Vec3f::dot (&axis, &axis) < 1.0e-12 {
+
+            if Vec3f::dot (&from, &to) > 0.0 {
+                return Vec3f::lerp (start, end, percentage);
+            }
+
+            axis = Vec3f::orthonormal_basis (&from).0;
+        }
+
+        let axis   = Vec3f::normalize (&axis);
+        let angle  = Vec3f::angle (&from, &to) * percentage;
+        let length = Mathf::lerp (start_length, end_length, percentage);
+        let (sin, cos) = angle.sin_cos ();
+
+        // Rodrigues' rotation (the axis is perpendicular to the start direction)
+        (from * cos + Vec3f::cross (&axis, &from) * sin) * length
+    }
 }
 
 /*================================================================================================*/
@@ -478,36 +725,286 @@ impl PartialEq for Vec3f {
 
 /*================================================================================================*/
 
-impl Index <u8> for Vec3f {
+impl Index <usize> for Vec3f {
 
     type Output = f32;
 
     // Index operator (immutable)
-    fn index (&self, index : u8) -> &f32 {
+    fn index (&self, index : usize) -> &f32 {
 
         match index {
 
             0 => &self.x,
             1 => &self.y,
             2 => &self.z,
-            _ => unreachable! ("Index out of range for Vec3f")
+            _ => panic! ("Index out of range for Vec3f: {}", index)
         }
     }
 }
 
 /*================================================================================================*/
 
-impl IndexMut <u8> for Vec3f {
+impl IndexMut <usize> for Vec3f {
 
     // Index operator (mutable)
-    fn index_mut (&mut self, index : u8) -> &mut f32 {
+    fn index_mut (&mut self, index : usize) -> &mut f32 {
 
         match index {
 
             0 => &mut self.x,
             1 => &mut self.y,
             2 => &mut self.z,
-            _ => unreachable! ("Index out of range for Vec3f")
+            _ => panic! ("Index out of range for Vec3f: {}", index)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Add <Vec3f> for f32 {
+
+    type Output = Vec3f;
+
+    // Addition operator (f32 on the left)
+    fn add (self, rhs : Vec3f) -> Vec3f {
+
+        Vec3f {x : self + rhs.x,
+               y : self + rhs.y,
+               z : self + rhs.z}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sub <Vec3f> for f32 {
+
+    type Output = Vec3f;
+
+    // Subtraction operator (f32 on the left)
+    fn sub (self, rhs : Vec3f) -> Vec3f {
+
+        Vec3f {x : self - rhs.x,
+               y : self - rhs.y,
+               z : self - rhs.z}
+    }
+}
+
+/*================================================================================================*/
+
+impl Mul <Vec3f> for f32 {
+
+    type Output = Vec3f;
+
+    // Multiplication operator (f32 on the left)
+    fn mul (self, rhs : Vec3f) -> Vec3f {
+
+        Vec3f {x : self * rhs.x,
+               y : self * rhs.y,
+               z : self * rhs.z}
+    }
+}
+
+/*================================================================================================*/
+
+impl Div <Vec3f> for f32 {
+
+    type Output = Vec3f;
+
+    // Division operator (f32 on the left)
+    fn div (self, rhs : Vec3f) -> Vec3f {
+
+        Vec3f {x : self / rhs.x,
+               y : self / rhs.y,
+               z : self / rhs.z}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sum for Vec3f {
+
+    // Sum of an iterator of vectors (component-wise)
+    fn sum<I> (iter : I) -> Vec3f where I : Iterator <Item = Vec3f> {
+
+        iter.fold (Vec3f {x : 0.0,
+                          y : 0.0,
+                          z : 0.0}, |acc, vector| acc + vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Sum <&'a Vec3f> for Vec3f {
+
+    // Sum of an iterator of vector references (component-wise)
+    fn sum<I> (iter : I) -> Vec3f where I : Iterator <Item = &'a Vec3f> {
+
+        iter.cloned ().sum ()
+    }
+}
+
+/*================================================================================================*/
+
+impl Product for Vec3f {
+
+    // Product of an iterator of vectors (component-wise)
+    fn product<I> (iter : I) -> Vec3f where I : Iterator <Item = Vec3f> {
+
+        iter.fold (Vec3f {x : 1.0,
+                          y : 1.0,
+                          z : 1.0}, |acc, vector| acc * vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Product <&'a Vec3f> for Vec3f {
+
+    // Product of an iterator of vector references (component-wise)
+    fn product<I> (iter : I) -> Vec3f where I : Iterator <Item = &'a Vec3f> {
+
+        iter.cloned ().product ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Vec3f {
+
+    // Formats the vector as "x, y, z", honouring any requested precision
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match f.precision () {
+
+            Some (precision) => write! (f, "{:.*}, {:.*}, {:.*}",
+                                       precision, self.x,
+                                       precision, self.y,
+                                       precision, self.z),
+            None             => write! (f, "{}, {}, {}", self.x, self.y, self.z)
         }
     }
 }
+
+/*================================================================================================*/
+
+impl FromStr for Vec3f {
+
+    type Err = ParseError;
+
+    // Parses a vector from the "x, y, z" format produced by Display
+    fn from_str (string : &str) -> Result <Vec3f, ParseError> {
+
+        let components : [f32; 3] = parse_components (string)?;
+
+        Ok (Vec3f {x : components[0],
+                   y : components[1],
+                   z : components[2]})
+    }
+}
+
+/*================================================================================================*/
+
+impl PartialEq for Vec3fKey {
+
+    // Equal to operator (bit-wise)
+    fn eq (&self, rhs : &Vec3fKey) -> bool {
+
+        canonical_bits (self.0.x) == canonical_bits (rhs.0.x) &&
+        canonical_bits (self.0.y) == canonical_bits (rhs.0.y) &&
+        canonical_bits (self.0.z) == canonical_bits (rhs.0.z)
+    }
+}
+
+/*================================================================================================*/
+
+impl Eq for Vec3fKey {}
+
+/*================================================================================================*/
+
+impl Hash for Vec3fKey {
+
+    // Hashes the bit patterns of the components
+    fn hash<H : Hasher> (&self, state : &mut H) {
+
+        canonical_bits (self.0.x).hash (state);
+        canonical_bits (self.0.y).hash (state);
+        canonical_bits (self.0.z).hash (state);
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec3f> for Vec3fKey {
+
+    // Wraps a vector as a hash key
+    fn from (vector : Vec3f) -> Vec3fKey {
+
+        Vec3fKey (vector)
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl IntoIterator for Vec3f {
+
+    type Item     = f32;
+    type IntoIter = IntoIter <f32, 3>;
+
+    // Consuming iterator over the components
+    fn into_iter (self) -> IntoIter <f32, 3> {
+
+        self.iter ()
+    }
+}
+
+/*================================================================================================*/
+
+impl From <[f32; 3]> for Vec3f {
+
+    // Conversion from an array
+    fn from (array : [f32; 3]) -> Vec3f {
+
+        Vec3f {x : array[0],
+               y : array[1],
+               z : array[2]}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec3f> for [f32; 3] {
+
+    // Conversion to an array
+    fn from (vector : Vec3f) -> [f32; 3] {
+
+        [vector.x, vector.y, vector.z]
+    }
+}
+
+/*================================================================================================*/
+
+impl From <(f32, f32, f32)> for Vec3f {
+
+    // Conversion from a tuple
+    fn from (tuple : (f32, f32, f32)) -> Vec3f {
+
+        Vec3f {x : tuple.0,
+               y : tuple.1,
+               z : tuple.2}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec3f> for (f32, f32, f32) {
+
+    // Conversion to a tuple
+    fn from (vector : Vec3f) -> (f32, f32, f32) {
+
+        (vector.x, vector.y, vector.z)
+    }
+}
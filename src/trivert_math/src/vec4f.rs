@@ -15,9 +15,16 @@
 /*================================================================================================*/
 
 use Mathf;
+use error::{parse_components, ParseError};
+use mathf::canonical_bits;
 
+use std::array::IntoIter;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
 use std::ops::*;
 use std::cmp::PartialEq;
+use std::str::FromStr;
 
 /*================================================================================================*/
 /*------STRUCTS-----------------------------------------------------------------------------------*/
@@ -27,7 +34,10 @@ use std::cmp::PartialEq;
 ///
 /// It is used for things such as mesh tangets, and shader parameters.
 /// 32-bit floats are used for the values.
-#[derive (Copy, Clone, Default)]
+///
+/// Components are indexed with `usize` (from `vec[0]` for x to `vec[3]` for w), which panics
+/// when the index is out of range, while `get` returns `None` instead.
+#[derive (Copy, Clone, Debug, Default)]
 pub struct Vec4f {
 
     // Public
@@ -41,24 +51,50 @@ pub struct Vec4f {
     pub w : f32
 }
 
+/*================================================================================================*/
+
+/// A hashable wrapper around Vec4f
+///
+/// It compares and hashes the bit patterns of the components, allowing vectors to be used as
+/// keys in hash maps and sets. Positive and negative zero are treated as equal, while other
+/// values (including NaN) are only equal if their bits match exactly.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct Vec4fKey (pub Vec4f);
+
 /*================================================================================================*/
 /*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
 /*================================================================================================*/
 
 impl Vec4f {
 
-    /// Formats the vector as a string.
+    /// Returns the component at the given index, or `None` if the index is out of range.
     ///
     /// # Examples
     /// ```
-    /// let vec = Vec4f {x : 10.0, y : 5.0, z : 1.0, w : 0.0};
-    /// println! ("Vector = {}", vec.to_string ());
+    /// # use trivert_math::Vec4f;
+    /// let vec = Vec4f::new ();
+    ///
+    /// assert_eq! (vec.get (0), Some (0.0));
+    /// assert_eq! (vec.get (4), None);
     /// ```
-    /// ```c
-    /// Output : Vector = 10.0, 5.0, 1.0, 0.0
-    pub fn to_string (&self) -> String {
+    pub fn get (&self, index : usize) -> Option <f32> {
+
+        match index {
 
-        format! ("{}, {}, {}, {}", self.x, self.y, self.z, self.w)
+            0 => Some (self.x),
+            1 => Some (self.y),
+            2 => Some (self.z),
+            3 => Some (self.w),
+            _ => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns an iterator over the components of the vector.
+    pub fn iter (&self) -> IntoIter <f32, 4> {
+
+        IntoIterator::into_iter ([self.x, self.y, self.z, self.w])
     }
 
 /*================================================================================================*/
@@ -133,19 +169,46 @@ impl Vec4f {
 /*================================================================================================*/
 
     /// Get a normalized vector.
+    ///
+    /// Returns a zero vector if the vector cannot be normalized.
     pub fn normalize (vector : &Vec4f) -> Vec4f {
 
+        Vec4f::normalize_or_zero (vector)
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or `None` if the vector cannot be normalized.
+    ///
+    /// Normalizing fails if the vector has no length, or if the result would not be finite
+    /// (e.g. the vector contains NaN or infinite components).
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_math::Vec4f;
+    /// assert! (Vec4f::try_normalize (&Vec4f::new ()).is_none ());
+    /// ```
+    pub fn try_normalize (vector : &Vec4f) -> Option <Vec4f> {
+
         let length = Vec4f::length (vector);
 
-        if length != 0.0 {
+        if length != 0.0 && length.is_finite () {
 
-            return Vec4f {x : vector.x / length,
-                          y : vector.y / length,
-                          z : vector.z / length,
-                          w : vector.w / length}
+            return Some (Vec4f {x : vector.x / length,
+                                y : vector.y / length,
+                                z : vector.z / length,
+                                w : vector.w / length})
         }
 
-        Vec4f::new ()
+        None
+    }
+
+/*================================================================================================*/
+
+    /// Get a normalized vector, or a zero vector if the vector cannot be normalized.
+    pub fn normalize_or_zero (vector : &Vec4f) -> Vec4f {
+
+        Vec4f::try_normalize (vector).unwrap_or_default ()
     }
 }
 
@@ -433,12 +496,12 @@ impl PartialEq for Vec4f {
 
 /*================================================================================================*/
 
-impl Index <u8> for Vec4f {
+impl Index <usize> for Vec4f {
 
     type Output = f32;
 
     // Index operator (immutable)
-    fn index (&self, index : u8) -> &f32 {
+    fn index (&self, index : usize) -> &f32 {
 
         match index {
 
@@ -446,17 +509,17 @@ impl Index <u8> for Vec4f {
             1 => &self.y,
             2 => &self.z,
             3 => &self.w,
-            _ => unreachable! ("Index out of range for Vec4f")
+            _ => panic! ("Index out of range for Vec4f: {}", index)
         }
     }
 }
 
 /*================================================================================================*/
 
-impl IndexMut <u8> for Vec4f {
+impl IndexMut <usize> for Vec4f {
 
     // Index operator (mutable)
-    fn index_mut (&mut self, index : u8) -> &mut f32 {
+    fn index_mut (&mut self, index : usize) -> &mut f32 {
 
         match index {
 
@@ -464,7 +527,269 @@ impl IndexMut <u8> for Vec4f {
             1 => &mut self.y,
             2 => &mut self.z,
             3 => &mut self.w,
-            _ => unreachable! ("Index out of range for Vec4f")
+            _ => panic! ("Index out of range for Vec4f: {}", index)
         }
     }
 }
+
+/*================================================================================================*/
+
+impl Add <Vec4f> for f32 {
+
+    type Output = Vec4f;
+
+    // Addition operator (f32 on the left)
+    fn add (self, rhs : Vec4f) -> Vec4f {
+
+        Vec4f {x : self + rhs.x,
+               y : self + rhs.y,
+               z : self + rhs.z,
+               w : self + rhs.w}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sub <Vec4f> for f32 {
+
+    type Output = Vec4f;
+
+    // Subtraction operator (f32 on the left)
+    fn sub (self, rhs : Vec4f) -> Vec4f {
+
+        Vec4f {x : self - rhs.x,
+               y : self - rhs.y,
+               z : self - rhs.z,
+               w : self - rhs.w}
+    }
+}
+
+/*================================================================================================*/
+
+impl Mul <Vec4f> for f32 {
+
+    type Output = Vec4f;
+
+    // Multiplication operator (f32 on the left)
+    fn mul (self, rhs : Vec4f) -> Vec4f {
+
+        Vec4f {x : self * rhs.x,
+               y : self * rhs.y,
+               z : self * rhs.z,
+               w : self * rhs.w}
+    }
+}
+
+/*================================================================================================*/
+
+impl Div <Vec4f> for f32 {
+
+    type Output = Vec4f;
+
+    // Division operator (f32 on the left)
+    fn div (self, rhs : Vec4f) -> Vec4f {
+
+        Vec4f {x : self / rhs.x,
+               y : self / rhs.y,
+               z : self / rhs.z,
+               w : self / rhs.w}
+    }
+}
+
+/*================================================================================================*/
+
+impl Sum for Vec4f {
+
+    // Sum of an iterator of vectors (component-wise)
+    fn sum<I> (iter : I) -> Vec4f where I : Iterator <Item = Vec4f> {
+
+        iter.fold (Vec4f {x : 0.0,
+                          y : 0.0,
+                          z : 0.0,
+                          w : 0.0}, |acc, vector| acc + vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Sum <&'a Vec4f> for Vec4f {
+
+    // Sum of an iterator of vector references (component-wise)
+    fn sum<I> (iter : I) -> Vec4f where I : Iterator <Item = &'a Vec4f> {
+
+        iter.cloned ().sum ()
+    }
+}
+
+/*================================================================================================*/
+
+impl Product for Vec4f {
+
+    // Product of an iterator of vectors (component-wise)
+    fn product<I> (iter : I) -> Vec4f where I : Iterator <Item = Vec4f> {
+
+        iter.fold (Vec4f {x : 1.0,
+                          y : 1.0,
+                          z : 1.0,
+                          w : 1.0}, |acc, vector| acc * vector)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Product <&'a Vec4f> for Vec4f {
+
+    // Product of an iterator of vector references (component-wise)
+    fn product<I> (iter : I) -> Vec4f where I : Iterator <Item = &'a Vec4f> {
+
+        iter.cloned ().product ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Vec4f {
+
+    // Formats the vector as "x, y, z, w", honouring any requested precision
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match f.precision () {
+
+            Some (precision) => write! (f, "{:.*}, {:.*}, {:.*}, {:.*}",
+                                       precision, self.x,
+                                       precision, self.y,
+                                       precision, self.z,
+                                       precision, self.w),
+            None             => write! (f, "{}, {}, {}, {}", self.x, self.y, self.z, self.w)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl FromStr for Vec4f {
+
+    type Err = ParseError;
+
+    // Parses a vector from the "x, y, z, w" format produced by Display
+    fn from_str (string : &str) -> Result <Vec4f, ParseError> {
+
+        let components : [f32; 4] = parse_components (string)?;
+
+        Ok (Vec4f {x : components[0],
+                   y : components[1],
+                   z : components[2],
+                   w : components[3]})
+    }
+}
+
+/*================================================================================================*/
+
+impl PartialEq for Vec4fKey {
+
+    // Equal to operator (bit-wise)
+    fn eq (&self, rhs : &Vec4fKey) -> bool {
+
+        canonical_bits (self.0.x) == canonical_bits (rhs.0.x) &&
+        canonical_bits (self.0.y) == canonical_bits (rhs.0.y) &&
+        canonical_bits (self.0.z) == canonical_bits (rhs.0.z) &&
+        canonical_bits (self.0.w) == canonical_bits (rhs.0.w)
+    }
+}
+
+/*================================================================================================*/
+
+impl Eq for Vec4fKey {}
+
+/*================================================================================================*/
+
+impl Hash for Vec4fKey {
+
+    // Hashes the bit patterns of the components
+    fn hash<H : Hasher> (&self, state : &mut H) {
+
+        canonical_bits (self.0.x).hash (state);
+        canonical_bits (self.0.y).hash (state);
+        canonical_bits (self.0.z).hash (state);
+        canonical_bits (self.0.w).hash (state);
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec4f> for Vec4fKey {
+
+    // Wraps a vector as a hash key
+    fn from (vector : Vec4f) -> Vec4fKey {
+
+        Vec4fKey (vector)
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl IntoIterator for Vec4f {
+
+    type Item     = f32;
+    type IntoIter = IntoIter <f32, 4>;
+
+    // Consuming iterator over the components
+    fn into_iter (self) -> IntoIter <f32, 4> {
+
+        self.iter ()
+    }
+}
+
+/*================================================================================================*/
+
+impl From <[f32; 4]> for Vec4f {
+
+    // Conversion from an array
+    fn from (array : [f32; 4]) -> Vec4f {
+
+        Vec4f {x : array[0],
+               y : array[1],
+               z : array[2],
+               w : array[3]}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec4f> for [f32; 4] {
+
+    // Conversion to an array
+    fn from (vector : Vec4f) -> [f32; 4] {
+
+        [vector.x, vector.y, vector.z, vector.w]
+    }
+}
+
+/*================================================================================================*/
+
+impl From <(f32, f32, f32, f32)> for Vec4f {
+
+    // Conversion from a tuple
+    fn from (tuple : (f32, f32, f32, f32)) -> Vec4f {
+
+        Vec4f {x : tuple.0,
+               y : tuple.1,
+               z : tuple.2,
+               w : tuple.3}
+    }
+}
+
+/*================================================================================================*/
+
+impl From <Vec4f> for (f32, f32, f32, f32) {
+
+    // Conversion to a tuple
+    fn from (vector : Vec4f) -> (f32, f32, f32, f32) {
+
+        (vector.x, vector.y, vector.z, vector.w)
+    }
+}
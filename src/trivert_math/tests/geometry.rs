@@ -0,0 +1,248 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate trivert_math;
+
+use trivert_math::{Geometry, Vec2f, Vec3f};
+
+/*================================================================================================*/
+/*------TESTS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+#[test]
+fn concave_polygons_are_triangulated_in_either_winding () {
+
+    let mut shape = points (&[(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 3.0),
+                              (3.0, 3.0), (3.0, 4.0), (0.0, 4.0)]);
+
+    let triangles = Geometry::triangulate (&shape);
+
+    assert_eq! (triangles.len (), shape.len () - 2);
+    check_triangulation (&shape, &[], &triangles, 9.0);
+
+    shape.reverse ();
+
+    let triangles = Geometry::triangulate (&shape);
+
+    assert_eq! (triangles.len (), shape.len () - 2);
+    check_triangulation (&shape, &[], &triangles, 9.0);
+
+    assert! (Geometry::triangulate (&shape[..2]).is_empty ());
+}
+
+/*================================================================================================*/
+
+#[test]
+fn collinear_and_duplicate_vertices_are_triangulated () {
+
+    // Every edge of the square has a vertex in the middle
+    let collinear = points (&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (2.0, 2.0),
+                              (1.0, 2.0), (0.0, 2.0), (0.0, 1.0)]);
+
+    check_triangulation (&collinear, &[], &Geometry::triangulate (&collinear), 4.0);
+
+    // Repeated vertices, including the first and last
+    let duplicates = points (&[(0.0, 0.0), (2.0, 0.0), (2.0, 0.0), (2.0, 2.0), (1.0, 3.0),
+                               (1.0, 3.0), (1.0, 3.0), (0.0, 2.0), (0.0, 0.0)]);
+
+    check_triangulation (&duplicates, &[], &Geometry::triangulate (&duplicates), 5.0);
+
+    // A concave polygon with a spike back along one of its edges
+    let spike = points (&[(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (2.0, 3.0), (2.0, 1.0),
+                          (2.0, 2.0), (0.0, 2.0)]);
+
+    check_triangulation (&spike, &[], &Geometry::triangulate (&spike), 7.0);
+
+    // A polygon with no area produces no triangles
+    let line = points (&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (1.0, 1.0)]);
+    assert! (Geometry::triangulate (&line).is_empty ());
+}
+
+/*================================================================================================*/
+
+#[test]
+fn polygons_with_holes_are_triangulated () {
+
+    let outer = points (&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+
+    // Holes in either winding, one spanning most of the height and one with collinear edges
+    let clockwise = points (&[(2.0, 2.0), (2.0, 4.0), (4.0, 4.0), (4.0, 2.0)]);
+    let tall      = points (&[(6.0, 0.5), (9.0, 0.5), (9.0, 9.5), (6.0, 9.5)]);
+    let collinear = points (&[(1.0, 6.0), (2.0, 6.0), (3.0, 6.0), (3.0, 8.0), (1.0, 8.0)]);
+
+    let cases : [(&[&[Vec2f]], f32); 4] = [
+        (&[&clockwise], 96.0),
+        (&[&clockwise, &tall], 69.0),
+        (&[&tall, &collinear, &clockwise], 65.0),
+        (&[&collinear, &clockwise], 92.0)
+    ];
+
+    for (holes, area) in cases.iter () {
+
+        let (vertices, triangles) = Geometry::triangulate_with_holes (&outer, holes);
+        let hole_vertices : usize = holes.iter ().map (|hole| hole.len ()).sum ();
+
+        assert_eq! (vertices.len (), outer.len () + hole_vertices);
+        check_triangulation (&vertices, holes, &triangles, *area);
+    }
+
+    // Degenerate holes are ignored
+    let (vertices, triangles) = Geometry::triangulate_with_holes (&outer, &[&clockwise[..2]]);
+
+    assert_eq! (vertices.len (), 6);
+    check_triangulation (&vertices, &[], &triangles, 100.0);
+}
+
+/*================================================================================================*/
+
+#[test]
+fn hull_3d_encloses_every_point () {
+
+    let mut cloud = Vec::new ();
+
+    // Cube corners, face centres, edge midpoints, interior points and duplicates
+    for &x in &[-1.0, 0.0, 1.0] {
+        for &y in &[-1.0, 0.0, 1.0] {
+            for &z in &[-1.0, 0.0, 1.0] {
+                cloud.push (Vec3f {x, y, z});
+                cloud.push (Vec3f {x : x * 0.5, y : y * 0.5, z : z * 0.5});
+            }
+        }
+    }
+
+    cloud.extend_from_slice (&cloud.clone ()[..10]);
+
+    let faces = Geometry::convex_hull_3d (&cloud);
+
+    check_hull (&cloud, &faces, 8.0);
+
+    // Only the corners can be vertices which aren't coplanar with their neighbours
+    for face in &faces {
+        for &index in face {
+            assert! (cloud[index].x.abs () == 1.0 || cloud[index].y.abs () == 1.0 ||
+                     cloud[index].z.abs () == 1.0);
+        }
+    }
+
+    // A flat base with a single apex gives a pyramid
+    let mut pyramid = points_3d (&[(0.0, 0.0, 0.0), (3.0, 0.0, 0.0), (3.0, 3.0, 0.0),
+                                   (0.0, 3.0, 0.0), (1.0, 1.0, 0.0), (2.0, 1.5, 0.0)]);
+
+    pyramid.push (Vec3f {x : 1.5, y : 1.5, z : 2.0});
+    check_hull (&pyramid, &Geometry::convex_hull_3d (&pyramid), 6.0);
+}
+
+/*================================================================================================*/
+
+#[test]
+fn hull_3d_of_degenerate_points_is_empty () {
+
+    let flat   = points_3d (&[(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0),
+                              (1.0, 1.0, 0.0), (0.5, 0.5, 0.0), (0.2, 0.7, 0.0)]);
+    let tilted = points_3d (&[(0.0, 0.0, 0.0), (1.0, 1.0, 2.0), (2.0, 0.0, 2.0),
+                              (3.0, 1.0, 4.0), (-1.0, 2.0, 1.0), (4.0, 4.0, 8.0)]);
+    let line   = points_3d (&[(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (2.0, 4.0, 6.0),
+                              (-1.0, -2.0, -3.0), (0.5, 1.0, 1.5)]);
+    let single = points_3d (&[(1.0, 2.0, 3.0); 5]);
+    let few    = points_3d (&[(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)]);
+
+    for cloud in &[flat, tilted, line, single, few, Vec::new ()] {
+        assert! (Geometry::convex_hull_3d (cloud).is_empty ());
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Builds 2D points from coordinate pairs
+fn points (coordinates : &[(f32, f32)]) -> Vec <Vec2f> {
+
+    coordinates.iter ().map (|&(x, y)| Vec2f {x, y}).collect ()
+}
+
+/*================================================================================================*/
+
+// Builds 3D points from coordinate triples
+fn points_3d (coordinates : &[(f32, f32, f32)]) -> Vec <Vec3f> {
+
+    coordinates.iter ().map (|&(x, y, z)| Vec3f {x, y, z}).collect ()
+}
+
+/*================================================================================================*/
+
+// Checks that triangles are counter-clockwise, cover the area and stay out of the holes
+fn check_triangulation (vertices  : &[Vec2f],
+                        holes     : &[&[Vec2f]],
+                        triangles : &[[usize; 3]],
+                        area      : f32) {
+
+    let mut total = 0.0;
+
+    for triangle in triangles {
+
+        let corners = [vertices[triangle[0]], vertices[triangle[1]], vertices[triangle[2]]];
+        let signed  = Geometry::polygon_area (&corners);
+
+        assert! (signed > 0.0, "triangle {:?} has area {}", corners, signed);
+
+        let centroid = Geometry::polygon_centroid (&corners);
+
+        for hole in holes {
+            assert! (!Geometry::point_in_polygon (&centroid, hole), "{:?} in a hole", corners);
+        }
+
+        total += signed;
+    }
+
+    assert! ((total - area).abs () < 1e-3, "area {} != {}", total, area);
+}
+
+/*================================================================================================*/
+
+// Checks that a hull is closed, faces outwards from every point and has the expected volume
+fn check_hull (points : &[Vec3f], faces : &[[usize; 3]], volume : f32) {
+
+    let mut edges = Vec::new ();
+
+    for face in faces {
+        edges.extend_from_slice (&[(face[0], face[1]), (face[1], face[2]), (face[2], face[0])]);
+    }
+
+    for &(a, b) in &edges {
+
+        assert_eq! (edges.iter ().filter (|&&edge| edge == (a, b)).count (), 1);
+        assert_eq! (edges.iter ().filter (|&&edge| edge == (b, a)).count (), 1);
+    }
+
+    let mut total = 0.0;
+
+    for face in faces {
+
+        let (a, b, c) = (points[face[0]], points[face[1]], points[face[2]]);
+        let normal    = Vec3f::cross (&(b - a), &(c - a));
+
+        assert! (Vec3f::length (&normal) > 0.0);
+
+        for point in points {
+            assert! (Vec3f::dot (&normal, &(*point - a)) <= 1e-4, "{:?} is outside", point);
+        }
+
+        total += Vec3f::dot (&a, &Vec3f::cross (&b, &c)) / 6.0;
+    }
+
+    assert! ((total - volume).abs () < 1e-3, "volume {} != {}", total, volume);
+}
@@ -0,0 +1,365 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate trivert_math;
+
+use trivert_math::{Aabb, BoundingVolume, Bvh, HashGrid, Quadtree, Rect, Vec2f, Vec3f};
+
+/*================================================================================================*/
+/*------TESTS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+#[test]
+fn bvh_items_are_moved_and_removed () {
+
+    let mut bvh = Bvh::new (1.0);
+
+    let a = bvh.insert (rect (0.0, 0.0, 2.0, 2.0), 'a');
+    let b = bvh.insert (rect (10.0, 0.0, 2.0, 2.0), 'b');
+    let c = bvh.insert (rect (20.0, 0.0, 2.0, 2.0), 'c');
+
+    assert_eq! (bvh.len (), 3);
+    assert_eq! (sorted (bvh.query (&rect (-1.0, -1.0, 12.0, 1.0))), vec! [a, b]);
+
+    // Moving within the margin keeps the fat bounds, moving further reinserts the item
+    assert! (!bvh.update (a, rect (0.5, 0.5, 2.0, 2.0)));
+    assert! (bvh.update (a, rect (30.0, 0.0, 2.0, 2.0)));
+    assert! (!bvh.update (99, rect (0.0, 0.0, 1.0, 1.0)));
+
+    assert_eq! (bvh.bounds (a), Some (rect (30.0, 0.0, 2.0, 2.0)));
+    assert_eq! (sorted (bvh.query (&rect (-1.0, -1.0, 12.0, 1.0))), vec! [b]);
+    assert_eq! (sorted (bvh.query (&rect (19.0, 0.0, 12.0, 1.0))), vec! [a, c]);
+
+    *bvh.get_mut (c).unwrap () = 'd';
+
+    assert_eq! (bvh.remove (b), Some ('b'));
+    assert_eq! (bvh.remove (b), None);
+    assert_eq! (bvh.get (b), None);
+    assert_eq! (bvh.get (c), Some (&'d'));
+    assert_eq! (bvh.len (), 2);
+    assert! (bvh.query (&rect (9.0, 0.0, 4.0, 4.0)).is_empty ());
+
+    bvh.clear ();
+
+    assert! (bvh.is_empty ());
+    assert_eq! (bvh.height (), 0);
+    assert! (bvh.query (&rect (0.0, 0.0, 100.0, 100.0)).is_empty ());
+}
+
+/*================================================================================================*/
+
+#[test]
+fn bvh_stays_balanced_and_matches_brute_force () {
+
+    let mut bvh   = Bvh::new (0.5);
+    let mut items = Vec::new ();
+
+    // Sorted inserts would build a linked list without rotations
+    for i in 0..1024 {
+
+        let bounds = rect (i as f32 * 4.0, 0.0, 2.0, 2.0);
+        items.push ((bvh.insert (bounds, i), bounds));
+    }
+
+    assert! (bvh.height () <= 24, "height {} is unbalanced", bvh.height ());
+
+    // Move and remove some items, which rotates the tree again
+    let mut random = Random (7);
+
+    for item in items.iter_mut ().step_by (3) {
+
+        item.1 = rect (random.next (4096.0), random.next (64.0), 1.0 + random.next (8.0), 1.0);
+        bvh.update (item.0, item.1);
+    }
+
+    for (handle, _) in items.iter ().filter (|item| item.0 % 5 == 0) {
+        assert! (bvh.remove (*handle).is_some ());
+    }
+
+    items.retain (|item| item.0 % 5 != 0);
+
+    assert_eq! (bvh.len (), items.len ());
+    assert! (bvh.height () <= 24, "height {} is unbalanced", bvh.height ());
+
+    for _ in 0..100 {
+
+        let region = rect (random.next (4096.0), random.next (64.0), random.next (256.0), 16.0);
+        assert_eq! (sorted (bvh.query (&region)), brute_force (&items, &region));
+    }
+}
+
+/*================================================================================================*/
+
+#[test]
+fn bvh_ray_casts_are_sorted_by_distance () {
+
+    let mut bvh = Bvh::new (0.0);
+    let half    = Vec3f {x : 1.0, y : 1.0, z : 1.0};
+
+    let far  = bvh.insert (Aabb::from_center (&Vec3f {x : 30.0, y : 0.0, z : 0.0}, &half), 3);
+    let near = bvh.insert (Aabb::from_center (&Vec3f {x : 10.0, y : 0.0, z : 0.0}, &half), 1);
+    let mid  = bvh.insert (Aabb::from_center (&Vec3f {x : 20.0, y : 0.5, z : 0.0}, &half), 2);
+    let off  = bvh.insert (Aabb::from_center (&Vec3f {x : 20.0, y : 5.0, z : 0.0}, &half), 4);
+
+    let origin = Vec3f::new ();
+    let right  = Vec3f::right ();
+
+    let hits = bvh.ray_cast (&origin, &right, 100.0);
+
+    assert_eq! (hits.iter ().map (|hit| hit.0).collect::<Vec <usize>> (), vec! [near, mid, far]);
+    assert! ((hits[0].1 - 9.0).abs () < 1e-5);
+    assert! ((hits[1].1 - 19.0).abs () < 1e-5);
+    assert! ((hits[2].1 - 29.0).abs () < 1e-5);
+
+    // Unnormalized directions measure distances in multiples of their length
+    let hits = bvh.ray_cast (&origin, &(right * 2.0), 100.0);
+    assert! ((hits[0].1 - 4.5).abs () < 1e-5);
+
+    assert_eq! (bvh.ray_cast (&origin, &right, 20.0).len (), 2);
+    assert_eq! (bvh.ray_cast_first (&origin, &right, 100.0).map (|hit| hit.0), Some (near));
+    assert_eq! (bvh.ray_cast_first (&origin, &right, 5.0), None);
+    assert! (bvh.ray_cast (&origin, &(right * -1.0), 100.0).is_empty ());
+
+    assert_eq! (bvh.remove (near), Some (1));
+    assert_eq! (bvh.ray_cast_first (&origin, &right, 100.0).map (|hit| hit.0), Some (mid));
+
+    let upward = Vec3f {x : 0.0, y : 1.0, z : 0.0};
+    let start  = Vec3f {x : 20.0, y : -10.0, z : 0.0};
+
+    let hits = bvh.ray_cast (&start, &upward, 100.0);
+    assert_eq! (hits.iter ().map (|hit| hit.0).collect::<Vec <usize>> (), vec! [mid, off]);
+}
+
+/*================================================================================================*/
+
+#[test]
+fn bvh_finds_the_nearest_item () {
+
+    let mut bvh : Bvh <Rect, u32> = Bvh::new (1.0);
+
+    assert_eq! (bvh.nearest (&Vec2f::new ()), None);
+
+    let mut random = Random (3);
+    let mut items  = Vec::new ();
+
+    for i in 0..200 {
+
+        let bounds = rect (random.next (1000.0), random.next (1000.0), 1.0, 1.0);
+        items.push ((bvh.insert (bounds, i), bounds));
+    }
+
+    for _ in 0..50 {
+
+        let point            = Vec2f {x : random.next (1200.0) - 100.0,
+                                      y : random.next (1200.0) - 100.0};
+        let (handle, dist_2) = bvh.nearest (&point).unwrap ();
+
+        let closest = items.iter ()
+                           .map (|item| item.1.point_distance_squared (&point))
+                           .fold (f32::INFINITY, f32::min);
+
+        assert_eq! (dist_2, closest);
+        assert_eq! (bvh.bounds (handle).unwrap ().point_distance_squared (&point), closest);
+    }
+
+    // Points inside an item are at distance zero
+    let inside = items[10].1.center ();
+    assert_eq! (bvh.nearest (&inside).map (|nearest| nearest.1), Some (0.0));
+}
+
+/*================================================================================================*/
+
+#[test]
+fn hash_grid_keeps_large_items_apart () {
+
+    let mut grid = HashGrid::new (1.0);
+
+    let small = grid.insert (rect (5.5, 5.5, 0.5, 0.5), "small");
+    let large = grid.insert (rect (0.0, 0.0, 100.0, 100.0), "large");
+
+    assert_eq! (grid.len (), 2);
+    assert_eq! (sorted (grid.query (&rect (5.0, 5.0, 1.0, 1.0))), vec! [small, large]);
+    assert_eq! (grid.query (&rect (80.0, 20.0, 1.0, 1.0)), vec! [large]);
+    assert_eq! (grid.query_point (&Vec2f {x : 99.0, y : 1.0}), vec! [large]);
+    assert! (grid.query (&rect (150.0, 150.0, 1.0, 1.0)).is_empty ());
+
+    let everything = Rect {position : Vec2f {x : -f32::MAX,     y : -f32::MAX},
+                           size     : Vec2f {x : f32::INFINITY, y : f32::INFINITY}};
+
+    assert_eq! (sorted (grid.query (&everything)), vec! [small, large]);
+
+    // Shrinking moves the item into the cells, growing moves it back to the large list
+    assert! (grid.update (large, rect (200.0, 200.0, 2.0, 2.0)));
+    assert! (grid.query (&rect (80.0, 20.0, 1.0, 1.0)).is_empty ());
+    assert_eq! (grid.query_point (&Vec2f {x : 201.0, y : 201.0}), vec! [large]);
+
+    assert! (grid.update (large, rect (-500.0, -500.0, 400.0, 1000.0)));
+    assert! (grid.query_point (&Vec2f {x : 201.0, y : 201.0}).is_empty ());
+    assert_eq! (grid.query_point (&Vec2f {x : -400.0, y : 400.0}), vec! [large]);
+    assert_eq! (sorted (grid.query (&everything)), vec! [small, large]);
+
+    assert_eq! (grid.remove (large), Some ("large"));
+    assert_eq! (grid.remove (large), None);
+    assert! (!grid.update (large, rect (0.0, 0.0, 1.0, 1.0)));
+    assert_eq! (grid.query (&everything), vec! [small]);
+    assert! (grid.query_point (&Vec2f {x : -400.0, y : 400.0}).is_empty ());
+
+    grid.clear ();
+    assert! (grid.is_empty ());
+}
+
+/*================================================================================================*/
+
+#[test]
+fn hash_grid_matches_brute_force () {
+
+    let mut grid   = HashGrid::new (16.0);
+    let mut random = Random (11);
+    let mut items  = Vec::new ();
+
+    for i in 0..300 {
+
+        // Every tenth item covers many cells
+        let size   = if i % 10 == 0 {random.next (1000.0)} else {random.next (20.0)};
+        let bounds = rect (random.next (2000.0) - 1000.0,
+                           random.next (2000.0) - 1000.0,
+                           size,
+                           size);
+
+        items.push ((grid.insert (bounds, i), bounds));
+    }
+
+    for item in items.iter_mut ().step_by (4) {
+
+        item.1 = rect (item.1.position.x + 50.0, item.1.position.y - 30.0, 5.0, 5.0);
+        assert! (grid.update (item.0, item.1));
+    }
+
+    for _ in 0..100 {
+
+        let region = rect (random.next (2000.0) - 1000.0,
+                           random.next (2000.0) - 1000.0,
+                           random.next (200.0),
+                           random.next (200.0));
+
+        assert_eq! (sorted (grid.query (&region)), brute_force (&items, &region));
+    }
+}
+
+/*================================================================================================*/
+
+#[test]
+fn loose_tree_matches_brute_force () {
+
+    let mut tree   = Quadtree::new (rect (0.0, 0.0, 1024.0, 1024.0), 6);
+    let mut random = Random (5);
+    let mut items  = Vec::new ();
+
+    // Some items lie partly or completely outside of the tree bounds
+    for i in 0..500 {
+
+        let size   = if i % 25 == 0 {random.next (400.0)} else {random.next (16.0)};
+        let bounds = rect (random.next (1200.0) - 100.0, random.next (1200.0) - 100.0, size, size);
+
+        items.push ((tree.insert (bounds, i), bounds));
+    }
+
+    assert_eq! (tree.len (), 500);
+
+    for item in items.iter_mut ().step_by (3) {
+
+        item.1 = rect (random.next (1024.0), random.next (1024.0), 2.0, 2.0);
+        assert! (tree.update (item.0, item.1));
+    }
+
+    for (handle, _) in items.iter ().filter (|item| item.0 % 7 == 0) {
+        assert! (tree.remove (*handle).is_some ());
+    }
+
+    items.retain (|item| item.0 % 7 != 0);
+
+    assert_eq! (tree.len (), items.len ());
+    assert! (tree.get (7).is_none ());
+    assert! (!tree.update (7, rect (0.0, 0.0, 1.0, 1.0)));
+
+    for _ in 0..100 {
+
+        let region = rect (random.next (1200.0) - 100.0,
+                           random.next (1200.0) - 100.0,
+                           random.next (300.0),
+                           random.next (300.0));
+
+        assert_eq! (sorted (tree.query (&region)), brute_force (&items, &region));
+    }
+
+    for &(handle, bounds) in &items {
+        assert_eq! (tree.bounds (handle), Some (bounds));
+    }
+
+    tree.clear ();
+
+    assert! (tree.is_empty ());
+    assert! (tree.query (&rect (0.0, 0.0, 1024.0, 1024.0)).is_empty ());
+}
+
+/*================================================================================================*/
+/*------TYPES-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// A small xorshift generator, so the tests are repeatable without extra dependencies
+struct Random (u32);
+
+/*================================================================================================*/
+
+impl Random {
+
+    // Returns a number between zero and the maximum
+    fn next (&mut self, max : f32) -> f32 {
+
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+
+        (self.0 % 1_000_000) as f32 / 1_000_000.0 * max
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns a rect from its position and size
+fn rect (x : f32, y : f32, width : f32, height : f32) -> Rect {
+
+    Rect {position : Vec2f {x, y}, size : Vec2f {x : width, y : height}}
+}
+
+/*================================================================================================*/
+
+// Sorts query results, since the structures return them in no particular order
+fn sorted (mut handles : Vec <usize>) -> Vec <usize> {
+
+    handles.sort ();
+    handles
+}
+
+/*================================================================================================*/
+
+// Returns the handles of every item overlapping a region, checking them one by one
+fn brute_force (items : &[(usize, Rect)], region : &Rect) -> Vec <usize> {
+
+    sorted (items.iter ().filter (|item| item.1.overlaps (region)).map (|item| item.0).collect ())
+}
@@ -0,0 +1,156 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use VfsMetadata;
+
+use std::collections::BTreeMap;
+use std::io;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The index of the files and directories in an archive, used by the archive VFS sources
+//
+// Each file holds the item needed to read it from the archive (such as its position).
+// Directories which only exist as part of a file's path are added automatically, and the
+// root always exists.
+pub struct ArchiveIndex<T> {
+
+    entries : BTreeMap <String, (Option <T>, VfsMetadata)>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<T> ArchiveIndex<T> {
+
+    // Creates a new empty index
+    pub fn new () -> ArchiveIndex<T> {
+
+        ArchiveIndex {entries : BTreeMap::new ()}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Adds a file, or a directory when the item is `None`, along with the directories it is in
+    //
+    // Fails without changing the index if the path is inside a file, or if it is already in
+    // the index (unless both are directories).
+    pub fn insert (&mut self,
+                   path     : String,
+                   item     : Option <T>,
+                   metadata : VfsMetadata) -> io::Result <()> {
+
+        let parents = path.match_indices ('/').map (|(end, _)| end).collect::<Vec <_>> ();
+
+        for &end in &parents {
+
+            if self.entries.get (&path[..end]).is_some_and (|&(_, parent)| !parent.is_dir) {
+                return Err (collision (format! ("\"{}\" is inside the file \"{}\"",
+                                                path,
+                                                &path[..end])));
+            }
+        }
+
+        if self.entries.get (&path).is_some_and (|&(_, entry)| !entry.is_dir || !metadata.is_dir) {
+            return Err (collision (format! ("\"{}\" is already in the archive", path)));
+        }
+
+        for end in parents {
+
+            self.entries.entry (path[..end].to_string ())
+                        .or_insert ((None, VfsMetadata::directory ()));
+        }
+
+        self.entries.insert (path, (item, metadata));
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Returns the item of a file, or `None` if it isn't a file in the index
+    pub fn file (&self, path : &str) -> Option <&T> {
+
+        match self.entries.get (path) {
+            Some (&(Some (ref item), metadata)) if !metadata.is_dir => Some (item),
+            _                                                       => None
+        }
+    }
+
+/*================================================================================================*/
+
+    // Returns the metadata of a file or directory
+    pub fn metadata (&self, path : &str) -> Option <VfsMetadata> {
+
+        if path.is_empty () {
+            return Some (VfsMetadata::directory ());
+        }
+
+        self.entries.get (path).map (|&(_, metadata)| metadata)
+    }
+
+/*================================================================================================*/
+
+    // Returns the entries directly inside a directory
+    pub fn list (&self, path : &str) -> io::Result <Vec <(String, VfsMetadata)>> {
+
+        if !self.metadata (path).is_some_and (|metadata| metadata.is_dir) {
+            return Err (io::Error::new (io::ErrorKind::NotFound, "directory not in archive"));
+        }
+
+        let prefix = if path.is_empty () {String::new ()} else {format! ("{}/", path)};
+
+        Ok (self.entries.range (prefix.clone ()..)
+                        .take_while (|&(name, _)| name.starts_with (&prefix))
+                        .filter_map (|(name, &(_, metadata))| {
+
+                            let name = &name[prefix.len ()..];
+
+                            if name.contains ('/') {
+                                return None;
+                            }
+
+                            Some ((name.to_string (), metadata))
+                        })
+                        .collect ())
+    }
+
+/*================================================================================================*/
+
+    // Returns an iterator over the paths, items and metadata of every file
+    pub fn files (&self) -> impl Iterator <Item = (&str, &T, VfsMetadata)> {
+
+        self.entries.iter ().filter_map (|(path, &(ref item, metadata))| {
+            item.as_ref ()
+                .filter (|_| !metadata.is_dir)
+                .map (|item| (path.as_str (), item, metadata))
+        })
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Creates an error for a path which collides with another in the index
+fn collision (message : String) -> io::Error {
+
+    io::Error::new (io::ErrorKind::AlreadyExists, message)
+}
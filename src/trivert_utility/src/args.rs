@@ -0,0 +1,382 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {ArgError, Config, ConfigLayer, LogFilter, Value};
+use config_document::parse_value;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The argument parser struct
+///
+/// It parses the command line arguments of a game. Options are registered as either flags
+/// (e.g. `--windowed`) or options which take a value (e.g. `--width 1280` or `--width=1280`),
+/// and can be bound to a config setting, so they override it when applied to a config.
+///
+/// A few arguments are always understood:
+///
+/// * `--help` (or `-h`), which asks for the help text to be shown
+/// * `--log <filter>`, which sets the log filter (e.g. `--log warn,renderer=debug`)
+/// * `+set <key> <value>`, which overrides any config setting, like a console command
+/// * `--`, after which every argument is positional
+///
+/// # Examples
+/// ```
+/// # use trivert_utility::{ArgParser, Config};
+/// let mut parser = ArgParser::new ("game", "A TriVert game");
+///
+/// parser.add_flag ("windowed", "Runs in a window instead of fullscreen");
+/// parser.add_option ("width", "pixels", "Sets the window width");
+/// parser.bind ("windowed", "video.windowed");
+/// parser.bind ("width", "video.width");
+///
+/// let args = parser.parse (vec! ["--windowed", "--width", "1280", "+set", "audio.volume", "0.5"])
+///                  .unwrap ();
+///
+/// let mut config = Config::new ();
+/// args.apply_to (&mut config);
+///
+/// assert_eq! (config.get_bool ("video.windowed", false), true);
+/// assert_eq! (config.get_int ("video.width", 800), 1280);
+/// assert_eq! (config.get_float ("audio.volume", 1.0), 0.5);
+/// ```
+pub struct ArgParser {
+
+    // Private
+    program : String,
+    about   : String,
+    options : Vec <ArgOption>
+}
+
+/*================================================================================================*/
+
+/// The args struct
+///
+/// It holds the command line arguments parsed by an argument parser.
+#[derive (Clone, Debug, Default, PartialEq)]
+pub struct Args {
+
+    // Private
+    flags      : BTreeSet <String>,
+    values     : BTreeMap <String, String>,
+    settings   : Vec <(String, Value)>,
+    positional : Vec <String>,
+    log_filter : Option <LogFilter>,
+    help       : bool
+}
+
+/*================================================================================================*/
+
+// An option understood by an argument parser
+struct ArgOption {
+
+    name       : String,
+    value_name : Option <String>,
+    help       : String,
+    config_key : Option <String>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl ArgParser {
+
+    /// Creates a new argument parser, given the program name and description used in the help
+    /// text.
+    pub fn new (program : &str, about : &str) -> ArgParser {
+
+        ArgParser {program : program.to_string (),
+                   about   : about.to_string (),
+                   options : Vec::new ()}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Adds a flag, which is given as `--name`.
+    pub fn add_flag (&mut self, name : &str, help : &str) {
+
+        self.add (name, None, help);
+    }
+
+/*================================================================================================*/
+
+    /// Adds an option which takes a value, given as `--name value` or `--name=value`.
+    ///
+    /// The value name is only used in the help text.
+    pub fn add_option (&mut self, name : &str, value_name : &str, help : &str) {
+
+        self.add (name, Some (value_name.to_string ()), help);
+    }
+
+/*================================================================================================*/
+
+    /// Binds a flag or option to a config setting.
+    ///
+    /// When the args are applied to a config, a bound flag sets its setting to `true`, and a
+    /// bound option sets its setting to its value. Unknown names are ignored.
+    pub fn bind (&mut self, name : &str, config_key : &str) {
+
+        if let Some (option) = self.options.iter_mut ().find (|option| option.name == name) {
+            option.config_key = Some (config_key.to_string ());
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Parses a list of arguments, not including the program path.
+    pub fn parse<I, S> (&self, arguments : I) -> Result <Args, ArgError>
+        where I : IntoIterator <Item = S>,
+              S : Into <String> {
+
+        let mut arguments     = arguments.into_iter ().map (Into::into);
+        let mut args          = Args::default ();
+        let mut options_ended = false;
+
+        while let Some (argument) = arguments.next () {
+
+            if options_ended {
+
+                args.positional.push (argument);
+                continue;
+            }
+
+            match argument.as_str () {
+
+                "--" => options_ended = true,
+
+                "-h" | "--help" => args.help = true,
+
+                "+set" => {
+
+                    let missing = || ArgError::MissingValue (argument.clone ());
+                    let key     = arguments.next ().ok_or_else (missing)?;
+                    let value   = arguments.next ().ok_or_else (missing)?;
+
+                    args.settings.push ((key, parse_value (&value)));
+                },
+
+                _ if argument.starts_with ("--") => {
+
+                    let (name, value) = match argument.find ('=') {
+                        Some (index) => (&argument[2..index],
+                                         Some (argument[index + 1..].to_string ())),
+                        None         => (&argument[2..], None)
+                    };
+
+                    self.parse_option (&mut args, name, value, &mut arguments)?;
+                },
+
+                _ if argument.starts_with ('+') || argument.starts_with ('-') => {
+                    return Err (ArgError::Unknown (argument));
+                },
+
+                _ => args.positional.push (argument)
+            }
+        }
+
+        Ok (args)
+    }
+
+/*================================================================================================*/
+
+    /// Parses the arguments the program was started with.
+    pub fn parse_env (&self) -> Result <Args, ArgError> {
+
+        self.parse (env::args ().skip (1))
+    }
+
+/*================================================================================================*/
+
+    /// Returns the help text, listing every option.
+    pub fn help (&self) -> String {
+
+        let mut rows = Vec::new ();
+
+        for option in &self.options {
+
+            let usage = match option.value_name {
+                Some (ref value_name) => format! ("--{} <{}>", option.name, value_name),
+                None                  => format! ("--{}", option.name)
+            };
+
+            let help = match option.config_key {
+                Some (ref key) => format! ("{} ({})", option.help, key),
+                None           => option.help.clone ()
+            };
+
+            rows.push ((usage, help));
+        }
+
+        rows.push (("--log <filter>".to_string (),
+                    "Sets the log filter (e.g. warn,renderer=debug)".to_string ()));
+        rows.push (("-h, --help".to_string (), "Shows this help".to_string ()));
+        rows.push (("+set <key> <value>".to_string (), "Overrides a config setting".to_string ()));
+
+        let width    = rows.iter ().map (|(usage, _)| usage.len ()).max ().unwrap_or (0);
+        let mut help = format! ("{} - {}\n\nUsage: {} [options] [+set <key> <value>]...\n\n\
+                                 Options:\n",
+                                self.program,
+                                self.about,
+                                self.program);
+
+        for (usage, text) in rows {
+            help.push_str (&format! ("  {:width$}  {}\n", usage, text, width = width));
+        }
+
+        help
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Adds an option, replacing any option with the same name
+    fn add (&mut self, name : &str, value_name : Option <String>, help : &str) {
+
+        self.options.retain (|option| option.name != name);
+        self.options.push (ArgOption {name       : name.to_string (),
+                                      value_name,
+                                      help       : help.to_string (),
+                                      config_key : None});
+    }
+
+/*================================================================================================*/
+
+    // Parses a `--name` argument, taking its value from the next argument if needed
+    fn parse_option<I> (&self,
+                        args      : &mut Args,
+                        name      : &str,
+                        value     : Option <String>,
+                        arguments : &mut I) -> Result <(), ArgError>
+        where I : Iterator <Item = String> {
+
+        let argument = format! ("--{}", name);
+
+        if name == "log" {
+
+            let value  = value.or_else (|| arguments.next ())
+                              .ok_or (ArgError::MissingValue (argument))?;
+            let filter = LogFilter::parse (&value).map_err (ArgError::LogFilter)?;
+
+            args.log_filter = Some (filter);
+            return Ok (());
+        }
+
+        let option = match self.options.iter ().find (|option| option.name == name) {
+            Some (option) => option,
+            None          => return Err (ArgError::Unknown (argument))
+        };
+
+        let setting = match option.value_name {
+
+            None => {
+
+                if value.is_some () {
+                    return Err (ArgError::UnexpectedValue (argument));
+                }
+
+                args.flags.insert (name.to_string ());
+                Value::Bool (true)
+            },
+
+            Some (_) => {
+
+                let value = value.or_else (|| arguments.next ())
+                                 .ok_or (ArgError::MissingValue (argument))?;
+                let parsed = parse_value (&value);
+
+                args.values.insert (name.to_string (), value);
+                parsed
+            }
+        };
+
+        if let Some (ref key) = option.config_key {
+            args.settings.push ((key.clone (), setting));
+        }
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+
+impl Args {
+
+    /// Checks if a flag was given.
+    pub fn flag (&self, name : &str) -> bool {
+
+        self.flags.contains (name)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of an option, if it was given. The last value is used if the option
+    /// was given more than once.
+    pub fn value (&self, name : &str) -> Option <&str> {
+
+        self.values.get (name).map (|value| value.as_str ())
+    }
+
+/*================================================================================================*/
+
+    /// Returns the config settings from bound options and `+set` overrides, in the order they
+    /// were given.
+    pub fn settings (&self) -> &[(String, Value)] {
+
+        &self.settings
+    }
+
+/*================================================================================================*/
+
+    /// Returns the positional arguments.
+    pub fn positional (&self) -> &[String] {
+
+        &self.positional
+    }
+
+/*================================================================================================*/
+
+    /// Returns the log filter given with `--log`.
+    pub fn log_filter (&self) -> Option <&LogFilter> {
+
+        self.log_filter.as_ref ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the help text was asked for with `--help`.
+    pub fn help_requested (&self) -> bool {
+
+        self.help
+    }
+
+/*================================================================================================*/
+
+    /// Applies the config settings to the command line layer of a config.
+    pub fn apply_to (&self, config : &mut Config) {
+
+        for (key, value) in &self.settings {
+            config.set_layer (ConfigLayer::CommandLine, key, value.clone ());
+        }
+    }
+}
@@ -0,0 +1,397 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {LogError, LogLevel, LogRecord, LogSink};
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The overflow policy enum
+///
+/// It decides what an async sink does when a record is logged while its queue is full.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+
+    /// Wait for the writer thread to make room
+    Block,
+    /// Drop the oldest queued record to make room
+    DropOldest,
+    /// Drop the new record
+    DropNewest
+}
+
+/*================================================================================================*/
+
+// A queued command for the writer thread
+enum Command {
+
+    Write (LogRecord),
+    Flush (u64),
+    Begin,
+    End
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The async sink struct
+///
+/// It wraps another sink, which is written to from a background thread. Logging only queues
+/// the record, so slow sinks (and formatting of their output) are kept off the calling
+/// thread. Queued records are always written before the sink is dropped, and flushing waits
+/// until the writer thread has caught up.
+///
+/// Errors from the wrapped sink can't be returned as they happen, so the latest one is
+/// returned by the next flush instead. A warning is written if any records are dropped.
+pub struct AsyncSink {
+
+    // Private
+    shared   : Arc <Shared>,
+    overflow : OverflowPolicy,
+    capacity : usize,
+    writer   : Option <thread::JoinHandle <()>>
+}
+
+/*================================================================================================*/
+
+// The state shared with the writer thread
+struct Shared {
+
+    state     : Mutex <State>,
+    queued    : Condvar,
+    processed : Condvar
+}
+
+/*================================================================================================*/
+
+// The queue and progress of the writer thread
+struct State {
+
+    queue         : VecDeque <Command>,
+    dropped       : usize,
+    flush_count   : u64,
+    flushed_count : u64,
+    error         : Option <io::Error>,
+    is_closed     : bool,
+    is_stopped    : bool
+}
+
+/*================================================================================================*/
+
+// Marks the writer thread as stopped when it exits, even if the wrapped sink panics
+struct StopGuard {
+
+    shared : Arc <Shared>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl AsyncSink {
+
+    /// Creates a new async sink, which queues up to `capacity` records for a sink.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{AsyncSink, FileSink, Logger, OverflowPolicy};
+    /// let file = FileSink::new ("log.txt").unwrap ();
+    /// let sink = AsyncSink::new (file, 4096, OverflowPolicy::DropOldest).unwrap ();
+    ///
+    /// let mut logger = Logger::empty ();
+    /// logger.add_sink (sink);
+    /// ```
+    pub fn new<S : LogSink + 'static> (sink     : S,
+                                       capacity : usize,
+                                       overflow : OverflowPolicy) -> Result <AsyncSink, LogError> {
+
+        let state = State {queue         : VecDeque::with_capacity (capacity),
+                           dropped       : 0,
+                           flush_count   : 0,
+                           flushed_count : 0,
+                           error         : None,
+                           is_closed     : false,
+                           is_stopped    : false};
+
+        let shared = Arc::new (Shared {state     : Mutex::new (state),
+                                       queued    : Condvar::new (),
+                                       processed : Condvar::new ()});
+
+        let writer_shared = shared.clone ();
+        let writer        = thread::Builder::new ().name ("trivert-log-writer".to_string ())
+                                                   .spawn (move || run_writer (sink,
+                                                                               writer_shared))?;
+
+        Ok (AsyncSink {shared,
+                       overflow,
+                       capacity : capacity.max (1),
+                       writer   : Some (writer)})
+    }
+}
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl AsyncSink {
+
+    // Queues a command for the writer thread, ignoring the capacity
+    fn send (&self, command : Command) -> io::Result <()> {
+
+        let mut state = self.shared.lock ();
+
+        if state.is_stopped {
+            return Err (writer_stopped ());
+        }
+
+        state.queue.push_back (command);
+        self.shared.queued.notify_one ();
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+
+impl Shared {
+
+    // Locks the state, ignoring poisoning as the state is always left valid
+    fn lock (&self) -> MutexGuard <'_, State> {
+
+        self.state.lock ().unwrap_or_else (|error| error.into_inner ())
+    }
+
+/*================================================================================================*/
+
+    // Waits on a condition variable, ignoring poisoning
+    fn wait<'a> (&self,
+                 condvar : &Condvar,
+                 state   : MutexGuard <'a, State>) -> MutexGuard <'a, State> {
+
+        condvar.wait (state).unwrap_or_else (|error| error.into_inner ())
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogSink for AsyncSink {
+
+    // Queues the record, applying the overflow policy if the queue is full
+    fn write (&mut self, record : &LogRecord) -> io::Result <()> {
+
+        let mut state = self.shared.lock ();
+
+        while !state.is_stopped && state.queue.len () >= self.capacity {
+
+            match self.overflow {
+
+                OverflowPolicy::Block => {
+                    state = self.shared.wait (&self.shared.processed, state);
+                },
+
+                OverflowPolicy::DropOldest => {
+
+                    // Only records are dropped, never flushes or markers
+                    let oldest = state.queue.iter ().position (|command| {
+                        matches! (*command, Command::Write (_))
+                    });
+
+                    match oldest {
+                        Some (index) => {
+                            state.queue.remove (index);
+                            state.dropped += 1;
+                        },
+                        None => break
+                    }
+                },
+
+                OverflowPolicy::DropNewest => {
+
+                    state.dropped += 1;
+                    return Ok (());
+                }
+            }
+        }
+
+        if state.is_stopped {
+            return Err (writer_stopped ());
+        }
+
+        state.queue.push_back (Command::Write (record.clone ()));
+        self.shared.queued.notify_one ();
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Waits for every queued record to be written and flushed
+    fn flush (&mut self) -> io::Result <()> {
+
+        let mut state = self.shared.lock ();
+
+        if state.is_stopped {
+            return Err (writer_stopped ());
+        }
+
+        state.flush_count += 1;
+
+        let ticket = state.flush_count;
+
+        state.queue.push_back (Command::Flush (ticket));
+        self.shared.queued.notify_one ();
+
+        while !state.is_stopped && state.flushed_count < ticket {
+            state = self.shared.wait (&self.shared.processed, state);
+        }
+
+        if let Some (error) = state.error.take () {
+            return Err (error);
+        }
+
+        if state.flushed_count < ticket {
+            return Err (writer_stopped ());
+        }
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Queues the begin marker
+    fn begin (&mut self) -> io::Result <()> {
+
+        self.send (Command::Begin)
+    }
+
+/*================================================================================================*/
+
+    // Queues the end marker
+    fn end (&mut self) -> io::Result <()> {
+
+        self.send (Command::End)
+    }
+}
+
+/*================================================================================================*/
+
+impl Drop for AsyncSink {
+
+    // Writes anything still queued, then stops the writer thread
+    fn drop (&mut self) {
+
+        self.shared.lock ().is_closed = true;
+        self.shared.queued.notify_one ();
+
+        if let Some (writer) = self.writer.take () {
+            let _ = writer.join ();
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Drop for StopGuard {
+
+    // Wakes anyone waiting on the writer thread
+    fn drop (&mut self) {
+
+        self.shared.lock ().is_stopped = true;
+        self.shared.processed.notify_all ();
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Writes queued commands to the sink until the async sink is dropped
+fn run_writer<S : LogSink> (mut sink : S, shared : Arc <Shared>) {
+
+    let _guard = StopGuard {shared : shared.clone ()};
+
+    loop {
+
+        let (command, dropped) = {
+
+            let mut state = shared.lock ();
+
+            while state.queue.is_empty () && !state.is_closed {
+                state = shared.wait (&shared.queued, state);
+            }
+
+            match state.queue.pop_front () {
+                Some (command) => (command, ::std::mem::replace (&mut state.dropped, 0)),
+                None           => break
+            }
+        };
+
+        // Room has been made in the queue
+        shared.processed.notify_all ();
+
+        let mut result = Ok (());
+
+        if dropped > 0 {
+
+            let message = format! ("{} log messages were dropped by a full queue", dropped);
+            result = sink.write (&LogRecord::new (LogLevel::Warning, "", &message));
+        }
+
+        let flushed = match command {
+            Command::Flush (ticket) => Some (ticket),
+            _                       => None
+        };
+
+        result = result.and (match command {
+            Command::Write (record) => sink.write (&record),
+            Command::Flush (_)      => sink.flush (),
+            Command::Begin          => sink.begin (),
+            Command::End            => sink.end ()
+        });
+
+        let mut state = shared.lock ();
+
+        if let Err (error) = result {
+            state.error = Some (error);
+        }
+
+        if let Some (ticket) = flushed {
+
+            state.flushed_count = state.flushed_count.max (ticket);
+            shared.processed.notify_all ();
+        }
+    }
+
+    // Nothing is left to report the error to once the sink has been dropped
+    let _ = sink.flush ();
+}
+
+/*================================================================================================*/
+
+// Returns the error used once the writer thread has stopped
+fn writer_stopped () -> io::Error {
+
+    io::Error::new (io::ErrorKind::BrokenPipe, "log writer thread has stopped")
+}
@@ -0,0 +1,172 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+/*================================================================================================*/
+//! Builds, lists and verifies TriVert pack files.
+/*================================================================================================*/
+
+extern crate trivert_utility;
+
+use trivert_utility::{ArgParser, PackCompression, PackSource, PackWriter};
+
+use std::error::Error;
+use std::path::Path;
+use std::process;
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The commands listed after the options in the help text
+const COMMANDS : &str = "
+Commands:
+  build <directory> <pack>  Packs every file in a directory
+  list <pack>               Lists the files in a pack
+  verify <pack>             Checks the integrity of every file in a pack
+";
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Runs the tool, exiting with an error code if the command fails
+fn main () {
+
+    if let Err (error) = run () {
+
+        eprintln! ("trivert-pack: {}", error);
+        process::exit (1);
+    }
+}
+
+/*================================================================================================*/
+
+// Parses the arguments, and runs the command given
+fn run () -> Result <(), Box <dyn Error>> {
+
+    let mut parser = ArgParser::new ("trivert-pack", "Builds and checks TriVert pack files");
+
+    parser.add_option ("compression", "none|lz4|zstd", "Sets how files are compressed (lz4)");
+    parser.add_option ("level", "1-22", "Sets the zstd compression level (19)");
+
+    let args = parser.parse_env ()?;
+
+    if args.help_requested () || args.positional ().is_empty () {
+
+        print! ("{}{}", parser.help (), COMMANDS);
+        return Ok (());
+    }
+
+    let positional = args.positional ();
+
+    match (positional[0].as_str (), &positional[1..]) {
+
+        ("build", [directory, pack]) => {
+
+            let compression = match args.value ("compression").unwrap_or ("lz4") {
+                "none" => PackCompression::None,
+                "lz4"  => PackCompression::Lz4,
+                "zstd" => PackCompression::Zstd,
+                other  => return Err (format! ("unknown compression \"{}\"", other).into ())
+            };
+
+            let level = match args.value ("level") {
+                Some (level) => level.parse ()?,
+                None         => 19
+            };
+
+            build (Path::new (directory), Path::new (pack), compression, level)
+        },
+
+        ("list", [pack])   => list (Path::new (pack)),
+        ("verify", [pack]) => verify (Path::new (pack)),
+
+        _ => Err ("unknown command, or wrong number of arguments (see --help)".into ())
+    }
+}
+
+/*================================================================================================*/
+
+// Packs every file in a directory
+fn build (directory   : &Path,
+          pack        : &Path,
+          compression : PackCompression,
+          level       : i32) -> Result <(), Box <dyn Error>> {
+
+    // The pack would otherwise try to pack itself
+    let pack_directory = pack.parent ().filter (|parent| !parent.as_os_str ().is_empty ())
+                                       .unwrap_or (Path::new ("."));
+
+    if pack_directory.canonicalize ()?.starts_with (directory.canonicalize ()?) {
+        return Err ("the pack can't be written inside the directory being packed".into ());
+    }
+
+    let mut writer = PackWriter::create (pack)?;
+
+    writer.set_zstd_level (level);
+    writer.add_directory (directory, compression)?;
+
+    let entries = writer.finish ()?;
+    let size    = entries.iter ().map (|entry| entry.size).sum::<u64> ();
+    let stored  = entries.iter ().map (|entry| entry.stored_size).sum::<u64> ();
+
+    println! ("Packed {} files, {} bytes into {} bytes ({:.1}%)",
+              entries.len (),
+              size,
+              stored,
+              if size == 0 {100.0} else {stored as f64 / size as f64 * 100.0});
+
+    Ok (())
+}
+
+/*================================================================================================*/
+
+// Lists the files in a pack
+fn list (pack : &Path) -> Result <(), Box <dyn Error>> {
+
+    let source = PackSource::new (pack)?;
+
+    println! ("{:>12} {:>12}  {:<5} {:<16}  Path", "Size", "Stored", "Comp", "Hash");
+
+    for entry in source.entries () {
+
+        let compression = match entry.compression {
+            PackCompression::None => "none",
+            PackCompression::Lz4  => "lz4",
+            PackCompression::Zstd => "zstd"
+        };
+
+        println! ("{:>12} {:>12}  {:<5} {:016x}  {}",
+                  entry.size,
+                  entry.stored_size,
+                  compression,
+                  entry.hash,
+                  entry.path);
+    }
+
+    Ok (())
+}
+
+/*================================================================================================*/
+
+// Checks the integrity of every file in a pack
+fn verify (pack : &Path) -> Result <(), Box <dyn Error>> {
+
+    let count = PackSource::new (pack)?.verify ()?;
+
+    println! ("Verified {} files", count);
+    Ok (())
+}
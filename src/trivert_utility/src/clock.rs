@@ -0,0 +1,331 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::time::{Duration, Instant};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The clock struct
+///
+/// It is a monotonic game clock, which is ticked once per frame to find how long the frame
+/// took. The clock keeps two times:
+///
+/// * The real time since it was created, which is never scaled or paused
+/// * The game time, which is the sum of every scaled delta, and stops while paused
+///
+/// A maximum delta can be set, so a long stall (such as from a debugger) doesn't cause a huge
+/// jump in game time.
+#[derive (Copy, Clone, Debug)]
+pub struct Clock {
+
+    // Private
+    start       : Instant,
+    last_tick   : Instant,
+    raw_delta   : Duration,
+    delta       : Duration,
+    game_time   : Duration,
+    time_scale  : f64,
+    max_delta   : Option <Duration>,
+    frame_count : u64,
+    paused      : bool
+}
+
+/*================================================================================================*/
+
+/// The stopwatch struct
+///
+/// It measures real time while it is running, and can be stopped and started again without
+/// losing the time measured so far.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct Stopwatch {
+
+    // Private
+    started : Option <Instant>,
+    elapsed : Duration
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Clock {
+
+    /// Creates a new clock, starting now.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::Clock;
+    /// let mut clock = Clock::new ();
+    ///
+    /// for _ in 0..3 {
+    ///
+    ///     let delta = clock.tick ();
+    ///
+    ///     // Update the game by delta ...
+    /// }
+    ///
+    /// assert_eq! (clock.frame_count (), 3);
+    /// ```
+    pub fn new () -> Clock {
+
+        let now = Instant::now ();
+
+        Clock {start       : now,
+               last_tick   : now,
+               raw_delta   : Duration::from_secs (0),
+               delta       : Duration::from_secs (0),
+               game_time   : Duration::from_secs (0),
+               time_scale  : 1.0,
+               max_delta   : None,
+               frame_count : 0,
+               paused      : false}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Ticks the clock, returning the game time which passed since the last tick.
+    ///
+    /// The real time since the last tick is limited to the maximum delta, then scaled by the
+    /// time scale. Nothing is added to the game time while the clock is paused.
+    pub fn tick (&mut self) -> Duration {
+
+        let now = Instant::now ();
+
+        self.raw_delta    = now.duration_since (self.last_tick);
+        self.last_tick    = now;
+        self.frame_count += 1;
+
+        self.delta = if self.paused {
+            Duration::from_secs (0)
+        } else {
+
+            let limited = self.max_delta.map_or (self.raw_delta, |max| self.raw_delta.min (max));
+            limited.mul_f64 (self.time_scale)
+        };
+
+        self.game_time += self.delta;
+        self.delta
+    }
+
+/*================================================================================================*/
+
+    /// Returns the game time which passed during the last tick.
+    pub fn delta (&self) -> Duration {
+
+        self.delta
+    }
+
+/*================================================================================================*/
+
+    /// Returns the real time which passed during the last tick, without limiting or scaling.
+    pub fn raw_delta (&self) -> Duration {
+
+        self.raw_delta
+    }
+
+/*================================================================================================*/
+
+    /// Returns the real time since the clock was created.
+    pub fn elapsed (&self) -> Duration {
+
+        self.start.elapsed ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the game time, as of the last tick.
+    pub fn game_time (&self) -> Duration {
+
+        self.game_time
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of times the clock has been ticked.
+    pub fn frame_count (&self) -> u64 {
+
+        self.frame_count
+    }
+
+/*================================================================================================*/
+
+    /// Sets how fast game time passes compared to real time.
+    ///
+    /// A scale of `0.5` runs the game at half speed. Negative scales are treated as zero.
+    pub fn set_time_scale (&mut self, time_scale : f64) {
+
+        self.time_scale = time_scale.max (0.0);
+    }
+
+/*================================================================================================*/
+
+    /// Returns how fast game time passes compared to real time.
+    pub fn time_scale (&self) -> f64 {
+
+        self.time_scale
+    }
+
+/*================================================================================================*/
+
+    /// Sets the longest real time a single tick can add to the game time, or `None` for no
+    /// limit (the default).
+    pub fn set_max_delta (&mut self, max_delta : Option <Duration>) {
+
+        self.max_delta = max_delta;
+    }
+
+/*================================================================================================*/
+
+    /// Returns the longest real time a single tick can add to the game time.
+    pub fn max_delta (&self) -> Option <Duration> {
+
+        self.max_delta
+    }
+
+/*================================================================================================*/
+
+    /// Pauses the game time. Ticks still measure the real time while paused.
+    pub fn pause (&mut self) {
+
+        self.paused = true;
+    }
+
+/*================================================================================================*/
+
+    /// Resumes the game time.
+    pub fn resume (&mut self) {
+
+        self.paused = false;
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the game time is paused.
+    pub fn is_paused (&self) -> bool {
+
+        self.paused
+    }
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Stopwatch {
+
+    /// Creates a new stopwatch, which isn't running.
+    pub fn new () -> Stopwatch {
+
+        Stopwatch::default ()
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new stopwatch, which starts running now.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::Stopwatch;
+    /// let stopwatch = Stopwatch::started ();
+    ///
+    /// // ...
+    ///
+    /// println! ("Loaded in {:?}", stopwatch.elapsed ());
+    /// ```
+    pub fn started () -> Stopwatch {
+
+        Stopwatch {started : Some (Instant::now ()),
+                   elapsed : Duration::from_secs (0)}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Starts the stopwatch, if it isn't already running.
+    pub fn start (&mut self) {
+
+        if self.started.is_none () {
+            self.started = Some (Instant::now ());
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Stops the stopwatch, keeping the time measured so far.
+    pub fn stop (&mut self) {
+
+        if let Some (started) = self.started.take () {
+            self.elapsed += started.elapsed ();
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Stops the stopwatch, and clears the time measured.
+    pub fn reset (&mut self) {
+
+        self.started = None;
+        self.elapsed = Duration::from_secs (0);
+    }
+
+/*================================================================================================*/
+
+    /// Clears the time measured and starts the stopwatch again, returning the time measured
+    /// before it was restarted.
+    pub fn restart (&mut self) -> Duration {
+
+        let elapsed = self.elapsed ();
+
+        self.started = Some (Instant::now ());
+        self.elapsed = Duration::from_secs (0);
+
+        elapsed
+    }
+
+/*================================================================================================*/
+
+    /// Returns the total time measured while the stopwatch was running.
+    pub fn elapsed (&self) -> Duration {
+
+        self.elapsed + self.started.map_or (Duration::from_secs (0), |started| started.elapsed ())
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the stopwatch is running.
+    pub fn is_running (&self) -> bool {
+
+        self.started.is_some ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Default for Clock {
+
+    // Creates a new clock, starting now
+    fn default () -> Clock {
+
+        Clock::new ()
+    }
+}
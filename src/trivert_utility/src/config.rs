@@ -0,0 +1,443 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {ConfigDocument, ConfigError, Value};
+use config_document::parse_value;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The config struct
+///
+/// It holds a game's settings in layers, where each layer overrides the ones below it:
+///
+/// 1. Defaults, set by the game
+/// 2. The user's config file, which is what gets saved
+/// 3. The command line
+/// 4. The environment
+///
+/// Settings are named by dotted keys (e.g. `video.vsync`), and read with typed getters which
+/// fall back to a default when a setting is missing or has the wrong type.
+///
+/// Callbacks can be registered to be told when the value of a setting changes, from any layer.
+///
+/// # Examples
+/// ```
+/// # use trivert_utility::{Config, ConfigLayer};
+/// let mut config = Config::new ();
+///
+/// config.set_default ("video.width", 1280);
+/// config.set_default ("video.vsync", true);
+/// config.set_layer (ConfigLayer::CommandLine, "video.width", 1920);
+///
+/// assert_eq! (config.get_int ("video.width", 800), 1920);
+/// assert_eq! (config.get_bool ("video.vsync", false), true);
+/// assert_eq! (config.get_float ("audio.volume", 1.0), 1.0);
+/// ```
+#[derive (Default)]
+pub struct Config {
+
+    // Private
+    defaults     : BTreeMap <String, Value>,
+    user         : ConfigDocument,
+    user_path    : Option <PathBuf>,
+    command_line : BTreeMap <String, Value>,
+    environment  : BTreeMap <String, Value>,
+    listeners    : Vec <Listener>
+}
+
+/*================================================================================================*/
+
+// A callback told about changes to the settings under a key
+struct Listener {
+
+    prefix   : String,
+    callback : ChangeCallback
+}
+
+// The callback of a listener, given a setting's key and new value
+type ChangeCallback = Box <dyn FnMut (&str, Option <&Value>) + Send>;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The config layer enum
+///
+/// It names a layer of settings in a config, from lowest to highest priority.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigLayer {
+
+    /// Defaults set by the game
+    Default,
+    /// The user's config file
+    User,
+    /// Settings from the command line
+    CommandLine,
+    /// Settings from environment variables
+    Environment
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Config {
+
+    /// Creates a new empty config.
+    pub fn new () -> Config {
+
+        Config::default ()
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Returns the value of a setting from the highest layer which has it.
+    pub fn get (&self, key : &str) -> Option <&Value> {
+
+        self.source (key).and_then (|layer| self.get_layer (layer, key))
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of a setting in a single layer.
+    pub fn get_layer (&self, layer : ConfigLayer, key : &str) -> Option <&Value> {
+
+        match layer {
+
+            ConfigLayer::Default     => self.defaults.get (key),
+            ConfigLayer::User        => self.user.get (key),
+            ConfigLayer::CommandLine => self.command_line.get (key),
+            ConfigLayer::Environment => self.environment.get (key)
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the highest layer which has a setting.
+    pub fn source (&self, key : &str) -> Option <ConfigLayer> {
+
+        [ConfigLayer::Environment,
+         ConfigLayer::CommandLine,
+         ConfigLayer::User,
+         ConfigLayer::Default].iter ()
+                              .cloned ()
+                              .find (|&layer| self.get_layer (layer, key).is_some ())
+    }
+
+/*================================================================================================*/
+
+    /// Returns a setting as a boolean, or the default if it is missing or not a boolean.
+    pub fn get_bool (&self, key : &str, default : bool) -> bool {
+
+        self.get (key).and_then (Value::as_bool).unwrap_or (default)
+    }
+
+/*================================================================================================*/
+
+    /// Returns a setting as a signed integer, or the default if it is missing or not an
+    /// integer which fits.
+    pub fn get_int (&self, key : &str, default : i64) -> i64 {
+
+        self.get (key).and_then (Value::as_int).unwrap_or (default)
+    }
+
+/*================================================================================================*/
+
+    /// Returns a setting as an unsigned integer, or the default if it is missing or not an
+    /// integer which fits.
+    pub fn get_uint (&self, key : &str, default : u64) -> u64 {
+
+        self.get (key).and_then (Value::as_uint).unwrap_or (default)
+    }
+
+/*================================================================================================*/
+
+    /// Returns a setting as a floating point number, or the default if it is missing or not a
+    /// number.
+    pub fn get_float (&self, key : &str, default : f64) -> f64 {
+
+        self.get (key).and_then (Value::as_float).unwrap_or (default)
+    }
+
+/*================================================================================================*/
+
+    /// Returns a setting as a string, or the default if it is missing or not a string.
+    pub fn get_str (&self, key : &str, default : &str) -> String {
+
+        self.get (key).and_then (Value::as_str).unwrap_or (default).to_string ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the keys of every setting in any layer, in order.
+    pub fn keys (&self) -> Vec <String> {
+
+        let mut keys : BTreeSet <String> = BTreeSet::new ();
+
+        keys.extend (self.defaults.keys ().cloned ());
+        keys.extend (self.user.iter ().map (|(key, _)| key.to_string ()));
+        keys.extend (self.command_line.keys ().cloned ());
+        keys.extend (self.environment.keys ().cloned ());
+
+        keys.into_iter ().collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Sets the default value of a setting.
+    pub fn set_default<V : Into <Value>> (&mut self, key : &str, value : V) {
+
+        self.set_layer (ConfigLayer::Default, key, value);
+    }
+
+/*================================================================================================*/
+
+    /// Sets a setting in the user layer, so it is saved to the user's config file.
+    pub fn set<V : Into <Value>> (&mut self, key : &str, value : V) {
+
+        self.set_layer (ConfigLayer::User, key, value);
+    }
+
+/*================================================================================================*/
+
+    /// Sets a setting in a single layer.
+    pub fn set_layer<V : Into <Value>> (&mut self, layer : ConfigLayer, key : &str, value : V) {
+
+        let value = value.into ();
+
+        self.change (key, |config| match layer {
+
+            ConfigLayer::Default     => {config.defaults.insert (key.to_string (), value);},
+            ConfigLayer::User        => config.user.set (key, value),
+            ConfigLayer::CommandLine => {config.command_line.insert (key.to_string (), value);},
+            ConfigLayer::Environment => {config.environment.insert (key.to_string (), value);}
+        });
+    }
+
+/*================================================================================================*/
+
+    /// Sets a setting in a single layer from text, which is parsed the same way as a value in
+    /// a config file.
+    pub fn set_parsed (&mut self, layer : ConfigLayer, key : &str, text : &str) {
+
+        self.set_layer (layer, key, parse_value (text));
+    }
+
+/*================================================================================================*/
+
+    /// Removes a setting from a single layer, returning its value if it existed.
+    pub fn remove (&mut self, layer : ConfigLayer, key : &str) -> Option <Value> {
+
+        let mut removed = None;
+
+        self.change (key, |config| removed = match layer {
+
+            ConfigLayer::Default     => config.defaults.remove (key),
+            ConfigLayer::User        => config.user.remove (key),
+            ConfigLayer::CommandLine => config.command_line.remove (key),
+            ConfigLayer::Environment => config.environment.remove (key)
+        });
+
+        removed
+    }
+
+/*================================================================================================*/
+
+    /// Loads the user layer from a config file, replacing any settings it had.
+    ///
+    /// A missing file is treated as empty, so it is created when the config is saved. The path
+    /// is kept for saving either way.
+    pub fn load_user<P : AsRef <Path>> (&mut self, path : P) -> Result <(), ConfigError> {
+
+        let path = path.as_ref ();
+
+        let document = match ConfigDocument::load (path) {
+
+            Ok (document) => document,
+
+            Err (ConfigError::Io {ref error, ..}) if error.kind () == io::ErrorKind::NotFound =>
+                ConfigDocument::new (),
+
+            Err (error) => return Err (error)
+        };
+
+        self.user_path = Some (path.to_path_buf ());
+        self.change_all (|config| config.user = document);
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    /// Saves the user layer to the file it was loaded from.
+    ///
+    /// Comments and formatting in the file are kept.
+    pub fn save (&self) -> Result <(), ConfigError> {
+
+        match self.user_path {
+
+            Some (ref path) => self.user.save (path),
+
+            None => Err (ConfigError::Io {path  : String::new (),
+                                          error : io::Error::new (io::ErrorKind::NotFound,
+                                                                  "no user config file loaded")})
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the user layer's document.
+    pub fn user_document (&self) -> &ConfigDocument {
+
+        &self.user
+    }
+
+/*================================================================================================*/
+
+    /// Loads the environment layer from the environment variables starting with a prefix,
+    /// replacing any settings it had.
+    ///
+    /// The variable names are turned into keys by removing the prefix and the underscore after
+    /// it, making them lower case, and replacing double underscores with dots. For example,
+    /// with the prefix `GAME`, `GAME_VIDEO__MAX_FPS=144` sets `video.max_fps` to `144`.
+    pub fn load_env (&mut self, prefix : &str) {
+
+        let prefix      = format! ("{}_", prefix);
+        let environment = env::vars ().filter_map (|(name, value)| {
+
+            let key = name.strip_prefix (&prefix)?;
+
+            if key.is_empty () {
+                return None;
+            }
+
+            Some ((key.to_lowercase ().replace ("__", "."), parse_value (&value)))
+        }).collect ();
+
+        self.change_all (|config| config.environment = environment);
+    }
+
+/*================================================================================================*/
+
+    /// Registers a callback, which is called whenever the value of a setting under a key
+    /// changes.
+    ///
+    /// The callback is given the setting's key and new value, or `None` if it was removed
+    /// from every layer. An empty key watches every setting.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::Config;
+    /// let mut config = Config::new ();
+    ///
+    /// config.on_change ("video", |key, value| {
+    ///     println! ("{} changed to {:?}", key, value);
+    /// });
+    ///
+    /// config.set ("video.vsync", false); // Prints "video.vsync changed to Some(Bool(false))"
+    /// ```
+    pub fn on_change<F> (&mut self, key : &str, callback : F)
+        where F : FnMut (&str, Option <&Value>) + Send + 'static {
+
+        self.listeners.push (Listener {prefix   : key.to_string (),
+                                       callback : Box::new (callback)});
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Makes a change to a single setting, notifying the listeners if its value changed
+    fn change<F : FnOnce (&mut Config)> (&mut self, key : &str, change : F) {
+
+        let before = self.get (key).cloned ();
+
+        change (self);
+
+        if self.get (key) != before.as_ref () {
+            self.notify (&[key.to_string ()]);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Makes a change to any settings, notifying the listeners of every value which changed
+    fn change_all<F : FnOnce (&mut Config)> (&mut self, change : F) {
+
+        let before : BTreeMap <String, Value> = self.keys ()
+                                                    .into_iter ()
+                                                    .filter_map (|key| {
+                                                        let value = self.get (&key).cloned ();
+                                                        value.map (|value| (key, value))
+                                                    })
+                                                    .collect ();
+
+        change (self);
+
+        let mut keys : BTreeSet <String> = before.keys ().cloned ().collect ();
+        keys.extend (self.keys ());
+
+        let changed : Vec <String> = keys.into_iter ()
+                                         .filter (|key| self.get (key) != before.get (key))
+                                         .collect ();
+
+        self.notify (&changed);
+    }
+
+/*================================================================================================*/
+
+    // Calls the listeners watching each of the keys
+    fn notify (&mut self, keys : &[String]) {
+
+        // The listeners are taken while called, as they can't borrow the config themselves
+        let mut listeners = mem::take (&mut self.listeners);
+
+        for key in keys {
+
+            let value = self.get (key);
+
+            for listener in &mut listeners {
+
+                if key_matches (&listener.prefix, key) {
+                    (listener.callback) (key, value);
+                }
+            }
+        }
+
+        self.listeners = listeners;
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Checks if a watched key covers a setting's key (itself or a setting under it)
+fn key_matches (prefix : &str, key : &str) -> bool {
+
+    prefix.is_empty () ||
+    key.strip_prefix (prefix).is_some_and (|rest| rest.is_empty () || rest.starts_with ('.'))
+}
@@ -0,0 +1,741 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {ConfigError, Value};
+use formatter::write_json_string;
+
+use std::char;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The config document struct
+///
+/// It holds the settings of a config file, written in a subset of TOML which also covers
+/// simple INI files:
+///
+/// * Sections are started with `[name]`, and may be nested with dots (e.g. `[audio.music]`)
+/// * Settings are written as `key = value`, where the key may also contain dots
+/// * Names in keys and sections are bare (letters, digits, `_` and `-`) or quoted
+/// * Values can be booleans, integers, floats (including `inf` and `nan`), or strings.
+///   Strings may be quoted with `"` (with escapes) or `'`, and any other text is read as a
+///   string
+/// * Comments start with `#` or `;`, either on their own line or after a value
+///
+/// Settings are named by their full key, such as `video.vsync` for `vsync` in the `[video]`
+/// section. Changing a setting only rewrites its value, so comments and formatting are kept
+/// when the document is saved.
+///
+/// # Examples
+/// ```
+/// # use trivert_utility::ConfigDocument;
+/// let mut document = ConfigDocument::parse ("[video]\nvsync = true # Limits the frame rate\n")
+///                                   .unwrap ();
+///
+/// document.set ("video.vsync", false);
+/// document.set ("audio.volume", 0.8);
+///
+/// assert_eq! (document.to_string (),
+///             "[video]\nvsync = false # Limits the frame rate\n\n[audio]\nvolume = 0.8\n");
+/// ```
+#[derive (Clone, Debug, Default, PartialEq)]
+pub struct ConfigDocument {
+
+    // Private
+    lines : Vec <Line>
+}
+
+/*================================================================================================*/
+
+/// The config parse error struct
+///
+/// It is returned when a config document is malformed.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ParseConfigError {
+
+    // Private
+    line    : usize,
+    message : String
+}
+
+/*================================================================================================*/
+
+// A setting, split into its parts so the value can be replaced
+#[derive (Clone, Debug, PartialEq)]
+struct Entry {
+
+    key        : String,
+    prefix     : String,
+    value_text : String,
+    value      : Value,
+    suffix     : String
+}
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// A line of a config document
+#[derive (Clone, Debug, PartialEq)]
+enum Line {
+
+    Text    (String),
+    Section {text : String, name : String},
+    Entry   (Entry)
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl ConfigDocument {
+
+    /// Creates a new empty config document.
+    pub fn new () -> ConfigDocument {
+
+        ConfigDocument::default ()
+    }
+
+/*================================================================================================*/
+
+    /// Parses a config document from text.
+    pub fn parse (text : &str) -> Result <ConfigDocument, ParseConfigError> {
+
+        let mut document = ConfigDocument::new ();
+        let mut section  = String::new ();
+
+        for (index, line) in text.lines ().enumerate () {
+
+            let error   = |message : &str| ParseConfigError {line    : index + 1,
+                                                             message : message.to_string ()};
+            let trimmed = line.trim ();
+
+            if trimmed.is_empty () || trimmed.starts_with ('#') || trimmed.starts_with (';') {
+
+                document.lines.push (Line::Text (line.to_string ()));
+                continue;
+            }
+
+            if trimmed.starts_with ('[') {
+
+                section = parse_section (trimmed).map_err (error)?;
+
+                document.lines.push (Line::Section {text : line.to_string (),
+                                                    name : section.clone ()});
+                continue;
+            }
+
+            let entry = parse_entry (line, &section).map_err (error)?;
+
+            if document.get (&entry.key).is_some () {
+                return Err (error ("duplicate key"));
+            }
+
+            document.lines.push (Line::Entry (entry));
+        }
+
+        Ok (document)
+    }
+
+/*================================================================================================*/
+
+    /// Loads a config document from a file.
+    pub fn load<P : AsRef <Path>> (path : P) -> Result <ConfigDocument, ConfigError> {
+
+        let path = path.as_ref ();
+
+        let text = fs::read_to_string (path).map_err (|error| {
+            ConfigError::Io {path : path.display ().to_string (),
+                             error}
+        })?;
+
+        ConfigDocument::parse (&text).map_err (|error| {
+            ConfigError::Parse {path : path.display ().to_string (),
+                                error}
+        })
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Saves the document to a file.
+    pub fn save<P : AsRef <Path>> (&self, path : P) -> Result <(), ConfigError> {
+
+        let path = path.as_ref ();
+
+        fs::write (path, self.to_string ()).map_err (|error| {
+            ConfigError::Io {path : path.display ().to_string (),
+                             error}
+        })
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value of a setting, given its full key.
+    pub fn get (&self, key : &str) -> Option <&Value> {
+
+        self.entries ().find (|entry| entry.key == key).map (|entry| &entry.value)
+    }
+
+/*================================================================================================*/
+
+    /// Sets the value of a setting, given its full key.
+    ///
+    /// Existing settings keep their place and comments. New settings are added to the end of
+    /// their section, which is added to the end of the document if it doesn't exist.
+    pub fn set<V : Into <Value>> (&mut self, key : &str, value : V) {
+
+        let value      = value.into ();
+        let value_text = format_value (&value);
+
+        for line in &mut self.lines {
+
+            if let Line::Entry (ref mut entry) = *line {
+
+                if entry.key == key {
+
+                    entry.value_text = value_text;
+                    entry.value      = value;
+                    return;
+                }
+            }
+        }
+
+        let (section, name) = match key.rfind ('.') {
+            Some (index) => (&key[..index], &key[index + 1..]),
+            None         => ("", key)
+        };
+
+        let entry = Line::Entry (Entry {key        : key.to_string (),
+                                        prefix     : format! ("{} = ", format_key (name)),
+                                        value_text,
+                                        value,
+                                        suffix     : String::new ()});
+
+        if let Some (index) = self.insert_position (section) {
+
+            self.lines.insert (index, entry);
+            return;
+        }
+
+        if self.lines.last ().is_some_and (|line| !line.is_blank ()) {
+            self.lines.push (Line::Text (String::new ()));
+        }
+
+        self.lines.push (Line::Section {text : format! ("[{}]", format_key (section)),
+                                        name : section.to_string ()});
+        self.lines.push (entry);
+    }
+
+/*================================================================================================*/
+
+    /// Removes a setting, returning its value if it existed.
+    pub fn remove (&mut self, key : &str) -> Option <Value> {
+
+        let index = self.lines.iter ().position (|line| match *line {
+            Line::Entry (ref entry) => entry.key == key,
+            _                       => false
+        })?;
+
+        match self.lines.remove (index) {
+            Line::Entry (entry) => Some (entry.value),
+            _                   => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns an iterator over the full keys and values of every setting, in document order.
+    pub fn iter (&self) -> impl Iterator <Item = (&str, &Value)> {
+
+        self.entries ().map (|entry| (entry.key.as_str (), &entry.value))
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of settings in the document.
+    pub fn len (&self) -> usize {
+
+        self.entries ().count ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the document has no settings.
+    pub fn is_empty (&self) -> bool {
+
+        self.entries ().next ().is_none ()
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Returns an iterator over the settings
+    fn entries (&self) -> impl Iterator <Item = &Entry> {
+
+        self.lines.iter ().filter_map (|line| match *line {
+            Line::Entry (ref entry) => Some (entry),
+            _                       => None
+        })
+    }
+
+/*================================================================================================*/
+
+    // Returns where to add a new setting to a section, or `None` if the section doesn't exist
+    fn insert_position (&self, section : &str) -> Option <usize> {
+
+        let mut current  = "";
+        let mut position = None;
+
+        for (index, line) in self.lines.iter ().enumerate () {
+
+            match *line {
+
+                Line::Section {ref name, ..} => {
+
+                    // Settings outside any section must go before the first section
+                    if section.is_empty () && position.is_none () {
+                        position = Some (index);
+                    }
+
+                    current = name;
+
+                    if current == section {
+                        position = Some (index + 1);
+                    }
+                },
+
+                Line::Entry (_) if current == section => position = Some (index + 1),
+                _                                     => {}
+            }
+        }
+
+        if section.is_empty () {
+            return position.or (Some (self.lines.len ()));
+        }
+
+        position
+    }
+}
+
+/*================================================================================================*/
+
+impl ParseConfigError {
+
+    /// Returns the line number the error is on, counting from one.
+    pub fn line (&self) -> usize {
+
+        self.line
+    }
+}
+
+/*================================================================================================*/
+
+impl Line {
+
+    // Checks if the line is blank
+    fn is_blank (&self) -> bool {
+
+        match *self {
+            Line::Text (ref text) => text.trim ().is_empty (),
+            _                     => false
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for ConfigDocument {
+
+    // Writes the document as it would be saved, ending with a new line
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        for line in &self.lines {
+
+            match *line {
+
+                Line::Text (ref text) | Line::Section {ref text, ..} => writeln! (f, "{}", text)?,
+
+                Line::Entry (ref entry) =>
+                    writeln! (f, "{}{}{}", entry.prefix, entry.value_text, entry.suffix)?
+            }
+        }
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ParseConfigError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ParseConfigError {}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Parses the text of a setting's value, as written in a config file
+//
+// Quoted strings are unquoted, and anything which isn't a boolean or a number is read as a
+// string.
+pub fn parse_value (text : &str) -> Value {
+
+    let text = text.trim ();
+
+    if let Some (string) = parse_quoted (text) {
+        return Value::Str (string);
+    }
+
+    match text {
+
+        "true"                  => Value::Bool (true),
+        "false"                 => Value::Bool (false),
+        "inf" | "+inf"          => Value::Float (f64::INFINITY),
+        "-inf"                  => Value::Float (f64::NEG_INFINITY),
+        "nan" | "+nan" | "-nan" => Value::Float (f64::NAN),
+        _                       => {
+            parse_number (text).unwrap_or_else (|| Value::Str (text.to_string ()))
+        }
+    }
+}
+
+/*================================================================================================*/
+
+// Parses a section header, returning the section name
+fn parse_section (line : &str) -> Result <String, &'static str> {
+
+    if line.starts_with ("[[") {
+        return Err ("arrays of tables are not supported");
+    }
+
+    let (name, length) = parse_key (&line[1..]).map_err (|_| "invalid section name")?;
+    let end            = 1 + length;
+
+    if !line[end..].starts_with (']') {
+
+        return Err (if line[end..].contains (']') {"invalid section name"}
+                    else                          {"unterminated section header"});
+    }
+
+    let rest = line[end + 1..].trim_start ();
+
+    if !rest.is_empty () && !rest.starts_with ('#') && !rest.starts_with (';') {
+        return Err ("unexpected text after section header");
+    }
+
+    Ok (name)
+}
+
+/*================================================================================================*/
+
+// Parses a setting within a section
+fn parse_entry (line : &str, section : &str) -> Result <Entry, &'static str> {
+
+    let (name, equals) = parse_key (line)?;
+
+    if !line[equals..].starts_with ('=') {
+        return Err (if line.contains ('=') {"invalid key"} else {"expected a key and value"});
+    }
+
+    let rest        = &line[equals + 1..];
+    let value_start = equals + 1 + (rest.len () - rest.trim_start ().len ());
+    let value_end   = value_start + value_length (&line[value_start..])?;
+    let value_text  = &line[value_start..value_end];
+
+    if value_text.is_empty () {
+        return Err ("missing value");
+    }
+
+    if (value_text.starts_with ('"') || value_text.starts_with ('\'')) &&
+       parse_quoted (value_text).is_none () {
+        return Err ("invalid string");
+    }
+
+    let key = if section.is_empty () {
+        name.to_string ()
+    } else {
+        format! ("{}.{}", section, name)
+    };
+
+    Ok (Entry {key,
+               prefix     : line[..value_start].to_string (),
+               value_text : value_text.to_string (),
+               value      : parse_value (value_text),
+               suffix     : line[value_end..].to_string ()})
+}
+
+/*================================================================================================*/
+
+// Returns the length of a value, without any whitespace or comment after it
+//
+// Only a quote at the start of the value begins a string, so unquoted text can contain quotes.
+fn value_length (text : &str) -> Result <usize, &'static str> {
+
+    let start = if text.starts_with ('"') || text.starts_with ('\'') {
+        quoted_length (text).ok_or ("unterminated string")?
+    } else {
+        0
+    };
+
+    let mut previous = text[..start].chars ().last ().unwrap_or (' ');
+
+    for (index, character) in text[start..].char_indices () {
+
+        if (character == '#' || character == ';') && previous.is_whitespace () {
+            return Ok (text[..start + index].trim_end ().len ());
+        }
+
+        previous = character;
+    }
+
+    Ok (text.trim_end ().len ())
+}
+
+/*================================================================================================*/
+
+// Returns the length of the quoted string at the start of some text, if it is terminated
+fn quoted_length (text : &str) -> Option <usize> {
+
+    let mark        = text.chars ().next ()?;
+    let mut escaped = false;
+
+    for (index, character) in text.char_indices ().skip (1) {
+
+        match character {
+
+            _ if escaped           => escaped = false,
+            '\\' if mark == '"'    => escaped = true,
+            _ if character == mark => return Some (index + 1),
+            _                      => {}
+        }
+    }
+
+    None
+}
+
+/*================================================================================================*/
+
+// Parses a dotted key, where each name is bare or quoted, returning the names joined by dots
+// and the length of the key (along with any whitespace after it)
+fn parse_key (text : &str) -> Result <(String, usize), &'static str> {
+
+    let mut names    = Vec::new ();
+    let mut position = 0;
+
+    loop {
+
+        let rest  = &text[position..];
+        let start = position + (rest.len () - rest.trim_start ().len ());
+        let rest  = &text[start..];
+
+        let (name, length) = if rest.starts_with ('"') || rest.starts_with ('\'') {
+
+            let length = quoted_length (rest).ok_or ("unterminated string")?;
+            let name   = parse_quoted (&rest[..length]).ok_or ("invalid key")?;
+
+            (name, length)
+
+        } else {
+
+            let length = rest.find (|character| !is_key_character (character))
+                             .unwrap_or (rest.len ());
+
+            if length == 0 {
+                return Err ("invalid key");
+            }
+
+            (rest[..length].to_string (), length)
+        };
+
+        names.push (name);
+
+        let after = &text[start + length..];
+        position  = start + length + (after.len () - after.trim_start ().len ());
+
+        if !text[position..].starts_with ('.') {
+            return Ok ((names.join ("."), position));
+        }
+
+        position += 1;
+    }
+}
+
+/*================================================================================================*/
+
+// Checks if a name is a valid bare key
+fn is_key (name : &str) -> bool {
+
+    !name.is_empty () && name.chars ().all (is_key_character)
+}
+
+/*================================================================================================*/
+
+// Checks if a character can be used in a bare key
+fn is_key_character (character : char) -> bool {
+
+    character.is_alphanumeric () || character == '_' || character == '-'
+}
+
+/*================================================================================================*/
+
+// Parses a string fully enclosed in quotes, unescaping it if double quoted
+fn parse_quoted (text : &str) -> Option <String> {
+
+    if text.len () >= 2 && text.starts_with ('\'') && text.ends_with ('\'') {
+
+        let inner = &text[1..text.len () - 1];
+        return if inner.contains ('\'') {None} else {Some (inner.to_string ())};
+    }
+
+    if text.len () < 2 || !text.starts_with ('"') || !text.ends_with ('"') {
+        return None;
+    }
+
+    let mut string     = String::new ();
+    let mut characters = text[1..text.len () - 1].chars ();
+
+    while let Some (character) = characters.next () {
+
+        match character {
+
+            '"'  => return None,
+            '\\' => {
+
+                let escaped = match characters.next ()? {
+
+                    'b'  => '\u{8}',
+                    't'  => '\t',
+                    'n'  => '\n',
+                    'f'  => '\u{c}',
+                    'r'  => '\r',
+                    '"'  => '"',
+                    '\\' => '\\',
+                    'u'  => parse_unicode (&mut characters, 4)?,
+                    'U'  => parse_unicode (&mut characters, 8)?,
+                    _    => return None
+                };
+
+                string.push (escaped);
+            },
+
+            character => string.push (character)
+        }
+    }
+
+    Some (string)
+}
+
+/*================================================================================================*/
+
+// Parses the hex digits of a unicode escape
+fn parse_unicode<I> (characters : &mut I, digits : usize) -> Option <char>
+    where I : Iterator <Item = char> {
+
+    let hex : String = characters.take (digits).collect ();
+
+    if hex.len () != digits {
+        return None;
+    }
+
+    char::from_u32 (u32::from_str_radix (&hex, 16).ok ()?)
+}
+
+/*================================================================================================*/
+
+// Parses an integer or float, which may contain underscores between digits
+fn parse_number (text : &str) -> Option <Value> {
+
+    if !text.chars ().any (|character| character.is_ascii_digit ()) ||
+       !text.chars ().all (|character| "0123456789+-._eE".contains (character)) {
+        return None;
+    }
+
+    let digits = text.replace ('_', "");
+
+    if let Ok (value) = digits.parse::<i64> () {
+        return Some (Value::Int (value));
+    }
+
+    if let Ok (value) = digits.parse::<u64> () {
+        return Some (Value::Uint (value));
+    }
+
+    digits.parse::<f64> ().ok ().map (Value::Float)
+}
+
+/*================================================================================================*/
+
+// Formats a key to be written to a config file, quoting any names which aren't bare
+fn format_key (key : &str) -> String {
+
+    let names : Vec <String> = key.split ('.')
+                                  .map (|name| {
+
+                                      if is_key (name) {
+                                          return name.to_string ();
+                                      }
+
+                                      let mut text = String::new ();
+
+                                      write_json_string (&mut text, name);
+                                      text
+                                  })
+                                  .collect ();
+    names.join (".")
+}
+
+/*================================================================================================*/
+
+// Formats a value to be written to a config file
+fn format_value (value : &Value) -> String {
+
+    match *value {
+
+        Value::Float (value) if value.is_nan ()      => "nan".to_string (),
+        Value::Float (value) if value == f64::INFINITY => "inf".to_string (),
+        Value::Float (value) if value.is_infinite ()   => "-inf".to_string (),
+        Value::Float (value)                           => format! ("{:?}", value),
+
+        Value::Str (ref value) => {
+
+            let mut text = String::new ();
+
+            write_json_string (&mut text, value);
+            text
+        },
+
+        ref value => value.to_string ()
+    }
+}
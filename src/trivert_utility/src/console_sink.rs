@@ -0,0 +1,192 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {LogFormatter, LogLevel, LogRecord, LogSink};
+use record::RecordBody;
+
+use std::io;
+use std::io::IsTerminal;
+use std::io::prelude::Write;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The stream a console sink writes to
+#[derive (Copy, Clone, Debug, PartialEq)]
+enum ConsoleStream {
+
+    Stdout,
+    Stderr
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The console sink struct
+///
+/// It writes records to either the standard output or error stream.
+/// The level of each record is colored using ANSI escape codes when the stream is a terminal,
+/// which can be turned off for terminals that don't support them, or forced on when the stream
+/// is redirected. Colors are only used with the default text format, and not when another
+/// formatter is set.
+pub struct ConsoleSink {
+
+    // Private
+    stream     : ConsoleStream,
+    formatter  : Option <Box <dyn LogFormatter>>,
+    use_colors : bool
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl ConsoleSink {
+
+    /// Creates a new console sink which writes to the standard output stream.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::ConsoleSink;
+    /// let sink = ConsoleSink::stdout ();
+    /// ```
+    pub fn stdout () -> ConsoleSink {
+
+        ConsoleSink {stream     : ConsoleStream::Stdout,
+                     formatter  : None,
+                     use_colors : io::stdout ().is_terminal ()}
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new console sink which writes to the standard error stream.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::ConsoleSink;
+    /// let sink = ConsoleSink::stderr ();
+    /// ```
+    pub fn stderr () -> ConsoleSink {
+
+        ConsoleSink {stream     : ConsoleStream::Stderr,
+                     formatter  : None,
+                     use_colors : io::stderr ().is_terminal ()}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets whether levels are colored using ANSI escape codes.
+    ///
+    /// By default they are only colored when the stream is a terminal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::ConsoleSink;
+    /// let mut sink = ConsoleSink::stdout ();
+    /// sink.set_colors (false);
+    /// ```
+    pub fn set_colors (&mut self, use_colors : bool) {
+
+        self.use_colors = use_colors;
+    }
+
+/*================================================================================================*/
+
+    /// Sets the formatter used to write records, replacing the colored text format.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{ConsoleSink, JsonFormatter};
+    /// let mut sink = ConsoleSink::stdout ();
+    /// sink.set_formatter (JsonFormatter);
+    /// ```
+    pub fn set_formatter<F : LogFormatter + 'static> (&mut self, formatter : F) {
+
+        self.formatter = Some (Box::new (formatter));
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Writes a record to the given stream
+    fn write_to<W : Write> (&self, stream : &mut W, record : &LogRecord) -> io::Result <()> {
+
+        if let Some (ref formatter) = self.formatter {
+
+            let mut line = String::new ();
+            formatter.format (record, &mut line);
+
+            return writeln! (stream, "{}", line);
+        }
+
+        if !self.use_colors {
+            return writeln! (stream, "{}", record);
+        }
+
+        writeln! (stream, "\x1b[{}m{}\x1b[0m {}",
+                  level_color (record.level), record.level.label (), RecordBody (record))
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogSink for ConsoleSink {
+
+    // Writes the record as a single line
+    fn write (&mut self, record : &LogRecord) -> io::Result <()> {
+
+        match self.stream {
+            ConsoleStream::Stdout => self.write_to (&mut io::stdout ().lock (), record),
+            ConsoleStream::Stderr => self.write_to (&mut io::stderr ().lock (), record)
+        }
+    }
+
+/*================================================================================================*/
+
+    // Flushes the stream
+    fn flush (&mut self) -> io::Result <()> {
+
+        match self.stream {
+            ConsoleStream::Stdout => io::stdout ().flush (),
+            ConsoleStream::Stderr => io::stderr ().flush ()
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns the ANSI color code used for a level
+fn level_color (level : LogLevel) -> &'static str {
+
+    match level {
+
+        LogLevel::Trace   => "90",
+        LogLevel::Debug   => "36",
+        LogLevel::Info    => "32",
+        LogLevel::Warning => "33",
+        LogLevel::Error   => "31",
+        LogLevel::Fatal   => "1;41"
+    }
+}
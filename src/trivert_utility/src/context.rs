@@ -0,0 +1,153 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use Value;
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log context struct
+///
+/// It manages the fields of the current thread's scoped contexts. While a context is
+/// entered, its fields are attached to every record logged from the thread, ahead of the
+/// record's own fields. Contexts nest, and are left when their guard is dropped.
+#[derive (Copy, Clone)]
+pub struct LogContext;
+
+/*================================================================================================*/
+
+/// The context guard struct
+///
+/// It leaves a log context when dropped. Guards must be dropped in the reverse order they
+/// were created in, which happens naturally when they are kept in local variables.
+pub struct ContextGuard {
+
+    // Private
+    length  : usize,
+    phantom : PhantomData <*const ()> // Contexts belong to a thread, so guards can't be sent
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogContext {
+
+    /// Enters a context with the given fields, until the returned guard is dropped.
+    ///
+    /// This is normally called through the `trivert_context!` macro.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogContext, Value};
+    /// let _context = LogContext::enter (&[("level", Value::from ("forest")),
+    ///                                     ("seed",  Value::from (1234))]);
+    ///
+    /// assert_eq! (LogContext::fields ().len (), 2);
+    /// ```
+    pub fn enter (fields : &[(&str, Value)]) -> ContextGuard {
+
+        CONTEXT.with (|context| {
+
+            let mut context = context.borrow_mut ();
+            let length      = context.len ();
+
+            context.extend (fields.iter ().map (|&(key, ref value)| {
+                (key.to_string (), value.clone ())
+            }));
+
+            ContextGuard {length,
+                          phantom : PhantomData}
+        })
+    }
+
+/*================================================================================================*/
+
+    /// Returns the fields of every context the current thread is in, from outermost to
+    /// innermost.
+    pub fn fields () -> Vec <(String, Value)> {
+
+        CONTEXT.with (|context| context.borrow ().clone ())
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Enters a span on the current thread, returning the depth to leave it at
+pub fn push_span (name : &str) -> usize {
+
+    SPANS.with (|spans| {
+
+        let mut spans = spans.borrow_mut ();
+        let depth     = spans.len ();
+
+        spans.push (name.to_string ());
+        depth
+    })
+}
+
+/*================================================================================================*/
+
+// Leaves a span, along with any spans entered within it
+pub fn pop_span (depth : usize) {
+
+    SPANS.with (|spans| spans.borrow_mut ().truncate (depth));
+}
+
+/*================================================================================================*/
+
+// Returns the path of the current thread's spans (e.g. "load_level/decode_textures")
+pub fn span_path () -> Option <String> {
+
+    SPANS.with (|spans| {
+
+        let spans = spans.borrow ();
+
+        if spans.is_empty () {None} else {Some (spans.join ("/"))}
+    })
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Drop for ContextGuard {
+
+    // Removes the context's fields
+    fn drop (&mut self) {
+
+        CONTEXT.with (|context| context.borrow_mut ().truncate (self.length));
+    }
+}
+
+/*================================================================================================*/
+/*------STATICS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+thread_local! {
+
+    // The fields of the thread's entered contexts
+    static CONTEXT : RefCell <Vec <(String, Value)>> = const {RefCell::new (Vec::new ())};
+
+    // The names of the thread's entered spans, from outermost to innermost
+    static SPANS : RefCell <Vec <String>> = const {RefCell::new (Vec::new ())};
+}
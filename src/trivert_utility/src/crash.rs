@@ -0,0 +1,258 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate time;
+
+use {LogFilter, LogLevel, LogRecord, MemoryReader, MemorySink, SharedLogger};
+use shared_logger::log_to_all;
+
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::Write;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The crash handler struct
+///
+/// It is installed as the panic hook, and makes sure as much as possible is known about a
+/// panic. When a panic happens it:
+///
+/// * Logs the panic to every shared logger as a fatal error, and flushes them
+/// * Writes a crash report to its directory, with the panic message and location, a
+///   backtrace, the panicking thread, build info, and the latest records of attached loggers
+/// * Calls the previously installed panic hook (which prints the message by default)
+///
+/// Loggers which aren't shared can't be reached from the hook, so aren't flushed.
+/// Shared loggers which are locked are waited on for up to 100ms in total, and any still locked
+/// after that (such as by the panicking thread) are skipped.
+pub struct CrashHandler {
+
+    // Private
+    directory   : PathBuf,
+    app_name    : String,
+    app_version : String,
+    history     : Vec <MemoryReader>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl CrashHandler {
+
+    /// Creates a new crash handler, which writes crash reports to a directory.
+    ///
+    /// The directory is created when a report is written, if it doesn't already exist.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{CrashHandler, Logger, SharedLogger};
+    /// let logger = SharedLogger::new (Logger::new ("log.txt").unwrap ());
+    ///
+    /// let mut crash_handler = CrashHandler::new ("crashes");
+    ///
+    /// crash_handler.set_build_info (env! ("CARGO_PKG_NAME"), env! ("CARGO_PKG_VERSION"));
+    /// crash_handler.attach (&logger, 100);
+    /// crash_handler.install ();
+    /// ```
+    pub fn new (directory : &str) -> CrashHandler {
+
+        CrashHandler {directory   : PathBuf::from (directory),
+                      app_name    : "unknown".to_string (),
+                      app_version : "unknown".to_string (),
+                      history     : Vec::new ()}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets the application name and version included in crash reports.
+    pub fn set_build_info (&mut self, app_name : &str, app_version : &str) {
+
+        self.app_name    = app_name.to_string ();
+        self.app_version = app_version.to_string ();
+    }
+
+/*================================================================================================*/
+
+    /// Keeps the latest records of a logger, to be included in crash reports.
+    ///
+    /// This attaches a memory sink holding `record_count` records to the logger.
+    pub fn attach (&mut self, logger : &SharedLogger, record_count : usize) {
+
+        let sink = MemorySink::new (record_count);
+
+        self.history.push (sink.reader ());
+        logger.add_sink_with_filter (sink, LogFilter::new (Some (LogLevel::Trace)));
+    }
+
+/*================================================================================================*/
+
+    /// Installs the crash handler as the panic hook, replacing any previous crash handler.
+    ///
+    /// The hook which was installed before the first crash handler is still called after the
+    /// crash handler, but a crash handler being replaced is not.
+    pub fn install (self) {
+
+        *INSTALLED.lock ().unwrap_or_else (|error| error.into_inner ()) = Some (Arc::new (self));
+
+        HOOK.call_once (|| {
+
+            let previous_hook = panic::take_hook ();
+
+            panic::set_hook (Box::new (move |info| {
+
+                let handler = INSTALLED.lock ()
+                                       .unwrap_or_else (|error| error.into_inner ())
+                                       .clone ();
+
+                // Guard against the handler being re-entered if it panics itself
+                if let Some (handler) = handler {
+
+                    if !HANDLING.with (|handling| handling.replace (true)) {
+
+                        handler.handle (info);
+                        HANDLING.with (|handling| handling.set (false));
+                    }
+                }
+
+                previous_hook (info);
+            }));
+        });
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Logs the panic and writes the crash report
+    fn handle (&self, info : &panic::PanicHookInfo) {
+
+        let payload = info.payload ();
+        let message = payload.downcast_ref::<&str> ().map (|message| message.to_string ())
+                             .or_else (|| payload.downcast_ref::<String> ().cloned ())
+                             .unwrap_or_else (|| "Box<dyn Any>".to_string ());
+
+        let location = info.location ().map_or ("unknown location".to_string (), |location| {
+            format! ("{}:{}:{}", location.file (), location.line (), location.column ())
+        });
+
+        let record = LogRecord::new (LogLevel::Fatal, "", &format! ("Panicked at {}: {}",
+                                                                    location,
+                                                                    message));
+        log_to_all (&record);
+
+        let result     = self.write_report (&record, &message, &location);
+        let mut stderr = io::stderr ().lock ();
+
+        let _ = match result {
+            Ok (path)   => writeln! (stderr, "Crash report written to {}", path.display ()),
+            Err (error) => writeln! (stderr, "Crash report could not be written: {}", error)
+        };
+    }
+
+/*================================================================================================*/
+
+    // Writes a crash report file, returning its path
+    fn write_report (&self,
+                     record   : &LogRecord,
+                     message  : &str,
+                     location : &str) -> io::Result <PathBuf> {
+
+        fs::create_dir_all (&self.directory)?;
+
+        let path       = report_path (&self.directory, &time::at (record.time));
+        let mut report = io::BufWriter::new (File::create (&path)?);
+        let thread     = thread::current ();
+
+        let profile = if cfg! (debug_assertions) {"debug"} else {"release"};
+
+        writeln! (report, "---CRASH REPORT---\n")?;
+        writeln! (report, "Application : {} {}", self.app_name, self.app_version)?;
+        writeln! (report, "Build       : {} ({}, {})",
+                  profile, ::std::env::consts::OS, ::std::env::consts::ARCH)?;
+        writeln! (report, "Time        : {}", time::at (record.time).asctime ())?;
+        writeln! (report, "Thread      : {} ({:?})",
+                  thread.name ().unwrap_or ("unnamed"), thread.id ())?;
+        writeln! (report, "Location    : {}", location)?;
+        writeln! (report, "Message     : {}", message)?;
+
+        writeln! (report, "\n---BACKTRACE---\n")?;
+        writeln! (report, "{}", Backtrace::force_capture ())?;
+
+        for (index, history) in self.history.iter ().enumerate () {
+
+            writeln! (report, "\n---LOG HISTORY ({})---\n", index + 1)?;
+
+            for record in history.iter () {
+                writeln! (report, "{}", record)?;
+            }
+        }
+
+        writeln! (report, "\n---END CRASH REPORT---")?;
+        report.flush ()?;
+
+        Ok (path)
+    }
+}
+
+/*================================================================================================*/
+/*------STATICS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The crash handler called by the panic hook
+static INSTALLED : Mutex <Option <Arc <CrashHandler>>> = Mutex::new (None);
+
+// Installs the panic hook the first time a crash handler is installed
+static HOOK : Once = Once::new ();
+
+thread_local! {
+
+    // Set while the thread is handling a panic
+    static HANDLING : Cell <bool> = const {Cell::new (false)};
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns an unused path for a crash report made at the given time
+fn report_path (directory : &Path, tm : &time::Tm) -> PathBuf {
+
+    // The format is known to be valid
+    let stamp = time::strftime ("%Y-%m-%d_%H-%M-%S", tm).unwrap_or_default ();
+
+    let mut path  = directory.join (format! ("crash_{}.txt", stamp));
+    let mut count = 1;
+
+    while path.exists () {
+
+        path   = directory.join (format! ("crash_{}-{}.txt", stamp, count));
+        count += 1;
+    }
+
+    path
+}
@@ -0,0 +1,285 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {ParseConfigError, ParseFilterError};
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log error enum
+///
+/// It is returned when a log sink can't be created, or fails to write.
+#[derive (Debug)]
+pub enum LogError {
+
+    /// A log file could not be created
+    Create {
+
+        /// The path of the log file
+        path  : String,
+        /// The underlying error
+        error : io::Error
+    },
+
+    /// Writing to a sink failed
+    Io (io::Error)
+}
+
+/*================================================================================================*/
+
+/// The argument error enum
+///
+/// It is returned when command line arguments can't be parsed.
+#[derive (Clone, Debug, PartialEq)]
+pub enum ArgError {
+
+    /// An argument isn't a known option or command
+    Unknown (String),
+    /// An option was given without its value
+    MissingValue (String),
+    /// A flag was given a value
+    UnexpectedValue (String),
+    /// The log filter option is malformed
+    LogFilter (ParseFilterError)
+}
+
+/*================================================================================================*/
+
+/// The config error enum
+///
+/// It is returned when a config file can't be read, parsed or saved.
+#[derive (Debug)]
+pub enum ConfigError {
+
+    /// A config file could not be read or written
+    Io {
+
+        /// The path of the config file
+        path  : String,
+        /// The underlying error
+        error : io::Error
+    },
+
+    /// A config file is malformed
+    Parse {
+
+        /// The path of the config file
+        path  : String,
+        /// The underlying error
+        error : ParseConfigError
+    }
+}
+
+/*================================================================================================*/
+
+/// The VFS error enum
+///
+/// It is returned when a virtual file system can't mount a source, or access a path.
+#[derive (Debug)]
+pub enum VfsError {
+
+    /// A virtual path is malformed, or leaves the root
+    InvalidPath (String),
+    /// Nothing exists at a virtual path
+    NotFound (String),
+    /// A virtual path is a directory where a file was expected
+    IsDirectory (String),
+
+    /// A source failed to access a path
+    Io {
+
+        /// The virtual path, or the real path of a source being mounted
+        path  : String,
+        /// The underlying error
+        error : io::Error
+    }
+}
+
+/*================================================================================================*/
+
+/// The failure policy enum
+///
+/// It decides what a logger does when one of its sinks fails to write.
+/// The error is always kept, and can be checked through `Logger::last_error`.
+#[derive (Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+
+    /// Panic with the error
+    Panic,
+    /// Drop the message and carry on
+    Drop,
+    /// Write the message to the standard error stream instead (default)
+    #[default]
+    Stderr
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for LogError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            LogError::Create {ref path, ref error} =>
+                write! (f, "log file \"{}\" could not be created: {}", path, error),
+
+            LogError::Io (ref error) =>
+                write! (f, "log write failed: {}", error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for LogError {
+
+    // Returns the underlying error
+    fn source (&self) -> Option <&(dyn Error + 'static)> {
+
+        match *self {
+
+            LogError::Create {ref error, ..} => Some (error),
+            LogError::Io (ref error)         => Some (error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ArgError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            ArgError::Unknown (ref argument) =>
+                write! (f, "unknown argument \"{}\"", argument),
+
+            ArgError::MissingValue (ref argument) =>
+                write! (f, "argument \"{}\" requires a value", argument),
+
+            ArgError::UnexpectedValue (ref argument) =>
+                write! (f, "argument \"{}\" doesn't take a value", argument),
+
+            ArgError::LogFilter (ref error) => write! (f, "{}", error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ArgError {
+
+    // Returns the underlying error
+    fn source (&self) -> Option <&(dyn Error + 'static)> {
+
+        match *self {
+
+            ArgError::LogFilter (ref error) => Some (error),
+            _                               => None
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ConfigError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            ConfigError::Io {ref path, ref error} =>
+                write! (f, "config file \"{}\" could not be accessed: {}", path, error),
+
+            ConfigError::Parse {ref path, ref error} =>
+                write! (f, "config file \"{}\" is malformed: {}", path, error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ConfigError {
+
+    // Returns the underlying error
+    fn source (&self) -> Option <&(dyn Error + 'static)> {
+
+        match *self {
+
+            ConfigError::Io {ref error, ..}    => Some (error),
+            ConfigError::Parse {ref error, ..} => Some (error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for VfsError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            VfsError::InvalidPath (ref path) => write! (f, "invalid virtual path \"{}\"", path),
+            VfsError::NotFound (ref path)    => write! (f, "\"{}\" was not found", path),
+            VfsError::IsDirectory (ref path) => write! (f, "\"{}\" is a directory", path),
+
+            VfsError::Io {ref path, ref error} =>
+                write! (f, "\"{}\" could not be accessed: {}", path, error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for VfsError {
+
+    // Returns the underlying error
+    fn source (&self) -> Option <&(dyn Error + 'static)> {
+
+        match *self {
+
+            VfsError::Io {ref error, ..} => Some (error),
+            _                            => None
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl From <io::Error> for LogError {
+
+    // Wraps a write error
+    fn from (error : io::Error) -> LogError {
+
+        LogError::Io (error)
+    }
+}
@@ -0,0 +1,194 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate log;
+
+use {LogLevel, LogRecord, Logger, SharedLogger};
+
+use std::sync::MutexGuard;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log facade struct
+///
+/// It wraps a shared logger so it can be installed as the global logger of the `log` crate,
+/// allowing messages from dependencies using the `log` macros to end up in the same sinks as
+/// our own. The module path, file and line of each message are kept in the logged record.
+///
+/// Messages logged from inside a sink must not go through the facade, as the logger is
+/// already locked while sinks are being written to.
+pub struct LogFacade {
+
+    // Private
+    logger : SharedLogger
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogFacade {
+
+    /// Creates a new log facade for a logger or shared logger.
+    pub fn new<L : Into <SharedLogger>> (logger : L) -> LogFacade {
+
+        LogFacade {logger : logger.into ()}
+    }
+
+/*================================================================================================*/
+
+    /// Installs a logger as the global logger of the `log` crate.
+    ///
+    /// The facade lives for the rest of the program, and is returned so the logger can still
+    /// be accessed. Passing a shared logger allows it to keep being used directly as well.
+    /// This fails if a global logger has already been installed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogFacade, Logger, SharedLogger};
+    /// let logger = SharedLogger::new (Logger::new ("log.txt").unwrap ());
+    /// logger.begin_log ();
+    ///
+    /// LogFacade::install (logger.clone ()).unwrap ();
+    ///
+    /// // ...
+    ///
+    /// logger.end_log ();
+    /// ```
+    pub fn install<L : Into <SharedLogger>> (logger : L)
+        -> Result <&'static LogFacade, log::SetLoggerError> {
+
+        let facade : &'static LogFacade = Box::leak (Box::new (LogFacade::new (logger)));
+
+        log::set_logger (facade)?;
+        facade.update_max_level ();
+
+        Ok (facade)
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Locks the wrapped logger for direct access.
+    pub fn lock (&self) -> MutexGuard <'_, Logger> {
+
+        self.logger.lock ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the wrapped shared logger.
+    pub fn logger (&self) -> &SharedLogger {
+
+        &self.logger
+    }
+
+/*================================================================================================*/
+
+    /// Updates the maximum level of the `log` crate to match the logger's filter.
+    ///
+    /// The `log` macros skip anything above this level without calling the logger at all, so
+    /// this should be called after lowering the level of the logger's filter.
+    pub fn update_max_level (&self) {
+
+        log::set_max_level (to_level_filter (self.lock ().filter ().max_level ()));
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl log::Log for LogFacade {
+
+    // Checks if the logger would log a message
+    fn enabled (&self, metadata : &log::Metadata) -> bool {
+
+        self.lock ().is_enabled (LogLevel::from (metadata.level ()), metadata.target ())
+    }
+
+/*================================================================================================*/
+
+    // Logs a record from the log crate
+    fn log (&self, record : &log::Record) {
+
+        let level = LogLevel::from (record.level ());
+
+        // Format the message before locking, so other threads aren't kept waiting
+        if self.logger.is_enabled (level, record.target ()) {
+
+            let mut log_record = LogRecord::new (level,
+                                                 record.target (),
+                                                 &record.args ().to_string ());
+
+            log_record.module_path = record.module_path ().map (|path| path.to_string ());
+            log_record.file        = record.file ().map (|file| file.to_string ());
+            log_record.line        = record.line ();
+
+            self.logger.log_record (&log_record);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Flushes every sink
+    fn flush (&self) {
+
+        self.lock ().flush ();
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl From <log::Level> for LogLevel {
+
+    // Maps a log crate level onto the matching log level
+    fn from (level : log::Level) -> LogLevel {
+
+        match level {
+
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn  => LogLevel::Warning,
+            log::Level::Info  => LogLevel::Info,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Converts the most verbose level of a filter into a log crate level filter
+fn to_level_filter (level : Option <LogLevel>) -> log::LevelFilter {
+
+    match level {
+
+        Some (LogLevel::Trace)   => log::LevelFilter::Trace,
+        Some (LogLevel::Debug)   => log::LevelFilter::Debug,
+        Some (LogLevel::Info)    => log::LevelFilter::Info,
+        Some (LogLevel::Warning) => log::LevelFilter::Warn,
+        Some (LogLevel::Error)   => log::LevelFilter::Error,
+        Some (LogLevel::Fatal)   => log::LevelFilter::Error,
+        None                     => log::LevelFilter::Off
+    }
+}
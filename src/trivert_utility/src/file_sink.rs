@@ -0,0 +1,574 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate flate2;
+extern crate time;
+
+use {LogError, LogFormatter, LogRecord, LogSink, TextFormatter};
+
+use self::flate2::Compression;
+use self::flate2::write::GzEncoder;
+
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::BufWriter;
+use std::io::prelude::Write;
+use std::path::{Path, PathBuf};
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+const TIMESTAMP_FORMAT  : &str = "%Y-%m-%d_%H-%M-%S";
+const TIMESTAMP_PATTERN : &str = "dddd-dd-dd_dd-dd-dd"; // d matches any digit
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The archive naming enum
+///
+/// It decides how rotated log files are named.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveNaming {
+
+    /// Archives are numbered from newest to oldest (`log.txt.1`, `log.txt.2`, ...)
+    Numbered,
+    /// Archives are named after the time their file was started
+    /// (`log.txt.2016-06-01_12-00-00`)
+    Timestamped
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The file sink struct
+///
+/// It writes records to a file one line at a time, using the text formatter (with its begin
+/// and end markers) unless another formatter is set. Output is buffered, and only guaranteed
+/// to reach the disk once flushed.
+///
+/// The file can optionally be rotated, in which case the current file is archived and a new
+/// one started once it grows too large or a new day begins. Each file gets its own markers.
+pub struct FileSink {
+
+    // Private
+    path      : PathBuf,
+    file      : Option <BufWriter <File>>,
+    formatter : Box <dyn LogFormatter>,
+    line      : String,
+    rotation  : Option <Rotation>,
+    size      : u64,
+    opened    : time::Tm
+}
+
+/*================================================================================================*/
+
+/// The rotation struct
+///
+/// It describes when a file sink rotates its file, and what happens to the old files.
+#[derive (Copy, Clone, Debug, PartialEq)]
+pub struct Rotation {
+
+    // Public
+    /// Rotate once the file would grow past this many bytes
+    pub max_size : Option <u64>,
+    /// Rotate when the first message of a new (local) day is logged
+    pub daily    : bool,
+    /// The number of archives kept, with older archives deleted
+    pub keep     : usize,
+    /// How archives are named
+    pub naming   : ArchiveNaming,
+    /// Compress archives with gzip, adding a `.gz` extension
+    pub compress : bool
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl FileSink {
+
+    /// Creates a new file sink, replacing any existing file at the path.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::FileSink;
+    /// let sink = FileSink::new ("log.txt").unwrap ();
+    /// ```
+    pub fn new (path : &str) -> Result <FileSink, LogError> {
+
+        match File::create (path) {
+
+            Ok (file) => Ok (FileSink {path      : PathBuf::from (path),
+                                       file      : Some (BufWriter::new (file)),
+                                       formatter : Box::new (TextFormatter),
+                                       line      : String::new (),
+                                       rotation  : None,
+                                       size      : 0,
+                                       opened    : time::now ()}),
+
+            Err (error) => Err (LogError::Create {path : path.to_string (),
+                                                  error})
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new file sink which rotates its file.
+    ///
+    /// Any existing file at the path is archived first, so logs from previous runs are kept.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{FileSink, Rotation};
+    /// let mut rotation = Rotation::by_size (10 * 1024 * 1024);
+    ///
+    /// rotation.keep     = 10;
+    /// rotation.compress = true;
+    ///
+    /// let sink = FileSink::with_rotation ("server.log", rotation).unwrap ();
+    /// ```
+    pub fn with_rotation (path : &str, rotation : Rotation) -> Result <FileSink, LogError> {
+
+        let create_error = |error| LogError::Create {path : path.to_string (),
+                                                     error};
+
+        let mut sink = FileSink {path      : PathBuf::from (path),
+                                 file      : None,
+                                 formatter : Box::new (TextFormatter),
+                                 line      : String::new (),
+                                 rotation  : Some (rotation),
+                                 size      : 0,
+                                 opened    : time::now ()};
+
+        // Archive the previous run's log, naming it after when it was last written to
+        if let Ok (metadata) = fs::metadata (path) {
+
+            if let Ok (modified) = metadata.modified () {
+
+                if let Ok (since_epoch) = modified.duration_since (::std::time::UNIX_EPOCH) {
+
+                    let seconds = since_epoch.as_secs () as i64;
+                    sink.opened = time::at (time::Timespec::new (seconds, 0));
+                }
+            }
+
+            sink.archive ().map_err (create_error)?;
+        }
+
+        sink.open (false).map_err (create_error)?;
+        Ok (sink)
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets the formatter used to write records.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{FileSink, JsonFormatter};
+    /// let mut sink = FileSink::new ("log.jsonl").unwrap ();
+    /// sink.set_formatter (JsonFormatter);
+    /// ```
+    pub fn set_formatter<F : LogFormatter + 'static> (&mut self, formatter : F) {
+
+        self.formatter = Box::new (formatter);
+    }
+}
+
+/*================================================================================================*/
+
+impl Rotation {
+
+    /// Creates a rotation which rolls the file once it would grow past a size in bytes.
+    ///
+    /// Five numbered, uncompressed archives are kept.
+    pub fn by_size (max_size : u64) -> Rotation {
+
+        Rotation {max_size : Some (max_size),
+                  .. Rotation::default ()}
+    }
+
+/*================================================================================================*/
+
+    /// Creates a rotation which rolls the file every day.
+    ///
+    /// Seven timestamped, uncompressed archives are kept.
+    pub fn daily () -> Rotation {
+
+        Rotation {daily  : true,
+                  keep   : 7,
+                  naming : ArchiveNaming::Timestamped,
+                  .. Rotation::default ()}
+    }
+}
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl FileSink {
+
+    // Returns the open file
+    fn file (&mut self) -> io::Result <&mut BufWriter <File>> {
+
+        self.file.as_mut ().ok_or_else (not_open)
+    }
+
+/*================================================================================================*/
+
+    // Writes the formatter's begin or end marker
+    fn write_marker (&mut self, is_begin : bool) -> io::Result <()> {
+
+        let marker = if is_begin {self.formatter.begin_marker ()}
+                     else        {self.formatter.end_marker ()};
+
+        match self.file {
+            Some (ref mut file) => file.write_all (marker.as_bytes ())?,
+            None                => return Err (not_open ())
+        }
+
+        self.size += marker.len () as u64;
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Opens the log file, either replacing or appending to any existing file
+    fn open (&mut self, append : bool) -> io::Result <()> {
+
+        let file = OpenOptions::new ().write (true)
+                                      .create (true)
+                                      .append (append)
+                                      .truncate (!append)
+                                      .open (&self.path)?;
+
+        self.size   = file.metadata ()?.len ();
+        self.opened = time::now ();
+        self.file   = Some (BufWriter::new (file));
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Checks if the file needs rotating before writing a line
+    fn needs_rotation (&self, record : &LogRecord, line_length : usize) -> bool {
+
+        match self.rotation {
+
+            Some (ref rotation) => {
+
+                let too_large = rotation.max_size.is_some_and (|max_size| {
+                    self.size > 0 && self.size + line_length as u64 > max_size
+                });
+
+                let new_day = rotation.daily && {
+                    let now = time::at (record.time);
+                    now.tm_yday != self.opened.tm_yday || now.tm_year != self.opened.tm_year
+                };
+
+                too_large || new_day
+            },
+
+            None => false
+        }
+    }
+
+/*================================================================================================*/
+
+    // Writes a formatted record, rotating the file first if needed
+    fn write_line (&mut self, record : &LogRecord, line : &str) -> io::Result <()> {
+
+        if self.needs_rotation (record, line.len ()) {
+            self.rotate ()?;
+        }
+
+        self.file ()?.write_all (line.as_bytes ())?;
+        self.size += line.len () as u64;
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Closes the current file with an end marker, archives it and starts a new one
+    fn rotate (&mut self) -> io::Result <()> {
+
+        self.write_marker (false)?;
+        self.file ()?.flush ()?;
+
+        // Close the file before moving it, as open files can't be moved on every platform
+        self.file = None;
+
+        let archived = self.archive ();
+
+        // Carry on with the old file if it couldn't be archived, so messages aren't lost
+        self.open (archived.is_err ())?;
+
+        self.write_marker (true)?;
+
+        archived
+    }
+
+/*================================================================================================*/
+
+    // Moves the current file into the archive, deleting any archives no longer kept
+    fn archive (&mut self) -> io::Result <()> {
+
+        let rotation = match self.rotation {
+            Some (rotation) => rotation,
+            None            => return Ok (())
+        };
+
+        if rotation.keep == 0 {
+            return fs::remove_file (&self.path);
+        }
+
+        let extension = if rotation.compress {".gz"} else {""};
+
+        let destination = match rotation.naming {
+
+            ArchiveNaming::Numbered => {
+
+                // Shift every archive back by one, dropping the oldest
+                remove_if_exists (&self.numbered_path (rotation.keep, extension))?;
+
+                for number in (1 .. rotation.keep).rev () {
+
+                    let source = self.numbered_path (number, extension);
+
+                    if source.exists () {
+                        fs::rename (&source, self.numbered_path (number + 1, extension))?;
+                    }
+                }
+
+                self.numbered_path (1, extension)
+            },
+
+            ArchiveNaming::Timestamped => self.timestamped_path (extension)
+        };
+
+        if rotation.compress {
+            compress (&self.path, &destination)?;
+        }
+
+        else {
+            fs::rename (&self.path, &destination)?;
+        }
+
+        if rotation.naming == ArchiveNaming::Timestamped {
+            self.remove_old_timestamped (rotation.keep)?;
+        }
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Returns the path of a numbered archive
+    fn numbered_path (&self, number : usize, extension : &str) -> PathBuf {
+
+        let mut path = self.path.clone ().into_os_string ();
+        path.push (format! (".{}{}", number, extension));
+
+        PathBuf::from (path)
+    }
+
+/*================================================================================================*/
+
+    // Returns an unused path for a timestamped archive of the current file
+    fn timestamped_path (&self, extension : &str) -> PathBuf {
+
+        // The format is known to be valid
+        let stamp = time::strftime (TIMESTAMP_FORMAT, &self.opened).unwrap_or_default ();
+
+        let mut count = 0;
+
+        loop {
+
+            let mut path = self.path.clone ().into_os_string ();
+
+            match count {
+                0 => path.push (format! (".{}{}", stamp, extension)),
+                _ => path.push (format! (".{}-{}{}", stamp, count, extension))
+            }
+
+            let path = PathBuf::from (path);
+
+            if !path.exists () {
+                return path;
+            }
+
+            count += 1;
+        }
+    }
+
+/*================================================================================================*/
+
+    // Deletes the oldest timestamped archives, keeping the given number
+    fn remove_old_timestamped (&self, keep : usize) -> io::Result <()> {
+
+        let file_name = match self.path.file_name ().and_then (|name| name.to_str ()) {
+            Some (name) => format! ("{}.", name),
+            None        => return Ok (())
+        };
+
+        let directory = match self.path.parent () {
+            Some (parent) if !parent.as_os_str ().is_empty () => parent.to_path_buf (),
+            _                                                  => PathBuf::from (".")
+        };
+
+        let mut archives = Vec::new ();
+
+        for entry in fs::read_dir (&directory)? {
+
+            let entry = entry?;
+            let name  = entry.file_name ().to_string_lossy ().into_owned ();
+
+            if name.starts_with (&file_name) && is_timestamp (&name[file_name.len () ..]) {
+                archives.push (name);
+            }
+        }
+
+        // Timestamps sort oldest first
+        archives.sort ();
+
+        let excess = archives.len ().saturating_sub (keep);
+
+        for name in &archives[.. excess] {
+            remove_if_exists (&directory.join (name))?;
+        }
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Default for Rotation {
+
+    // Never rotates, keeping five numbered archives once enabled
+    fn default () -> Rotation {
+
+        Rotation {max_size : None,
+                  daily    : false,
+                  keep     : 5,
+                  naming   : ArchiveNaming::Numbered,
+                  compress : false}
+    }
+}
+
+/*================================================================================================*/
+
+impl LogSink for FileSink {
+
+    // Writes the record as a single line, rotating the file first if needed
+    fn write (&mut self, record : &LogRecord) -> io::Result <()> {
+
+        // The line buffer is reused between records to avoid allocating
+        let mut line = ::std::mem::take (&mut self.line);
+
+        line.clear ();
+        self.formatter.format (record, &mut line);
+        line.push ('\n');
+
+        let result = self.write_line (record, &line);
+
+        self.line = line;
+        result
+    }
+
+/*================================================================================================*/
+
+    // Flushes the file buffer
+    fn flush (&mut self) -> io::Result <()> {
+
+        self.file ()?.flush ()
+    }
+
+/*================================================================================================*/
+
+    // Writes the begin marker
+    fn begin (&mut self) -> io::Result <()> {
+
+        self.write_marker (true)
+    }
+
+/*================================================================================================*/
+
+    // Writes the end marker
+    fn end (&mut self) -> io::Result <()> {
+
+        self.write_marker (false)
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Compresses a file with gzip, removing the original
+fn compress (source : &Path, destination : &Path) -> io::Result <()> {
+
+    let mut input   = File::open (source)?;
+    let mut encoder = GzEncoder::new (File::create (destination)?, Compression::default ());
+
+    io::copy (&mut input, &mut encoder)?;
+    encoder.finish ()?;
+
+    fs::remove_file (source)
+}
+
+/*================================================================================================*/
+
+// Returns the error used when the log file isn't open
+fn not_open () -> io::Error {
+
+    io::Error::new (io::ErrorKind::NotConnected, "log file is not open")
+}
+
+/*================================================================================================*/
+
+// Removes a file, ignoring it if it doesn't exist
+fn remove_if_exists (path : &Path) -> io::Result <()> {
+
+    match fs::remove_file (path) {
+        Err (ref error) if error.kind () == io::ErrorKind::NotFound => Ok (()),
+        result                                                      => result
+    }
+}
+
+/*================================================================================================*/
+
+// Checks if an archive suffix starts with a timestamp (e.g. "2016-06-01_12-00-00.gz")
+fn is_timestamp (suffix : &str) -> bool {
+
+    suffix.len () >= TIMESTAMP_PATTERN.len () &&
+    suffix.bytes ().zip (TIMESTAMP_PATTERN.bytes ()).all (|(character, expected)| {
+        match expected {
+            b'd' => character.is_ascii_digit (),
+            _    => character == expected
+        }
+    })
+}
@@ -0,0 +1,925 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+extern crate inotify;
+
+use {Vfs, VfsError};
+
+#[cfg (target_os = "linux")]
+use self::inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+
+use std::collections::BTreeMap;
+#[cfg (target_os = "linux")]
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/*================================================================================================*/
+/*------TYPES-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The state of every watched file, by its real and virtual paths (a file can be watched at
+// several virtual paths, and several files can share a virtual path)
+type Snapshot = BTreeMap <(PathBuf, String), FileState>;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The file watcher struct
+///
+/// It watches directories on disk for changes, and reports them by virtual path, so the asset
+/// system can reload what was edited without restarting the game.
+///
+/// On Linux, changes are picked up through inotify. Elsewhere, or if inotify can't be used
+/// (such as when the watch limit is reached), the directories are scanned for changes every
+/// poll interval instead.
+///
+/// Changes are debounced, so a file is only reported once it has stopped changing for the
+/// debounce duration. An editor saving a file in several writes, or by replacing it, gives a
+/// single change.
+///
+/// Symlinks to directories aren't followed, so a link back to a parent directory can't make
+/// the watcher loop.
+///
+/// The watcher doesn't run a thread of its own, so `poll` should be called regularly, such as
+/// once a frame.
+///
+/// # Examples
+/// ```no_run
+/// # use trivert_utility::{FileWatcher, Vfs};
+/// let mut vfs = Vfs::new ();
+/// vfs.mount_directory ("", "assets", 0).unwrap ();
+///
+/// let mut watcher = FileWatcher::new ();
+/// watcher.watch_vfs (&vfs).unwrap ();
+///
+/// loop {
+///     for change in watcher.poll () {
+///         println! ("{} was {:?}", change.path, change.kind);
+///     }
+/// }
+/// ```
+pub struct FileWatcher {
+
+    // Private
+    backend  : Backend,
+    roots    : Vec <WatchedDirectory>,
+    debounce : Duration,
+    pending  : BTreeMap <String, PendingChange>
+}
+
+/*================================================================================================*/
+
+/// The file change struct
+///
+/// It is a change to a file in a watched directory. Directories aren't reported themselves,
+/// but each file inside one is when it is added or removed.
+#[derive (Clone, Debug, PartialEq, Eq)]
+pub struct FileChange {
+
+    // Public
+    /// The virtual path of the file.
+    pub path : String,
+    /// What happened to the file.
+    pub kind : FileChangeKind
+}
+
+/*================================================================================================*/
+
+// A directory being watched, and the virtual path it is watched at
+#[derive (Clone, PartialEq, Eq)]
+struct WatchedDirectory {
+
+    real  : PathBuf,
+    point : String
+}
+
+/*================================================================================================*/
+
+// A change waiting for its file to settle
+#[derive (Copy, Clone)]
+struct PendingChange {
+
+    kind       : FileChangeKind,
+    last_event : Instant
+}
+
+/*================================================================================================*/
+
+// Picks up changes through inotify
+#[cfg (target_os = "linux")]
+struct InotifyBackend {
+
+    inotify : Inotify,
+    watches : HashMap <WatchDescriptor, Vec <WatchedDirectory>>,
+    roots   : Vec <WatchedDirectory>,
+    files   : Snapshot,
+    buffer  : Vec <u8>
+}
+
+/*================================================================================================*/
+
+// Picks up changes by scanning the watched directories
+struct PollingBackend {
+
+    interval  : Duration,
+    last_scan : Option <Instant>,
+    roots     : Vec <WatchedDirectory>,
+    files     : Snapshot
+}
+
+/*================================================================================================*/
+
+// The state of a file the last time it was seen
+#[derive (Copy, Clone, PartialEq, Eq)]
+struct FileState {
+
+    size     : u64,
+    modified : Option <SystemTime>
+}
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The file change kind enum
+///
+/// It describes what happened to a file.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FileChangeKind {
+
+    /// The file was created, or moved into a watched directory.
+    Created,
+    /// The file was written to, or replaced.
+    Modified,
+    /// The file was removed, or moved out of a watched directory.
+    Removed
+}
+
+/*================================================================================================*/
+
+/// The watch backend enum
+///
+/// It is how a file watcher picks up changes.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WatchBackend {
+
+    /// Changes are reported by the kernel through inotify (Linux only).
+    Inotify,
+    /// The watched directories are scanned for changes every poll interval.
+    Polling
+}
+
+/*================================================================================================*/
+
+// The backend in use by a file watcher
+enum Backend {
+
+    #[cfg (target_os = "linux")]
+    Inotify (InotifyBackend),
+    Polling (PollingBackend)
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// How long a file has to stop changing for before it is reported, by default
+const DEFAULT_DEBOUNCE : Duration = Duration::from_millis (200);
+
+// How often the watched directories are scanned by the polling backend, by default
+const DEFAULT_POLL_INTERVAL : Duration = Duration::from_millis (500);
+
+// The size of the buffer inotify events are read into
+#[cfg (target_os = "linux")]
+const EVENT_BUFFER_SIZE : usize = 4096;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl FileWatcher {
+
+    /// Creates a new file watcher, using inotify if it is available, and polling otherwise.
+    pub fn new () -> FileWatcher {
+
+        FileWatcher::with_backend (Backend::native ())
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new file watcher which scans the watched directories every interval.
+    pub fn polling (interval : Duration) -> FileWatcher {
+
+        FileWatcher::with_backend (Backend::Polling (PollingBackend::new (interval)))
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Watches a directory on disk, and every directory inside it, reporting its files as
+    /// being at a virtual path.
+    ///
+    /// Watching a directory which is already watched at the same virtual path does nothing.
+    pub fn watch<P : AsRef <Path>> (&mut self, point : &str, directory : P)
+        -> Result <(), VfsError> {
+
+        let directory = directory.as_ref ();
+        let point     = Vfs::normalize (point)?;
+
+        if self.roots.iter ().any (|root| root.real == directory && root.point == point) {
+            return Ok (());
+        }
+
+        match fs::metadata (directory) {
+
+            Ok (metadata) if metadata.is_dir () => {},
+            Ok (_)                              => {
+                return Err (real_error (directory, io::Error::other ("not a directory")));
+            },
+
+            Err (error)                         => return Err (real_error (directory, error))
+        }
+
+        let root = WatchedDirectory {real : directory.to_path_buf (),
+                                     point};
+
+        if let Err (error) = self.backend.add (&root) {
+
+            // Fall back to polling, as inotify can fail where scanning doesn't
+            if self.backend () == WatchBackend::Polling {
+                return Err (real_error (directory, error));
+            }
+
+            self.fall_back (&root)?;
+        }
+
+        self.roots.push (root);
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    /// Watches every directory mounted in a virtual file system, returning how many there are.
+    ///
+    /// Directories mounted later need to be watched separately, or by calling this again.
+    pub fn watch_vfs (&mut self, vfs : &Vfs) -> Result <usize, VfsError> {
+
+        let mounts = vfs.real_mounts ();
+
+        for &(point, ref root) in &mounts {
+            self.watch (point, root)?;
+        }
+
+        Ok (mounts.len ())
+    }
+
+/*================================================================================================*/
+
+    /// Stops watching every directory, dropping any changes which haven't been reported yet.
+    pub fn unwatch_all (&mut self) {
+
+        self.backend = match self.backend {
+
+            #[cfg (target_os = "linux")]
+            Backend::Inotify (_)               => Backend::native (),
+            Backend::Polling (ref backend)     => {
+                Backend::Polling (PollingBackend::new (backend.interval))
+            }
+        };
+
+        self.roots.clear ();
+        self.pending.clear ();
+    }
+
+/*================================================================================================*/
+
+    /// Returns the watched directories, with the virtual paths they are watched at.
+    pub fn watched (&self) -> Vec <(&str, &Path)> {
+
+        self.roots.iter ().map (|root| (root.point.as_str (), root.real.as_path ())).collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns how changes are being picked up.
+    pub fn backend (&self) -> WatchBackend {
+
+        match self.backend {
+
+            #[cfg (target_os = "linux")]
+            Backend::Inotify (_) => WatchBackend::Inotify,
+            Backend::Polling (_) => WatchBackend::Polling
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns how long a file has to stop changing for before it is reported.
+    pub fn debounce (&self) -> Duration {
+
+        self.debounce
+    }
+
+/*================================================================================================*/
+
+    /// Sets how long a file has to stop changing for before it is reported (200ms by default).
+    pub fn set_debounce (&mut self, debounce : Duration) {
+
+        self.debounce = debounce;
+    }
+
+/*================================================================================================*/
+
+    /// Checks if there are changes waiting for their files to settle.
+    pub fn has_pending (&self) -> bool {
+
+        !self.pending.is_empty ()
+    }
+
+/*================================================================================================*/
+
+    /// Picks up any new changes, and returns the ones whose files have settled, sorted by path.
+    ///
+    /// Several changes to a file are merged into one, so a file which is created then written
+    /// to is reported as created, and one which is removed then created again (as some editors
+    /// do when saving) is reported as modified. A file which is created then removed again
+    /// before settling isn't reported at all.
+    pub fn poll (&mut self) -> Vec <FileChange> {
+
+        let now        = Instant::now ();
+        let mut events = Vec::new ();
+
+        match self.backend {
+
+            #[cfg (target_os = "linux")]
+            Backend::Inotify (ref mut backend) => backend.read (&mut events),
+            Backend::Polling (ref mut backend) => backend.read (now, &mut events)
+        }
+
+        for (path, kind) in events {
+            self.record (path, kind, now);
+        }
+
+        let debounce = self.debounce;
+        let settled : Vec <String> = self.pending
+                                         .iter ()
+                                         .filter (|&(_, change)| {
+                                             now.duration_since (change.last_event) >= debounce
+                                         })
+                                         .map (|(path, _)| path.clone ())
+                                         .collect ();
+
+        settled.into_iter ()
+               .filter_map (|path| {
+                   self.pending.remove (&path).map (|change| FileChange {path,
+                                                                         kind : change.kind})
+               })
+               .collect ()
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Creates a new file watcher with a backend
+    fn with_backend (backend : Backend) -> FileWatcher {
+
+        FileWatcher {backend,
+                     roots    : Vec::new (),
+                     debounce : DEFAULT_DEBOUNCE,
+                     pending  : BTreeMap::new ()}
+    }
+
+/*================================================================================================*/
+
+    // Switches to the polling backend, watching every root again along with a new one
+    fn fall_back (&mut self, root : &WatchedDirectory) -> Result <(), VfsError> {
+
+        let mut backend = PollingBackend::new (DEFAULT_POLL_INTERVAL);
+
+        for root in self.roots.iter ().chain (Some (root)) {
+            backend.add (root).map_err (|error| real_error (&root.real, error))?;
+        }
+
+        self.backend = Backend::Polling (backend);
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Merges a change into the one waiting for its file to settle
+    fn record (&mut self, path : String, kind : FileChangeKind, now : Instant) {
+
+        let merged = match self.pending.get (&path) {
+
+            Some (pending) => merge (pending.kind, kind),
+            None           => Some (kind)
+        };
+
+        match merged {
+
+            Some (kind) => {
+                self.pending.insert (path, PendingChange {kind,
+                                                          last_event : now});
+            },
+
+            None        => {
+                self.pending.remove (&path);
+            }
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl Backend {
+
+    // Creates the best backend for the platform
+    #[cfg (target_os = "linux")]
+    fn native () -> Backend {
+
+        match InotifyBackend::new () {
+
+            Ok (backend) => Backend::Inotify (backend),
+            Err (_)      => Backend::Polling (PollingBackend::new (DEFAULT_POLL_INTERVAL))
+        }
+    }
+
+/*================================================================================================*/
+
+    // Creates the best backend for the platform
+    #[cfg (not (target_os = "linux"))]
+    fn native () -> Backend {
+
+        Backend::Polling (PollingBackend::new (DEFAULT_POLL_INTERVAL))
+    }
+
+/*================================================================================================*/
+
+    // Starts watching a directory
+    fn add (&mut self, root : &WatchedDirectory) -> io::Result <()> {
+
+        match *self {
+
+            #[cfg (target_os = "linux")]
+            Backend::Inotify (ref mut backend) => backend.add (root),
+            Backend::Polling (ref mut backend) => backend.add (root)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+impl InotifyBackend {
+
+    // Creates a new inotify instance, with nothing watched
+    fn new () -> io::Result <InotifyBackend> {
+
+        Ok (InotifyBackend {inotify : Inotify::init ()?,
+                            watches : HashMap::new (),
+                            roots   : Vec::new (),
+                            files   : Snapshot::new (),
+                            buffer  : vec! [0; EVENT_BUFFER_SIZE]})
+    }
+
+/*================================================================================================*/
+
+    // Watches a directory, taking the files in it as they are now
+    fn add (&mut self, root : &WatchedDirectory) -> io::Result <()> {
+
+        let mut files = Snapshot::new ();
+
+        self.watch_tree (&root.real, &root.point, &mut files)?;
+        self.files.extend (files);
+
+        self.roots.push (root.clone ());
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Reads every event which is waiting, without blocking
+    fn read (&mut self, events : &mut Vec <(String, FileChangeKind)>) {
+
+        let mut overflowed = false;
+
+        // Reading fails with `WouldBlock` once there are no more events
+        while let Ok (read) = self.inotify.read_events (&mut self.buffer) {
+
+            let read : Vec <(WatchDescriptor, EventMask, Option <String>)> =
+                read.map (|event| {
+                        let name = event.name.and_then (|name| name.to_str ()).map (String::from);
+                        (event.wd, event.mask, name)
+                    })
+                    .collect ();
+
+            for (descriptor, mask, name) in read {
+
+                if mask.contains (EventMask::Q_OVERFLOW) {
+                    overflowed = true;
+                    continue;
+                }
+
+                if mask.contains (EventMask::IGNORED) {
+                    self.watches.remove (&descriptor);
+                    continue;
+                }
+
+                let (directories, name) = match (self.watches.get (&descriptor), name) {
+
+                    (Some (directories), Some (name)) => (directories.clone (), name),
+                    _                                 => continue
+                };
+
+                // A directory watched at several virtual paths reports the event at each
+                for directory in directories {
+                    self.handle (&directory.real.join (&name),
+                                 &join (&directory.point, &name),
+                                 mask,
+                                 events);
+                }
+            }
+        }
+
+        if overflowed {
+            self.rescan (events);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Watches a directory and every directory inside it, adding the state of each file found
+    fn watch_tree (&mut self, directory : &Path, point : &str, files : &mut Snapshot)
+        -> io::Result <()> {
+
+        let mask = WatchMask::CREATE | WatchMask::MODIFY | WatchMask::CLOSE_WRITE |
+                   WatchMask::DELETE | WatchMask::MOVED_FROM | WatchMask::MOVED_TO |
+                   WatchMask::ONLYDIR;
+
+        // Watching a directory again gives the same descriptor, so it is kept for every path
+        let descriptor  = self.inotify.watches ().add (directory, mask)?;
+        let watched     = WatchedDirectory {real  : directory.to_path_buf (),
+                                            point : point.to_string ()};
+        let directories = self.watches.entry (descriptor).or_default ();
+
+        if !directories.contains (&watched) {
+            directories.push (watched);
+        }
+
+        for entry in fs::read_dir (directory)? {
+
+            let entry = entry?;
+
+            let (name, file_type) = match (entry.file_name ().into_string (), entry.file_type ()) {
+                (Ok (name), Ok (file_type)) => (name, file_type),
+                _                           => continue
+            };
+
+            let path = join (point, &name);
+
+            if file_type.is_dir () {
+                self.watch_tree (&entry.path (), &path, files)?;
+            } else if let Some (state) = file_state (&entry.path ()) {
+                files.insert ((entry.path (), path), state);
+            }
+        }
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Handles an event for a file or directory, given its real and virtual paths
+    fn handle (&mut self,
+               real   : &Path,
+               path   : &str,
+               mask   : EventMask,
+               events : &mut Vec <(String, FileChangeKind)>) {
+
+        let is_dir = mask.contains (EventMask::ISDIR);
+
+        if mask.intersects (EventMask::DELETE | EventMask::MOVED_FROM) {
+
+            // A directory takes every file inside it with it
+            let removed : Vec <(PathBuf, String)> = self.files
+                                                        .keys ()
+                                                        .filter (|&(file, file_path)| {
+                                                            file.starts_with (real) &&
+                                                            is_inside (path, file_path)
+                                                        })
+                                                        .cloned ()
+                                                        .collect ();
+
+            for key in removed {
+
+                self.files.remove (&key);
+                events.push ((key.1, FileChangeKind::Removed));
+            }
+
+            if is_dir {
+                self.unwatch (real, path);
+            }
+
+            return;
+        }
+
+        if is_dir {
+
+            // The directory may have gone again already, in which case its removal follows
+            if mask.intersects (EventMask::CREATE | EventMask::MOVED_TO) {
+
+                let mut files = Snapshot::new ();
+
+                self.watch_tree (real, path, &mut files).ok ();
+
+                for (key, state) in files {
+                    self.update (key, state, events);
+                }
+            }
+
+            return;
+        }
+
+        // A file which has gone again already has its removal reported by a later event
+        if let Some (state) = file_state (real) {
+            self.update ((real.to_path_buf (), path.to_string ()), state, events);
+        }
+    }
+
+/*================================================================================================*/
+
+    // Records the state of a file, reporting it as created if it wasn't known before
+    fn update (&mut self,
+               key    : (PathBuf, String),
+               state  : FileState,
+               events : &mut Vec <(String, FileChangeKind)>) {
+
+        let kind = match self.files.insert (key.clone (), state) {
+            Some (_) => FileChangeKind::Modified,
+            None     => FileChangeKind::Created
+        };
+
+        events.push ((key.1, kind));
+    }
+
+/*================================================================================================*/
+
+    // Stops watching a directory which was moved away or removed, and every directory inside it
+    fn unwatch (&mut self, real : &Path, path : &str) {
+
+        let mut unused = Vec::new ();
+
+        for (descriptor, directories) in &mut self.watches {
+
+            directories.retain (|directory| {
+                !(directory.real.starts_with (real) && is_inside (path, &directory.point))
+            });
+
+            if directories.is_empty () {
+                unused.push (descriptor.clone ());
+            }
+        }
+
+        for descriptor in unused {
+
+            self.watches.remove (&descriptor);
+            self.inotify.watches ().remove (descriptor).ok ();
+        }
+    }
+
+/*================================================================================================*/
+
+    // Watches every root again after events were lost, comparing the files to the last known
+    fn rescan (&mut self, events : &mut Vec <(String, FileChangeKind)>) {
+
+        let mut files = Snapshot::new ();
+
+        // Directories moved away while events were lost don't get watched at their old paths
+        self.watches.clear ();
+
+        for root in self.roots.clone () {
+            self.watch_tree (&root.real, &root.point, &mut files).ok ();
+        }
+
+        compare (&self.files, &files, events);
+        self.files = files;
+    }
+}
+
+/*================================================================================================*/
+
+impl PollingBackend {
+
+    // Creates a new polling backend, with nothing watched
+    fn new (interval : Duration) -> PollingBackend {
+
+        PollingBackend {interval,
+                        last_scan : None,
+                        roots     : Vec::new (),
+                        files     : Snapshot::new ()}
+    }
+
+/*================================================================================================*/
+
+    // Watches a directory, taking the files in it as they are now
+    fn add (&mut self, root : &WatchedDirectory) -> io::Result <()> {
+
+        scan (&root.real, &root.point, &mut self.files)?;
+
+        self.roots.push (root.clone ());
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    // Scans the watched directories if the interval has passed, comparing them to the last scan
+    fn read (&mut self, now : Instant, events : &mut Vec <(String, FileChangeKind)>) {
+
+        if self.last_scan.is_some_and (|last_scan| now.duration_since (last_scan) < self.interval) {
+            return;
+        }
+
+        self.last_scan = Some (now);
+
+        // A directory which can't be scanned has had everything in it removed
+        let mut files = Snapshot::new ();
+
+        for root in &self.roots {
+            scan (&root.real, &root.point, &mut files).ok ();
+        }
+
+        compare (&self.files, &files, events);
+        self.files = files;
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Default for FileWatcher {
+
+    // Creates a new file watcher, using inotify if it is available
+    fn default () -> FileWatcher {
+
+        FileWatcher::new ()
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<'a> From <&'a fs::Metadata> for FileState {
+
+    // Copies the size and modification time of a file
+    fn from (metadata : &'a fs::Metadata) -> FileState {
+
+        FileState {size     : metadata.len (),
+                   modified : metadata.modified ().ok ()}
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Merges two changes to a file, or returns `None` if they cancel out
+fn merge (previous : FileChangeKind, next : FileChangeKind) -> Option <FileChangeKind> {
+
+    match (previous, next) {
+
+        (FileChangeKind::Created, FileChangeKind::Removed)  => None,
+        (FileChangeKind::Created, _)                        => Some (FileChangeKind::Created),
+        (FileChangeKind::Removed, FileChangeKind::Removed)  => Some (FileChangeKind::Removed),
+        (FileChangeKind::Removed, _)                        => Some (FileChangeKind::Modified),
+        (FileChangeKind::Modified, FileChangeKind::Created) => Some (FileChangeKind::Modified),
+        (FileChangeKind::Modified, _)                       => Some (next)
+    }
+}
+
+/*================================================================================================*/
+
+// Compares the files of two snapshots, reporting what changed between them
+fn compare (previous : &Snapshot,
+            current  : &Snapshot,
+            events   : &mut Vec <(String, FileChangeKind)>) {
+
+    for (key, state) in current {
+
+        match previous.get (key) {
+
+            None                                  => {
+                events.push ((key.1.clone (), FileChangeKind::Created));
+            },
+
+            Some (previous) if previous != state => {
+                events.push ((key.1.clone (), FileChangeKind::Modified));
+            },
+
+            Some (_)                              => {}
+        }
+    }
+
+    for key in previous.keys () {
+
+        if !current.contains_key (key) {
+            events.push ((key.1.clone (), FileChangeKind::Removed));
+        }
+    }
+}
+
+/*================================================================================================*/
+
+// Scans a directory and every directory inside it, adding the state of each file found
+fn scan (directory : &Path, point : &str, files : &mut Snapshot) -> io::Result <()> {
+
+    for entry in fs::read_dir (directory)? {
+
+        let entry = entry?;
+
+        let (name, file_type) = match (entry.file_name ().into_string (), entry.file_type ()) {
+            (Ok (name), Ok (file_type)) => (name, file_type),
+            _                           => continue
+        };
+
+        let path = join (point, &name);
+
+        // A directory which vanishes while being scanned is left out, like its files
+        if file_type.is_dir () {
+            scan (&entry.path (), &path, files).ok ();
+        } else if let Some (state) = file_state (&entry.path ()) {
+            files.insert ((entry.path (), path), state);
+        }
+    }
+
+    Ok (())
+}
+
+/*================================================================================================*/
+
+// Returns the state of a file, following symlinks
+//
+// Symlinks to directories give `None` like files which can't be read, as following them could
+// lead back into a parent directory forever.
+fn file_state (path : &Path) -> Option <FileState> {
+
+    match fs::metadata (path) {
+        Ok (ref metadata) if !metadata.is_dir () => Some (FileState::from (metadata)),
+        _                                        => None
+    }
+}
+
+/*================================================================================================*/
+
+// Checks if a virtual path is a directory or inside it
+#[cfg (target_os = "linux")]
+fn is_inside (directory : &str, path : &str) -> bool {
+
+    match path.strip_prefix (directory) {
+
+        Some (rest) => rest.is_empty () || rest.starts_with ('/'),
+        None        => false
+    }
+}
+
+/*================================================================================================*/
+
+// Joins a name onto a directory's virtual path
+fn join (directory : &str, name : &str) -> String {
+
+    if directory.is_empty () {
+        name.to_string ()
+    } else {
+        format! ("{}/{}", directory, name)
+    }
+}
+
+/*================================================================================================*/
+
+// Wraps an error from watching a real path
+fn real_error (path : &Path, error : io::Error) -> VfsError {
+
+    VfsError::Io {path : path.display ().to_string (),
+                  error}
+}
@@ -0,0 +1,251 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use LogLevel;
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log filter struct
+///
+/// It decides which messages get logged, based on their level and target (normally the module
+/// path the message was logged from). Each target can have its own minimum level, with the
+/// default level used for everything else. A level of `None` turns logging off.
+///
+/// Filters can be parsed from a string such as `"warn,renderer=debug,audio=off"`, where a bare
+/// level sets the default. Targets match themselves and any of their child modules, with the
+/// longest match winning.
+#[derive (Clone, Debug, PartialEq)]
+pub struct LogFilter {
+
+    // Private
+    default_level : Option <LogLevel>,
+    targets       : Vec <(String, Option <LogLevel>)>
+}
+
+/*================================================================================================*/
+
+/// The filter parse error struct
+///
+/// It is returned when a log filter string is malformed.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ParseFilterError {
+
+    // Private
+    directive : String
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogFilter {
+
+    /// Creates a new filter which logs everything at or above a level.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogFilter, LogLevel};
+    /// let filter = LogFilter::new (Some (LogLevel::Warning));
+    /// ```
+    pub fn new (default_level : Option <LogLevel>) -> LogFilter {
+
+        LogFilter {default_level,
+                   targets : Vec::new ()}
+    }
+
+/*================================================================================================*/
+
+    /// Parses a filter from a comma separated list of directives.
+    ///
+    /// Each directive is either a level (`info`), or a target and level (`renderer=warn`).
+    /// Levels are `trace`, `debug`, `info`, `warn`/`warning`, `error`, `fatal` and `off`.
+    /// Anything not mentioned uses the build default (see `LogFilter::default`).
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogFilter, LogLevel};
+    /// let filter = LogFilter::parse ("info,renderer=warn,audio=debug").unwrap ();
+    ///
+    /// assert! ( filter.is_enabled (LogLevel::Debug, "audio::mixer"));
+    /// assert! (!filter.is_enabled (LogLevel::Info,  "renderer"));
+    /// ```
+    pub fn parse (spec : &str) -> Result <LogFilter, ParseFilterError> {
+
+        let mut filter = LogFilter::default ();
+
+        for directive in spec.split (',').map (str::trim).filter (|d| !d.is_empty ()) {
+
+            let error = || ParseFilterError {directive : directive.to_string ()};
+            let mut parts = directive.splitn (2, '=');
+            let first     = parts.next ().unwrap_or ("").trim ();
+
+            match parts.next () {
+
+                Some (level) => {
+
+                    let level = parse_level (level.trim ()).ok_or_else (error)?;
+
+                    if first.is_empty () {
+                        return Err (error ());
+                    }
+
+                    filter.set_target_level (first, level);
+                },
+
+                None => filter.default_level = parse_level (first).ok_or_else (error)?
+            }
+        }
+
+        Ok (filter)
+    }
+
+/*================================================================================================*/
+
+    /// Parses a filter from an environment variable (e.g. `TRIVERT_LOG`).
+    ///
+    /// Returns the default filter if the variable is not set.
+    pub fn from_env (name : &str) -> Result <LogFilter, ParseFilterError> {
+
+        match env::var (name) {
+
+            Ok (spec) => LogFilter::parse (&spec),
+            Err (_)   => Ok (LogFilter::default ())
+        }
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Checks if a message of the given level and target passes the filter.
+    pub fn is_enabled (&self, level : LogLevel, target : &str) -> bool {
+
+        match self.level_for (target) {
+
+            Some (min_level) => level >= min_level,
+            None             => false
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the minimum level for a target, or `None` if logging is off for it.
+    pub fn level_for (&self, target : &str) -> Option <LogLevel> {
+
+        self.targets.iter ()
+                    .filter (|(name, _)| target_matches (name, target))
+                    .max_by_key (|(name, _)| name.len ())
+                    .map_or (self.default_level, |&(_, level)| level)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the most verbose level any target can log at, or `None` if all logging is off.
+    pub fn max_level (&self) -> Option <LogLevel> {
+
+        self.targets.iter ()
+                    .filter_map (|&(_, level)| level)
+                    .chain (self.default_level)
+                    .min ()
+    }
+
+/*================================================================================================*/
+
+    /// Sets the level used by targets without their own level.
+    pub fn set_default_level (&mut self, level : Option <LogLevel>) {
+
+        self.default_level = level;
+    }
+
+/*================================================================================================*/
+
+    /// Sets the level for a target and its children, replacing any previous level.
+    pub fn set_target_level (&mut self, target : &str, level : Option <LogLevel>) {
+
+        match self.targets.iter_mut ().find (|&&mut (ref name, _)| name == target) {
+
+            Some (entry) => entry.1 = level,
+            None         => self.targets.push ((target.to_string (), level))
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Default for LogFilter {
+
+    // Logs debug messages and above in debug builds, and info messages and above in release
+    fn default () -> LogFilter {
+
+        if cfg! (debug_assertions) {
+            return LogFilter::new (Some (LogLevel::Debug));
+        }
+
+        LogFilter::new (Some (LogLevel::Info))
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ParseFilterError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f, "invalid log filter directive \"{}\"", self.directive)
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ParseFilterError {}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Parses a level name, where `Some (None)` means logging is off
+fn parse_level (name : &str) -> Option <Option <LogLevel>> {
+
+    match name.to_lowercase ().as_str () {
+
+        "trace"            => Some (Some (LogLevel::Trace)),
+        "debug"            => Some (Some (LogLevel::Debug)),
+        "info"             => Some (Some (LogLevel::Info)),
+        "warn" | "warning" => Some (Some (LogLevel::Warning)),
+        "error"            => Some (Some (LogLevel::Error)),
+        "fatal"            => Some (Some (LogLevel::Fatal)),
+        "off"              => Some (None),
+        _                  => None
+    }
+}
+
+/*================================================================================================*/
+
+// Checks if a filter target covers a message target (itself or one of its child modules)
+pub fn target_matches (filter_target : &str, target : &str) -> bool {
+
+    target.starts_with (filter_target) &&
+    (target.len () == filter_target.len () || target[filter_target.len ()..].starts_with ("::"))
+}
@@ -0,0 +1,190 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::time::Duration;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The fixed timestep struct
+///
+/// It runs updates at a fixed rate, independant of the frame rate. Frame deltas are added to
+/// an accumulator, and a step is taken for every whole step size in it. The time left over is
+/// used to interpolate between the last two updates when rendering.
+///
+/// To stop a slow frame from causing more steps, which make the next frame slower still, the
+/// accumulator only holds a limited number of steps (five by default). Any extra time is
+/// dropped.
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use trivert_utility::FixedTimestep;
+/// let mut timestep = FixedTimestep::per_second (50);
+/// let mut updates  = 0;
+///
+/// timestep.accumulate (Duration::from_millis (50));
+///
+/// while timestep.step () {
+///     updates += 1; // Update physics by timestep.step_size () ...
+/// }
+///
+/// assert_eq! (updates, 2);
+/// assert_eq! (timestep.alpha (), 0.5);
+/// ```
+#[derive (Copy, Clone, Debug, PartialEq)]
+pub struct FixedTimestep {
+
+    // Private
+    step_size   : Duration,
+    accumulator : Duration,
+    max_steps   : Option <u32>,
+    dropped     : Duration
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl FixedTimestep {
+
+    /// Creates a new fixed timestep, taking steps of the given size.
+    ///
+    /// The step size must be longer than zero.
+    pub fn new (step_size : Duration) -> FixedTimestep {
+
+        assert! (step_size > Duration::from_secs (0), "The step size must be longer than zero");
+
+        FixedTimestep {step_size,
+                       accumulator : Duration::from_secs (0),
+                       max_steps   : Some (5),
+                       dropped     : Duration::from_secs (0)}
+    }
+
+/*================================================================================================*/
+
+    /// Creates a new fixed timestep, taking the given number of steps per second.
+    ///
+    /// The rate must be greater than zero.
+    pub fn per_second (rate : u32) -> FixedTimestep {
+
+        assert! (rate > 0, "The rate must be greater than zero");
+        FixedTimestep::new (Duration::from_secs (1) / rate)
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Adds a frame's delta to the accumulator, dropping any time over the maximum steps.
+    pub fn accumulate (&mut self, delta : Duration) {
+
+        self.accumulator += delta;
+
+        if let Some (max_steps) = self.max_steps {
+
+            let limit = self.step_size * max_steps;
+
+            if self.accumulator > limit {
+
+                self.dropped     += self.accumulator - limit;
+                self.accumulator  = limit;
+            }
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Takes a step if the accumulator holds one, returning `true` if a step was taken.
+    pub fn step (&mut self) -> bool {
+
+        if self.accumulator < self.step_size {
+            return false;
+        }
+
+        self.accumulator -= self.step_size;
+        true
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of steps waiting in the accumulator.
+    pub fn pending_steps (&self) -> u32 {
+
+        (self.accumulator.as_nanos () / self.step_size.as_nanos ()) as u32
+    }
+
+/*================================================================================================*/
+
+    /// Returns how far the accumulator is towards the next step, from `0.0` to `1.0`.
+    ///
+    /// This is used to interpolate between the last two updates when rendering.
+    pub fn alpha (&self) -> f64 {
+
+        self.accumulator.as_secs_f64 () / self.step_size.as_secs_f64 ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the size of each step.
+    pub fn step_size (&self) -> Duration {
+
+        self.step_size
+    }
+
+/*================================================================================================*/
+
+    /// Sets the size of each step, which must be longer than zero.
+    pub fn set_step_size (&mut self, step_size : Duration) {
+
+        assert! (step_size > Duration::from_secs (0), "The step size must be longer than zero");
+        self.step_size = step_size;
+    }
+
+/*================================================================================================*/
+
+    /// Sets the most steps the accumulator can hold, or `None` for no limit.
+    pub fn set_max_steps (&mut self, max_steps : Option <u32>) {
+
+        self.max_steps = max_steps;
+    }
+
+/*================================================================================================*/
+
+    /// Returns the most steps the accumulator can hold.
+    pub fn max_steps (&self) -> Option <u32> {
+
+        self.max_steps
+    }
+
+/*================================================================================================*/
+
+    /// Returns the total time dropped because the accumulator was full.
+    pub fn dropped_time (&self) -> Duration {
+
+        self.dropped
+    }
+
+/*================================================================================================*/
+
+    /// Clears the accumulator and the dropped time.
+    pub fn reset (&mut self) {
+
+        self.accumulator = Duration::from_secs (0);
+        self.dropped     = Duration::from_secs (0);
+    }
+}
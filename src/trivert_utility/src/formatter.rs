@@ -0,0 +1,582 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate time;
+
+use {LogRecord, Value};
+use record::{Fields, ThreadName};
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+
+/*================================================================================================*/
+/*------TRAITS------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log formatter trait
+///
+/// A formatter turns records into the text written by a sink, one line per record.
+pub trait LogFormatter : Send {
+
+    /// Appends a record to the output, without a trailing newline.
+    fn format (&self, record : &LogRecord, output : &mut String);
+
+    /// Returns the text written when logging starts.
+    fn begin_marker (&self) -> &str {
+        ""
+    }
+
+    /// Returns the text written when logging stops.
+    fn end_marker (&self) -> &str {
+        ""
+    }
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+const DEFAULT_TIME_FORMAT : &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The time style enum
+///
+/// It decides which clock the timestamps of a pattern formatter are read from.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimeStyle {
+
+    /// The local time (default)
+    Local,
+    /// The time in UTC
+    Utc,
+    /// The time since logging started, which stays comparable between runs
+    Monotonic
+}
+
+/*================================================================================================*/
+
+// A parsed part of a pattern
+#[derive (Clone, Debug)]
+enum Piece {
+
+    Literal (String),
+    Time (Vec <TimePart>),
+    Level,
+    Target,
+    Message,
+    Thread,
+    Module,
+    File,
+    Line,
+    Location,
+    Fields
+}
+
+/*================================================================================================*/
+
+// A parsed part of a time format
+#[derive (Clone, Debug)]
+enum TimePart {
+
+    Strftime (String),
+    Fraction (usize)
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The text formatter struct
+///
+/// It formats records as human readable lines, surrounded by begin and end markers:
+///
+/// ```text
+/// INFO    (Wed Jun  1 12:00:00 2016) [main] renderer: Loaded textures count=12
+/// ```
+#[derive (Copy, Clone, Debug, Default)]
+pub struct TextFormatter;
+
+/*================================================================================================*/
+
+/// The JSON formatter struct
+///
+/// It formats records as JSON objects, one per line, for machine processing.
+/// Each object has the following keys, with the optional keys left out when unknown:
+///
+/// ```text
+/// {"timestamp":"2016-06-01T12:00:00.000Z","level":"INFO","target":"renderer",
+///  "thread":"main","message":"Loaded textures","module_path":"game::renderer",
+///  "file":"src/renderer.rs","line":42,"fields":{"count":12}}
+/// ```
+///
+/// Timestamps are in UTC, and `target`, `module_path`, `file` and `line` are optional.
+#[derive (Copy, Clone, Debug, Default)]
+pub struct JsonFormatter;
+
+/*================================================================================================*/
+
+/// The pattern formatter struct
+///
+/// It formats records using a pattern of placeholders, such as
+/// `"{time:%H:%M:%S%.3f} [{level}] {target}: {msg}"`. The placeholders are:
+///
+/// * `{time}` or `{time:format}` - The timestamp, using `strftime` style formatting with
+///   `%.3f`, `%.6f` or `%.9f` for fractional seconds (`%Y-%m-%d %H:%M:%S%.3f` by default)
+/// * `{level}` - The level (e.g. `WARNING`)
+/// * `{target}` - The target
+/// * `{msg}` - The message
+/// * `{thread}` - The thread name, or id if unnamed
+/// * `{module}`, `{file}` and `{line}` - The source location, empty if unknown
+/// * `{location}` - The source file and line (e.g. `src/main.rs:42`), empty if unknown
+/// * `{fields}` - The structured fields, as `key=value` pairs
+///
+/// Braces are escaped by doubling them (`{{` and `}}`). No begin or end markers are written.
+#[derive (Clone, Debug)]
+pub struct PatternFormatter {
+
+    // Private
+    pieces     : Vec <Piece>,
+    time_style : TimeStyle
+}
+
+/*================================================================================================*/
+
+/// The pattern parse error struct
+///
+/// It is returned when a formatter pattern is malformed.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ParsePatternError {
+
+    // Private
+    placeholder : String
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl PatternFormatter {
+
+    /// Creates a new pattern formatter, using local timestamps.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{PatternFormatter, TimeStyle};
+    /// let pattern       = "{time:%H:%M:%S%.3f} [{level}] {target}: {msg}";
+    /// let mut formatter = PatternFormatter::new (pattern).unwrap ();
+    ///
+    /// formatter.set_time_style (TimeStyle::Monotonic);
+    /// ```
+    pub fn new (pattern : &str) -> Result <PatternFormatter, ParsePatternError> {
+
+        let mut pieces  = Vec::new ();
+        let mut literal = String::new ();
+        let mut chars   = pattern.chars ();
+
+        while let Some (character) = chars.next () {
+
+            match character {
+
+                '{' if chars.as_str ().starts_with ('{') => {
+                    chars.next ();
+                    literal.push ('{');
+                },
+
+                '}' if chars.as_str ().starts_with ('}') => {
+                    chars.next ();
+                    literal.push ('}');
+                },
+
+                '{' => {
+
+                    let rest = chars.as_str ();
+                    let end  = rest.find ('}').ok_or_else (|| ParsePatternError::new (rest))?;
+
+                    if !literal.is_empty () {
+                        pieces.push (Piece::Literal (::std::mem::take (&mut literal)));
+                    }
+
+                    pieces.push (parse_placeholder (&rest[.. end])?);
+                    chars = rest[end + 1 ..].chars ();
+                },
+
+                '}' => return Err (ParsePatternError::new ("}")),
+                _   => literal.push (character)
+            }
+        }
+
+        if !literal.is_empty () {
+            pieces.push (Piece::Literal (literal));
+        }
+
+        Ok (PatternFormatter {pieces,
+                              time_style : TimeStyle::Local})
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets which clock timestamps are read from.
+    pub fn set_time_style (&mut self, time_style : TimeStyle) {
+
+        self.time_style = time_style;
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Writes the timestamp of a record
+    fn write_time (&self, record : &LogRecord, parts : &[TimePart], output : &mut String) {
+
+        let (tm, nanoseconds) = match self.time_style {
+
+            TimeStyle::Local => (time::at (record.time), record.time.nsec as u32),
+            TimeStyle::Utc   => (time::at_utc (record.time), record.time.nsec as u32),
+
+            // The uptime is treated as a time since the epoch, so hours start from zero
+            TimeStyle::Monotonic => {
+
+                let nanoseconds = record.uptime.subsec_nanos ();
+                let uptime      = time::Timespec::new (record.uptime.as_secs () as i64,
+                                                       nanoseconds as i32);
+
+                (time::at_utc (uptime), nanoseconds)
+            }
+        };
+
+        for part in parts {
+
+            // Formats are checked when parsed, so can't fail here
+            let _ = match *part {
+
+                TimePart::Strftime (ref format) => match tm.strftime (format) {
+                    Ok (formatted) => write! (output, "{}", formatted),
+                    Err (_)        => Ok (())
+                },
+
+                TimePart::Fraction (digits) => {
+                    write! (output, ".{:01$}", nanoseconds / 10u32.pow (9 - digits as u32), digits)
+                }
+            };
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl ParsePatternError {
+
+    // Creates a new error for a placeholder
+    fn new (placeholder : &str) -> ParsePatternError {
+
+        ParsePatternError {placeholder : placeholder.to_string ()}
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogFormatter for TextFormatter {
+
+    // Formats the record using its display format
+    fn format (&self, record : &LogRecord, output : &mut String) {
+
+        let _ = write! (output, "{}", record);
+    }
+
+/*================================================================================================*/
+
+    // Returns the begin marker
+    fn begin_marker (&self) -> &str {
+
+        "---BEGIN LOG---\n\n"
+    }
+
+/*================================================================================================*/
+
+    // Returns the end marker
+    fn end_marker (&self) -> &str {
+
+        "\n---END LOG---\n"
+    }
+}
+
+/*================================================================================================*/
+
+impl LogFormatter for JsonFormatter {
+
+    // Formats the record as a single JSON object
+    fn format (&self, record : &LogRecord, output : &mut String) {
+
+        let utc = time::at_utc (record.time);
+
+        // Neither format can fail
+        let _ = write! (output, "{{\"timestamp\":\"{}.{:03}Z\",\"level\":\"{}\"",
+                        time::strftime ("%Y-%m-%dT%H:%M:%S", &utc).unwrap_or_default (),
+                        record.time.nsec / 1_000_000,
+                        record.level.label ().trim_end ());
+
+        if !record.target.is_empty () {
+            output.push_str (",\"target\":");
+            write_json_string (output, &record.target);
+        }
+
+        output.push_str (",\"thread\":");
+
+        write_json_string (output, &ThreadName (record).to_string ());
+
+        output.push_str (",\"message\":");
+        write_json_string (output, &record.message);
+
+        if let Some (ref module_path) = record.module_path {
+            output.push_str (",\"module_path\":");
+            write_json_string (output, module_path);
+        }
+
+        if let Some (ref file) = record.file {
+            output.push_str (",\"file\":");
+            write_json_string (output, file);
+        }
+
+        if let Some (line) = record.line {
+            let _ = write! (output, ",\"line\":{}", line);
+        }
+
+        if !record.fields.is_empty () {
+
+            output.push_str (",\"fields\":{");
+
+            for (index, (key, value)) in record.fields.iter ().enumerate () {
+
+                if index > 0 {
+                    output.push (',');
+                }
+
+                write_json_string (output, key);
+                output.push (':');
+                write_json_value (output, value);
+            }
+
+            output.push ('}');
+        }
+
+        output.push ('}');
+    }
+}
+
+/*================================================================================================*/
+
+impl LogFormatter for PatternFormatter {
+
+    // Formats the record by filling in each placeholder of the pattern
+    fn format (&self, record : &LogRecord, output : &mut String) {
+
+        let level = record.level.label ().trim_end ();
+
+        for piece in &self.pieces {
+
+            let _ = match *piece {
+
+                Piece::Literal (ref text) => write! (output, "{}", text),
+                Piece::Level              => write! (output, "{}", level),
+                Piece::Target             => write! (output, "{}", record.target),
+                Piece::Message            => write! (output, "{}", record.message),
+                Piece::Thread             => write! (output, "{}", ThreadName (record)),
+                Piece::Module             => write! (output, "{}", or_empty (&record.module_path)),
+                Piece::File               => write! (output, "{}", or_empty (&record.file)),
+                Piece::Fields             => write! (output, "{}", Fields (&record.fields)),
+
+                Piece::Time (ref parts) => {
+                    self.write_time (record, parts, output);
+                    Ok (())
+                },
+
+                Piece::Line => match record.line {
+                    Some (line) => write! (output, "{}", line),
+                    None        => Ok (())
+                },
+
+                Piece::Location => match (&record.file, record.line) {
+                    (Some (file), Some (line)) => write! (output, "{}:{}", file, line),
+                    (Some (file), None)        => write! (output, "{}", file),
+                    _                          => Ok (())
+                }
+            };
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ParsePatternError {
+
+    // Formats the error message
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f, "invalid log pattern placeholder \"{}\"", self.placeholder)
+    }
+}
+
+/*================================================================================================*/
+
+impl Error for ParsePatternError {}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Parses the contents of a placeholder (without braces)
+fn parse_placeholder (placeholder : &str) -> Result <Piece, ParsePatternError> {
+
+    let (name, format) = match placeholder.find (':') {
+        Some (index) => (&placeholder[.. index], Some (&placeholder[index + 1 ..])),
+        None         => (placeholder, None)
+    };
+
+    let error = || ParsePatternError::new (placeholder);
+
+    let piece = match name.trim () {
+
+        "time" => {
+            let format = format.unwrap_or (DEFAULT_TIME_FORMAT);
+            return parse_time_format (format).map (Piece::Time).ok_or_else (error);
+        },
+
+        "level"           => Piece::Level,
+        "target"          => Piece::Target,
+        "msg" | "message" => Piece::Message,
+        "thread"          => Piece::Thread,
+        "module"          => Piece::Module,
+        "file"            => Piece::File,
+        "line"            => Piece::Line,
+        "location"        => Piece::Location,
+        "fields"          => Piece::Fields,
+        _                 => return Err (error ())
+    };
+
+    // Only the timestamp takes a format
+    match format {
+        Some (_) => Err (error ()),
+        None     => Ok (piece)
+    }
+}
+
+/*================================================================================================*/
+
+// Splits a time format into strftime formats and fractional seconds, checking it is valid
+fn parse_time_format (format : &str) -> Option <Vec <TimePart>> {
+
+    let mut parts = Vec::new ();
+    let mut rest  = format;
+
+    while let Some (index) = rest.find ("%.") {
+
+        let bytes = rest.as_bytes ();
+
+        // Fractions look like "%.3f", with 1 to 9 digits
+        match (bytes.get (index + 2), bytes.get (index + 3)) {
+
+            (Some (&digit @ b'1' ..= b'9'), Some (&b'f')) => {
+
+                if index > 0 {
+                    parts.push (TimePart::Strftime (rest[.. index].to_string ()));
+                }
+
+                parts.push (TimePart::Fraction ((digit - b'0') as usize));
+                rest = &rest[index + 4 ..];
+            },
+
+            _ => return None
+        }
+    }
+
+    if !rest.is_empty () {
+        parts.push (TimePart::Strftime (rest.to_string ()));
+    }
+
+    // Check the formats are valid now, rather than every time a record is formatted
+    let now = time::now ();
+
+    let is_valid = parts.iter ().all (|part| match *part {
+        TimePart::Strftime (ref format) => time::strftime (format, &now).is_ok (),
+        TimePart::Fraction (_)          => true
+    });
+
+    if is_valid {Some (parts)} else {None}
+}
+
+/*================================================================================================*/
+
+// Returns an optional string, or an empty string if unknown
+fn or_empty (string : &Option <String>) -> &str {
+
+    string.as_ref ().map_or ("", |string| string.as_str ())
+}
+
+/*================================================================================================*/
+
+// Writes a string as a quoted and escaped JSON string
+pub fn write_json_string (output : &mut String, string : &str) {
+
+    output.push ('"');
+
+    for character in string.chars () {
+
+        match character {
+
+            '"'  => output.push_str ("\\\""),
+            '\\' => output.push_str ("\\\\"),
+            '\n' => output.push_str ("\\n"),
+            '\r' => output.push_str ("\\r"),
+            '\t' => output.push_str ("\\t"),
+
+            character if (character as u32) < 0x20 => {
+                let _ = write! (output, "\\u{:04x}", character as u32);
+            },
+
+            character => output.push (character)
+        }
+    }
+
+    output.push ('"');
+}
+
+/*================================================================================================*/
+
+// Writes a field value as JSON, with non finite floats written as null
+fn write_json_value (output : &mut String, value : &Value) {
+
+    let _ = match *value {
+
+        Value::Bool (value)                        => write! (output, "{}", value),
+        Value::Int (value)                         => write! (output, "{}", value),
+        Value::Uint (value)                        => write! (output, "{}", value),
+        Value::Float (value) if value.is_finite () => write! (output, "{}", value),
+        Value::Float (_)                           => write! (output, "null"),
+
+        Value::Str (ref value) => {
+            write_json_string (output, value);
+            Ok (())
+        }
+    };
+}
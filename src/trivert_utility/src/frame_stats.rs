@@ -0,0 +1,306 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The frame stats struct
+///
+/// It tracks the lengths of the most recent frames, to report the frame rate, percentiles and
+/// hitches.
+///
+/// A hitch is a frame which takes much longer than the frames before it, by default twice as
+/// long as the average of the frames being tracked.
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use trivert_utility::FrameStats;
+/// let mut stats = FrameStats::new (120);
+///
+/// for _ in 0..99 {
+///     stats.add_frame (Duration::from_millis (10));
+/// }
+///
+/// assert! (stats.add_frame (Duration::from_millis (50))); // A hitch
+///
+/// let report = stats.report ().unwrap ();
+///
+/// assert_eq! (report.max,     Duration::from_millis (50));
+/// assert_eq! (report.hitches, 1);
+/// assert_eq! (report.one_percent_low, 20.0);
+/// ```
+#[derive (Clone, Debug)]
+pub struct FrameStats {
+
+    // Private
+    frames       : VecDeque <Duration>,
+    capacity     : usize,
+    total        : Duration,
+    hitch_factor : f64,
+    hitches      : u64
+}
+
+/*================================================================================================*/
+
+/// The frame report struct
+///
+/// It is a summary of the frames tracked by frame stats.
+#[derive (Copy, Clone, Debug, PartialEq)]
+pub struct FrameReport {
+
+    // Public
+    /// The number of frames tracked.
+    pub frames          : usize,
+    /// The average frames per second.
+    pub fps             : f64,
+    /// The average frame length.
+    pub average         : Duration,
+    /// The shortest frame length.
+    pub min             : Duration,
+    /// The longest frame length.
+    pub max             : Duration,
+    /// The frame length which 99% of frames are as short as or shorter than.
+    pub p99             : Duration,
+    /// The frames per second of the slowest 1% of frames.
+    pub one_percent_low : f64,
+    /// The number of hitches since the stats were created or cleared.
+    pub hitches         : u64
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl FrameStats {
+
+    /// Creates new frame stats, which track up to `window` frames.
+    pub fn new (window : usize) -> FrameStats {
+
+        let capacity = window.max (1);
+
+        FrameStats {frames       : VecDeque::with_capacity (capacity),
+                    capacity,
+                    total        : Duration::from_secs (0),
+                    hitch_factor : 2.0,
+                    hitches      : 0}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Adds a frame's length, dropping the oldest frame if the window is full.
+    ///
+    /// Returns `true` if the frame was a hitch.
+    pub fn add_frame (&mut self, length : Duration) -> bool {
+
+        let hitch = !self.frames.is_empty () &&
+                    length.as_secs_f64 () > self.average ().as_secs_f64 () * self.hitch_factor;
+
+        if hitch {
+            self.hitches += 1;
+        }
+
+        if self.frames.len () == self.capacity {
+
+            if let Some (oldest) = self.frames.pop_front () {
+                self.total -= oldest;
+            }
+        }
+
+        self.frames.push_back (length);
+        self.total += length;
+
+        hitch
+    }
+
+/*================================================================================================*/
+
+    /// Returns the average frames per second, or `0.0` if no time has been tracked.
+    pub fn fps (&self) -> f64 {
+
+        per_second (self.frames.len (), self.total)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the average frame length.
+    pub fn average (&self) -> Duration {
+
+        if self.frames.is_empty () {
+            return Duration::from_secs (0);
+        }
+
+        self.total / self.frames.len () as u32
+    }
+
+/*================================================================================================*/
+
+    /// Returns the frame length which the given percentage of frames are as short as or
+    /// shorter than (e.g. `99.0` for the 99th percentile).
+    pub fn percentile (&self, percent : f64) -> Duration {
+
+        let sorted = self.sorted ();
+
+        if sorted.is_empty () {
+            return Duration::from_secs (0);
+        }
+
+        let rank = (percent.clamp (0.0, 100.0) / 100.0 * sorted.len () as f64).ceil () as usize;
+        sorted[rank.clamp (1, sorted.len ()) - 1]
+    }
+
+/*================================================================================================*/
+
+    /// Returns the frames per second of the slowest given percentage of frames (e.g. `1.0` for
+    /// the 1% low), which always includes at least the slowest frame.
+    pub fn percent_low (&self, percent : f64) -> f64 {
+
+        let sorted = self.sorted ();
+        let count  = (percent.clamp (0.0, 100.0) / 100.0 * sorted.len () as f64).ceil () as usize;
+        let count  = count.max (1).min (sorted.len ());
+
+        per_second (count, sorted.iter ().rev ().take (count).sum ())
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of hitches since the stats were created or cleared.
+    pub fn hitches (&self) -> u64 {
+
+        self.hitches
+    }
+
+/*================================================================================================*/
+
+    /// Sets how many times longer than the average a frame must be to count as a hitch.
+    pub fn set_hitch_factor (&mut self, hitch_factor : f64) {
+
+        self.hitch_factor = hitch_factor;
+    }
+
+/*================================================================================================*/
+
+    /// Returns how many times longer than the average a frame must be to count as a hitch.
+    pub fn hitch_factor (&self) -> f64 {
+
+        self.hitch_factor
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of frames tracked.
+    pub fn len (&self) -> usize {
+
+        self.frames.len ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if no frames are tracked.
+    pub fn is_empty (&self) -> bool {
+
+        self.frames.is_empty ()
+    }
+
+/*================================================================================================*/
+
+    /// Removes every frame tracked, and clears the hitch count.
+    pub fn clear (&mut self) {
+
+        self.frames.clear ();
+
+        self.total   = Duration::from_secs (0);
+        self.hitches = 0;
+    }
+
+/*================================================================================================*/
+
+    /// Returns a summary of the frames tracked, or `None` if no frames are tracked.
+    pub fn report (&self) -> Option <FrameReport> {
+
+        let sorted = self.sorted ();
+
+        let (min, max) = match (sorted.first (), sorted.last ()) {
+            (Some (&min), Some (&max)) => (min, max),
+            _                          => return None
+        };
+
+        Some (FrameReport {frames          : sorted.len (),
+                           fps             : self.fps (),
+                           average         : self.average (),
+                           min,
+                           max,
+                           p99             : self.percentile (99.0),
+                           one_percent_low : self.percent_low (1.0),
+                           hitches         : self.hitches})
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Returns the frame lengths, from shortest to longest
+    fn sorted (&self) -> Vec <Duration> {
+
+        let mut sorted : Vec <Duration> = self.frames.iter ().cloned ().collect ();
+
+        sorted.sort ();
+        sorted
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for FrameReport {
+
+    // Writes the report as a single line, with times in milliseconds
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f,
+                "{:.1} fps ({:.2} ms avg, {:.2} ms min, {:.2} ms max, {:.2} ms 99th), \
+                 {:.1} fps 1% low, {} hitches",
+                self.fps,
+                self.average.as_secs_f64 () * 1000.0,
+                self.min.as_secs_f64 () * 1000.0,
+                self.max.as_secs_f64 () * 1000.0,
+                self.p99.as_secs_f64 () * 1000.0,
+                self.one_percent_low,
+                self.hitches)
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns the rate of a number of frames over a time, or zero if no time passed
+fn per_second (frames : usize, time : Duration) -> f64 {
+
+    if time == Duration::from_secs (0) {
+        return 0.0;
+    }
+
+    frames as f64 / time.as_secs_f64 ()
+}
@@ -27,7 +27,68 @@
 #![deny    (missing_docs)]
 
 // Private modules
+#[macro_use]
+mod macros;
+mod archive_index;
+mod args;
+mod async_sink;
+mod clock;
+mod config;
+mod config_document;
+mod console_sink;
+mod context;
+mod crash;
+mod error;
+mod facade;
+mod file_sink;
+mod file_watcher;
+mod filter;
+mod fixed_timestep;
+mod formatter;
+mod frame_stats;
 mod logger;
+mod memory_sink;
+mod pack;
+mod profiler;
+mod record;
+mod shared_logger;
+mod sink;
+mod span;
+mod timer;
+mod value;
+mod vfs;
+mod vfs_source;
+mod zip_source;
 
 // Public module exports
+pub use self::args::{ArgParser, Args};
+pub use self::async_sink::{AsyncSink, OverflowPolicy};
+pub use self::clock::{Clock, Stopwatch};
+pub use self::config::{Config, ConfigLayer};
+pub use self::config_document::{ConfigDocument, ParseConfigError};
+pub use self::console_sink::ConsoleSink;
+pub use self::context::{ContextGuard, LogContext};
+pub use self::crash::CrashHandler;
+pub use self::error::{ArgError, ConfigError, FailurePolicy, LogError, VfsError};
+pub use self::facade::LogFacade;
+pub use self::file_sink::{ArchiveNaming, FileSink, Rotation};
+pub use self::file_watcher::{FileChange, FileChangeKind, FileWatcher, WatchBackend};
+pub use self::filter::{LogFilter, ParseFilterError};
+pub use self::fixed_timestep::FixedTimestep;
+pub use self::formatter::{JsonFormatter, LogFormatter, ParsePatternError, PatternFormatter};
+pub use self::formatter::{TextFormatter, TimeStyle};
+pub use self::frame_stats::{FrameReport, FrameStats};
 pub use self::logger::{LogLevel, Logger};
+pub use self::memory_sink::{LogCursor, LogQuery, MemoryReader, MemorySink};
+pub use self::pack::{PackCompression, PackEntry, PackSource, PackWriter};
+pub use self::profiler::{ProfileEvent, ProfileFrame, ProfileNode, ProfileScope, ProfileStats};
+pub use self::profiler::{Profiler, ThreadProfile};
+pub use self::record::{LogLocation, LogRecord};
+pub use self::shared_logger::SharedLogger;
+pub use self::sink::{CallbackSink, LogSink};
+pub use self::span::{LoggerSpan, Span};
+pub use self::timer::{Timer, TimerMode};
+pub use self::value::Value;
+pub use self::vfs::{Vfs, VfsEntry, VfsMetadata};
+pub use self::vfs_source::{DirectorySource, VfsSource};
+pub use self::zip_source::ZipSource;
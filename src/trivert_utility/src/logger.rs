@@ -16,8 +16,11 @@
 
 extern crate time;
 
-use std::fs::File;
-use std::io::BufWriter;
+use {FailurePolicy, FileSink, LogError, LogFilter, LogLocation, LogRecord, LogSink, Value};
+use record;
+
+use std::fmt;
+use std::io;
 use std::io::prelude::Write;
 
 /*================================================================================================*/
@@ -27,17 +30,22 @@ use std::io::prelude::Write;
 /// The log level enum
 ///
 /// It is used with the logger struct, and is used to determine the severity of the message.
-#[derive (Copy, Clone)]
+/// Levels are ordered from least to most severe.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LogLevel {
 
-    /// Used for logging debug messages (stripped out of release builds)
-    Debug (&'static str),
+    /// Used for very detailed tracing messages
+    Trace,
+    /// Used for logging debug messages (filtered out of release builds by default)
+    Debug,
     /// Used for normal logging messages
-    Info (&'static str),
+    Info,
     /// Used for warnings (non critical errors)
-    Warning (&'static str),
+    Warning,
     /// Used for critical errors
-    Error (&'static str)
+    Error,
+    /// Used for unrecoverable errors, after which the program is expected to exit
+    Fatal
 }
 
 /*================================================================================================*/
@@ -47,13 +55,53 @@ pub enum LogLevel {
 /// The logger struct.
 ///
 /// It allows for basic logging capabilities.
-/// It can currently log strings, but others will be added in the future.
+/// Messages can either be plain strings, or formatted through the `trivert_log!` family of
+/// macros, which skip formatting entirely if the message would not be logged.
+///
+/// Each message has a level and a target (the module it was logged from by default), which
+/// are checked against the logger's filter to decide if it should be logged.
+///
+/// Logged messages are written to any number of sinks, each of which can have its own filter
+/// on top of the logger's. Sinks that fail to write are handled by the failure policy, and
+/// leave the logger unhealthy until the error is cleared.
 pub struct Logger {
 
     // Private
-    log_file    : Option <BufWriter <File>>,
-    is_enabled  : bool,
-    is_logging  : bool
+    sinks          : Vec <SinkEntry>,
+    filter         : LogFilter,
+    failure_policy : FailurePolicy,
+    last_error     : Option <LogError>,
+    is_logging     : bool
+}
+
+/*================================================================================================*/
+
+// A sink attached to a logger, along with its filter
+struct SinkEntry {
+
+    sink   : Box <dyn LogSink>,
+    filter : LogFilter
+}
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogLevel {
+
+    /// Returns the name of the level, padded to a fixed width for aligned output.
+    pub fn label (&self) -> &'static str {
+
+        match *self {
+
+            LogLevel::Trace   => "TRACE  ",
+            LogLevel::Debug   => "DEBUG  ",
+            LogLevel::Info    => "INFO   ",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error   => "ERROR  ",
+            LogLevel::Fatal   => "FATAL  "
+        }
+    }
 }
 
 /*================================================================================================*/
@@ -62,116 +110,427 @@ pub struct Logger {
 
 impl Logger {
 
-    /// Creates a new logger instance
-    ///
-    /// Logging is disabled on faliure.
+    /// Creates a new logger instance, which logs to a file
     ///
     /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::Logger;
+    /// let logger = Logger::new ("log.txt").unwrap ();
     /// ```
-    /// let logger = Logger::new ("log.txt");
-    pub fn new (log_file_path : &str) -> Logger {
+    pub fn new (log_file_path : &str) -> Result <Logger, LogError> {
 
-        match File::create (&log_file_path) {
+        let mut logger = Logger::empty ();
+        logger.add_sink (FileSink::new (log_file_path)?);
 
-            Ok (file) => Logger {log_file   : Some (BufWriter::new (file)),
-                                 is_enabled : true,
-                                 is_logging : false},
+        Ok (logger)
+    }
 
-            Err (_) => {
+/*================================================================================================*/
 
-                println! ("{}\n{}",
-                          "WARNING: Log file could not be created",
-                          "         Logging has been disabled\n");
+    /// Creates a new logger instance without any sinks
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{ConsoleSink, Logger};
+    /// let mut logger = Logger::empty ();
+    /// logger.add_sink (ConsoleSink::stdout ());
+    /// ```
+    pub fn empty () -> Logger {
 
-                Logger {log_file   : None,
-                        is_enabled : false,
-                        is_logging : false}
-            }
-        }
+        // Start the uptime clock, so uptimes are relative to when logging was set up
+        record::uptime ();
+
+        Logger {sinks          : Vec::new (),
+                filter         : LogFilter::default (),
+                failure_policy : FailurePolicy::default (),
+                last_error     : None,
+                is_logging     : false}
     }
 
 /*================================================================================================*/
 /*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
 /*================================================================================================*/
 
-    /// Starts file logging
+    /// Starts logging
     pub fn begin_log (&mut self) {
 
         // Check if enabled
-        if self.is_enabled {
+        if !self.sinks.is_empty () {
 
             self.is_logging = true;
-            self.log_file.as_mut ().unwrap ().write ("---BEGIN LOG---\n\n".as_bytes ()).unwrap ();
+
+            for entry in &mut self.sinks {
+
+                if let Err (error) = entry.sink.begin () {
+                    self.last_error = Some (fail (self.failure_policy, error, None));
+                }
+            }
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Attaches a sink, which is sent every message that passes the logger's filter.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{ConsoleSink, Logger};
+    /// let mut logger = Logger::empty ();
+    /// logger.add_sink (ConsoleSink::stderr ());
+    /// ```
+    pub fn add_sink<S : LogSink + 'static> (&mut self, sink : S) {
+
+        self.add_sink_with_filter (sink, LogFilter::new (Some (LogLevel::Trace)));
+    }
+
+/*================================================================================================*/
+
+    /// Attaches a sink with its own filter.
+    ///
+    /// A message is only sent to the sink if it passes both the logger's and the sink's filter.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{ConsoleSink, LogFilter, LogLevel, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// let errors     = LogFilter::new (Some (LogLevel::Error));
+    ///
+    /// logger.add_sink_with_filter (ConsoleSink::stderr (), errors);
+    /// ```
+    pub fn add_sink_with_filter<S : LogSink + 'static> (&mut self, sink : S, filter : LogFilter) {
+
+        let mut entry = SinkEntry {sink : Box::new (sink),
+                                   filter};
+
+        // Sinks added mid log still get their begin marker
+        if self.is_logging {
+
+            if let Err (error) = entry.sink.begin () {
+                self.last_error = Some (fail (self.failure_policy, error, None));
+            }
         }
+
+        self.sinks.push (entry);
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a message of the given level and target would be logged.
+    pub fn is_enabled (&self, log_level : LogLevel, target : &str) -> bool {
+
+        self.is_logging &&
+        self.filter.is_enabled (log_level, target) &&
+        self.sinks.iter ().any (|entry| entry.filter.is_enabled (log_level, target))
+    }
+
+/*================================================================================================*/
+
+    /// Returns the filter used to decide which messages are logged.
+    pub fn filter (&self) -> &LogFilter {
+
+        &self.filter
+    }
+
+/*================================================================================================*/
+
+    /// Replaces the filter used to decide which messages are logged.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogFilter, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// logger.set_filter (LogFilter::from_env ("TRIVERT_LOG").unwrap_or_default ());
+    /// ```
+    pub fn set_filter (&mut self, filter : LogFilter) {
+
+        self.filter = filter;
+    }
+
+/*================================================================================================*/
+
+    /// Sets the minimum level logged for targets without their own filter level.
+    pub fn set_level (&mut self, log_level : Option <LogLevel>) {
+
+        self.filter.set_default_level (log_level);
+    }
+
+/*================================================================================================*/
+
+    /// Returns what happens when a sink fails to write.
+    pub fn failure_policy (&self) -> FailurePolicy {
+
+        self.failure_policy
+    }
+
+/*================================================================================================*/
+
+    /// Sets what happens when a sink fails to write.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{FailurePolicy, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// logger.set_failure_policy (FailurePolicy::Drop);
+    /// ```
+    pub fn set_failure_policy (&mut self, failure_policy : FailurePolicy) {
+
+        self.failure_policy = failure_policy;
+    }
+
+/*================================================================================================*/
+
+    /// Checks if every sink has written successfully since the last error was cleared.
+    pub fn is_healthy (&self) -> bool {
+
+        self.last_error.is_none ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the most recent sink error, if any.
+    pub fn last_error (&self) -> Option <&LogError> {
+
+        self.last_error.as_ref ()
+    }
+
+/*================================================================================================*/
+
+    /// Clears the most recent sink error, returning it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::Logger;
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    ///
+    /// if let Some (error) = logger.clear_error () {
+    ///     println! ("Logging failed: {}", error);
+    /// }
+    /// ```
+    pub fn clear_error (&mut self) -> Option <LogError> {
+
+        self.last_error.take ()
     }
 
 /*================================================================================================*/
 
     /// Logs a message
     ///
+    /// Messages logged this way have no target, so only the default filter level applies.
+    ///
     /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogLevel, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    ///
+    /// logger.log (LogLevel::Debug,   "This is a log entry"); // Debug
+    /// logger.log (LogLevel::Info,    "This is a log entry"); // Info
+    /// logger.log (LogLevel::Warning, "This is a log entry"); // Warning
+    /// logger.log (LogLevel::Error,   "This is a log entry"); // Error
     /// ```
-    /// let mut logger = Logger::new ("log.txt");
+    pub fn log (&mut self, log_level : LogLevel, message : &str) {
+
+        self.log_fmt (log_level, "", format_args! ("{}", message));
+    }
+
+/*================================================================================================*/
+
+    /// Logs a formatted message for a target
+    ///
+    /// The message is formatted once, then written to every sink whose filter accepts it.
+    /// This is normally called through the `trivert_log!` family of macros.
     ///
-    /// logger.log (LogLevel::Debug   ("This is a log entry")) // Debug
-    /// logger.log (LogLevel::Message ("This is a log entry")) // Message
-    /// logger.log (LogLevel::Warning ("This is a log entry")) // Warning
-    /// logger.log (LogLevel::Error   ("This is a log entry")) // Error
-    pub fn log (&mut self, log_level : LogLevel) {
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogLevel, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// logger.log_fmt (LogLevel::Info, "renderer", format_args! ("Loaded {} textures", 12));
+    /// ```
+    pub fn log_fmt (&mut self, log_level : LogLevel, target : &str, args : fmt::Arguments) {
 
-        // Check if logger is enabled and logging
-        if self.is_enabled && self.is_logging {
+        self.log_fields (log_level, target, &[], args);
+    }
 
-            // Get the buffer from the logger struct
-            let buffer = self.log_file.as_mut ().unwrap ();
+/*================================================================================================*/
 
-            match log_level {
+    /// Logs a formatted message for a target, with structured fields
+    ///
+    /// The fields are added after those of any contexts the current thread is in.
+    /// This is normally called through the `trivert_log!` family of macros.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogLevel, Logger, Value};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    ///
+    /// logger.log_fields (LogLevel::Info, "renderer",
+    ///                    &[("count", Value::from (12)), ("atlas", Value::from ("ui"))],
+    ///                    format_args! ("Loaded textures"));
+    /// ```
+    pub fn log_fields (&mut self,
+                       log_level : LogLevel,
+                       target    : &str,
+                       fields    : &[(&str, Value)],
+                       args      : fmt::Arguments) {
+
+        // Check if the message should be logged
+        if self.is_enabled (log_level, target) {
+            self.dispatch (&build_record (log_level, target, None, fields, args));
+        }
+    }
 
-                // Debug
-                LogLevel::Debug (m) => {
+/*================================================================================================*/
 
-                    // Only call if run in debug
-                    if cfg! (debug_assertions) {
+    /// Logs a formatted message for a target, with structured fields and its source location
+    ///
+    /// This is what the `trivert_log!` family of macros call, with the location of the macro.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogLevel, LogLocation, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// let location   = LogLocation {module_path : module_path! (),
+    ///                               file        : file! (),
+    ///                               line        : line! ()};
+    ///
+    /// logger.log_at (LogLevel::Info, "renderer", &location, &[], format_args! ("Ready"));
+    /// ```
+    pub fn log_at (&mut self,
+                   log_level : LogLevel,
+                   target    : &str,
+                   location  : &LogLocation,
+                   fields    : &[(&str, Value)],
+                   args      : fmt::Arguments) {
+
+        // Check if the message should be logged
+        if self.is_enabled (log_level, target) {
+            self.dispatch (&build_record (log_level, target, Some (location), fields, args));
+        }
+    }
 
-                        let log_string = format! ("DEBUG   ({}) {}\n", time::now ().asctime (), m);
-                        buffer.write (log_string.as_bytes ()).unwrap ();
-                    }
-                },
+/*================================================================================================*/
 
-                // Message
-                LogLevel::Info (m) => {
+    /// Logs an already built record
+    ///
+    /// The record is filtered on its level and target, like any other message.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{LogLevel, LogRecord, Logger};
+    /// let mut logger = Logger::new ("log.txt").unwrap ();
+    /// let mut record = LogRecord::new (LogLevel::Error, "audio", "Device lost");
+    ///
+    /// record.line = Some (42);
+    /// logger.log_record (&record);
+    /// ```
+    pub fn log_record (&mut self, record : &LogRecord) {
 
-                    let log_string = format! ("INFO    ({}) {}\n", time::now ().asctime (), m);
-                    buffer.write (log_string.as_bytes ()).unwrap ();
-                },
+        if self.is_enabled (record.level, &record.target) {
+            self.dispatch (record);
+        }
+    }
 
-                // Warning
-                LogLevel::Warning (m) => {
+/*================================================================================================*/
 
-                    let log_string = format! ("WARNING ({}) {}\n", time::now ().asctime (), m);
-                    buffer.write (log_string.as_bytes ()).unwrap ();
-                },
+    /// Flushes any buffered messages in every sink
+    pub fn flush (&mut self) {
 
-                // Error
-                LogLevel::Error (m) => {
+        for entry in &mut self.sinks {
 
-                    let log_string = format! ("ERROR   ({}) {}\n", time::now ().asctime (), m);
-                    buffer.write (log_string.as_bytes ()).unwrap ();
-                }
+            if let Err (error) = entry.sink.flush () {
+                self.last_error = Some (fail (self.failure_policy, error, None));
             }
         }
     }
 
 /*================================================================================================*/
 
-    /// Stops file logging
+    /// Stops logging
     pub fn end_log (&mut self) {
 
         // Check if enabled
-        if self.is_enabled {
+        if self.is_logging {
 
             self.is_logging = false;
-            self.log_file.as_mut ().unwrap ().write ("\n---END LOG---\n".as_bytes ()).unwrap ();
+
+            for entry in &mut self.sinks {
+
+                if let Err (error) = entry.sink.end ().and_then (|_| entry.sink.flush ()) {
+                    self.last_error = Some (fail (self.failure_policy, error, None));
+                }
+            }
         }
     }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Sends a record to every sink whose filter accepts it
+    fn dispatch (&mut self, record : &LogRecord) {
+
+        for entry in &mut self.sinks {
+
+            if entry.filter.is_enabled (record.level, &record.target) {
+
+                if let Err (error) = entry.sink.write (record) {
+                    self.last_error = Some (fail (self.failure_policy, error, Some (record)));
+                }
+            }
+        }
+
+        // Make sure fatal errors reach their destination before the program exits
+        if record.level == LogLevel::Fatal {
+            self.flush ();
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// Creates a record from a formatted message, its location and its fields.
+pub fn build_record (log_level : LogLevel,
+                     target    : &str,
+                     location  : Option <&LogLocation>,
+                     fields    : &[(&str, Value)],
+                     args      : fmt::Arguments) -> LogRecord {
+
+    let mut record = LogRecord::new (log_level, target, &fmt::format (args));
+
+    if let Some (location) = location {
+        record.set_location (location);
+    }
+
+    record.fields.extend (fields.iter ().map (|&(key, ref value)| {
+        (key.to_string (), value.clone ())
+    }));
+
+    record
+}
+
+/*================================================================================================*/
+
+// Applies a failure policy to a sink error, along with the record that failed to write
+fn fail (policy : FailurePolicy, error : io::Error, record : Option <&LogRecord>) -> LogError {
+
+    let error = LogError::from (error);
+
+    match policy {
+
+        FailurePolicy::Panic  => panic! ("{}", error),
+        FailurePolicy::Drop   => (),
+        FailurePolicy::Stderr => {
+
+            // Nothing else can be done if stderr fails too
+            let mut stderr = io::stderr ().lock ();
+
+            let _ = match record {
+                Some (record) => writeln! (stderr, "{}", record),
+                None          => writeln! (stderr, "WARNING: {}", error)
+            };
+        }
+    }
+
+    error
 }
@@ -0,0 +1,208 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+/*================================================================================================*/
+/*------MACROS------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// Logs a formatted message at the given level.
+///
+/// The message is only formatted if the logger would actually log it, so filtered out
+/// messages cost nothing beyond the filter check. The module path, file and line of the macro
+/// are recorded with the message. The logger expression is evaluated more
+/// than once, so should be a simple variable or field.
+///
+/// The target defaults to the current module path, and can be overridden by passing
+/// `target: "name"` before the message. Structured fields can be attached by passing
+/// `fields: {"key" => value, ...}` before the message (after the target if there is one).
+///
+/// # Examples
+/// ```no_run
+/// #[macro_use] extern crate trivert_utility;
+/// use trivert_utility::{LogLevel, Logger};
+///
+/// # fn main () {
+/// let mut logger = Logger::new ("log.txt").unwrap ();
+/// let textures   = 12;
+///
+/// trivert_log! (logger, LogLevel::Info, "Loaded {} textures", textures);
+/// trivert_log! (logger, LogLevel::Warning, target: "renderer", "Missing texture");
+/// trivert_log! (logger, LogLevel::Info, fields: {"count" => textures}, "Loaded textures");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trivert_log {
+
+    ($logger : expr, $level : expr, target : $target : expr,
+     fields : {$($key : expr => $value : expr),* $(,)*}, $($arg : tt)+) => {{
+
+        let level  = $level;
+        let target = $target;
+
+        if $logger.is_enabled (level, target) {
+
+            let location = $crate::LogLocation {module_path : module_path! (),
+                                                file        : file! (),
+                                                line        : line! ()};
+
+            $logger.log_at (level,
+                            target,
+                            &location,
+                            &[$(($key, $crate::Value::from ($value))),*],
+                            format_args! ($($arg)+));
+        }
+    }};
+
+    ($logger : expr, $level : expr, target : $target : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $level, target : $target, fields : {}, $($arg)+)
+    };
+
+    ($logger : expr, $level : expr, fields : {$($fields : tt)*}, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $level, target : module_path! (), fields : {$($fields)*},
+                              $($arg)+)
+    };
+
+    ($logger : expr, $level : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $level, target : module_path! (), $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted trace message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_trace {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Trace, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted debug message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_debug {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Debug, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted info message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_info {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Info, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted warning message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_warning {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Warning, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted error message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_error {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Error, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Logs a formatted fatal error message (see `trivert_log!`).
+#[macro_export]
+macro_rules! trivert_fatal {
+
+    ($logger : expr, $($arg : tt)+) => {
+        $crate::trivert_log! ($logger, $crate::LogLevel::Fatal, $($arg)+)
+    }
+}
+
+/*================================================================================================*/
+
+/// Enters a log context with the given fields, returning a guard which leaves it when dropped.
+///
+/// Every record logged from the current thread while in the context gets its fields.
+///
+/// # Examples
+/// ```no_run
+/// #[macro_use] extern crate trivert_utility;
+/// use trivert_utility::Logger;
+///
+/// # fn main () {
+/// let mut logger = Logger::new ("log.txt").unwrap ();
+/// let _context   = trivert_context! ("map" => "forest", "player" => 3);
+///
+/// trivert_info! (logger, "Player spawned"); // Logged with map="forest" player=3
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trivert_context {
+
+    ($($key : expr => $value : expr),* $(,)*) => {
+        $crate::LogContext::enter (&[$(($key, $crate::Value::from ($value))),*])
+    }
+}
+
+/*================================================================================================*/
+
+/// Enters a named span, returning a guard which exits it when dropped.
+///
+/// The enter and exit records are logged at the debug level, for the current module path
+/// unless a target is given. A span on a plain logger borrows it, so messages are then logged
+/// through the guard instead (see `LoggerSpan`).
+///
+/// # Examples
+/// ```no_run
+/// #[macro_use] extern crate trivert_utility;
+/// use trivert_utility::{Logger, SharedLogger};
+///
+/// # fn main () {
+/// let logger = SharedLogger::new (Logger::new ("log.txt").unwrap ());
+///
+/// let _level = trivert_span! (logger, "load_level");
+/// {
+///     let _textures = trivert_span! (logger, target: "renderer", "decode_textures");
+///     trivert_info! (logger, "Decoded atlas"); // Logged with span="load_level/decode_textures"
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trivert_span {
+
+    ($logger : expr, target : $target : expr, $name : expr) => {
+        $logger.span ($crate::LogLevel::Debug, $target, $name)
+    };
+
+    ($logger : expr, $name : expr) => {
+        $crate::trivert_span! ($logger, target : module_path! (), $name)
+    }
+}
@@ -0,0 +1,352 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {LogLevel, LogRecord, LogSink};
+use filter::target_matches;
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The memory sink struct
+///
+/// It keeps the most recent records in a fixed size ring buffer, dropping the oldest record
+/// once full. The records can be read at any time through a `MemoryReader`, which is useful
+/// for in-game consoles and tests.
+pub struct MemorySink {
+
+    // Private
+    buffer : Arc <Mutex <RingBuffer>>
+}
+
+/*================================================================================================*/
+
+/// The memory reader struct
+///
+/// It gives access to the records held by a memory sink, and can be freely cloned and sent
+/// between threads while the sink itself is owned by a logger.
+///
+/// Records can be searched with a query, and read incrementally using a cursor, so a console
+/// only has to handle the records logged since it last updated.
+#[derive (Clone)]
+pub struct MemoryReader {
+
+    // Private
+    buffer : Arc <Mutex <RingBuffer>>
+}
+
+/*================================================================================================*/
+
+/// The log cursor struct
+///
+/// It marks a position in the records of a memory sink, used to read only newer records.
+#[derive (Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogCursor {
+
+    // Private
+    sequence : u64
+}
+
+/*================================================================================================*/
+
+/// The log query struct
+///
+/// It selects records from a memory sink. Every condition which is set must match.
+///
+/// # Examples
+/// ```
+/// # use trivert_utility::{LogLevel, LogQuery};
+/// let query = LogQuery {min_level : Some (LogLevel::Warning),
+///                       text      : Some ("texture".to_string ()),
+///                       .. LogQuery::default ()};
+/// ```
+#[derive (Clone, Debug, Default, PartialEq)]
+pub struct LogQuery {
+
+    // Public
+    /// Only match records at or above this level
+    pub min_level : Option <LogLevel>,
+    /// Only match records for this target or its child modules
+    pub target    : Option <String>,
+    /// Only match records whose message contains this text, ignoring case
+    pub text      : Option <String>,
+    /// Only match records logged after this cursor
+    pub since     : Option <LogCursor>
+}
+
+/*================================================================================================*/
+
+// The records held by a memory sink
+struct RingBuffer {
+
+    records       : VecDeque <LogRecord>,
+    capacity      : usize,
+    next_sequence : u64
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl MemorySink {
+
+    /// Creates a new memory sink which holds up to `capacity` records.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::MemorySink;
+    /// let sink = MemorySink::new (256);
+    /// ```
+    pub fn new (capacity : usize) -> MemorySink {
+
+        let buffer = RingBuffer {records       : VecDeque::with_capacity (capacity),
+                                 capacity,
+                                 next_sequence : 0};
+
+        MemorySink {buffer : Arc::new (Mutex::new (buffer))}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Returns a reader for the records held by the sink.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogLevel, Logger, MemorySink};
+    /// let sink   = MemorySink::new (256);
+    /// let reader = sink.reader ();
+    ///
+    /// let mut logger = Logger::empty ();
+    /// logger.add_sink (sink);
+    /// logger.begin_log ();
+    /// logger.log (LogLevel::Warning, "Low memory");
+    ///
+    /// assert_eq! (reader.records ()[0].message, "Low memory");
+    /// ```
+    pub fn reader (&self) -> MemoryReader {
+
+        MemoryReader {buffer : self.buffer.clone ()}
+    }
+}
+
+/*================================================================================================*/
+
+impl MemoryReader {
+
+    /// Returns a copy of the held records, from oldest to newest.
+    pub fn records (&self) -> Vec <LogRecord> {
+
+        self.lock ().records.iter ().cloned ().collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns an iterator over a copy of the held records, from oldest to newest.
+    ///
+    /// Records logged after the call aren't included.
+    pub fn iter (&self) -> ::std::vec::IntoIter <LogRecord> {
+
+        self.records ().into_iter ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns a copy of the held records matching a query, from oldest to newest.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogLevel, LogQuery, MemorySink};
+    /// let reader = MemorySink::new (256).reader ();
+    ///
+    /// let errors = reader.query (&LogQuery {min_level : Some (LogLevel::Error),
+    ///                                       .. LogQuery::default ()});
+    /// ```
+    pub fn query (&self, query : &LogQuery) -> Vec <LogRecord> {
+
+        let buffer = self.lock ();
+        let skip   = query.since.map_or (0, |cursor| buffer.index_of (cursor));
+
+        buffer.records.iter ()
+                      .skip (skip)
+                      .filter (|record| query.matches (record))
+                      .cloned ()
+                      .collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns a cursor after the newest record, so only records logged from now on are read.
+    pub fn cursor (&self) -> LogCursor {
+
+        LogCursor {sequence : self.lock ().next_sequence}
+    }
+
+/*================================================================================================*/
+
+    /// Returns a copy of the records logged since a cursor, moving the cursor after them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogCursor, LogLevel, Logger, MemorySink};
+    /// let sink       = MemorySink::new (256);
+    /// let reader     = sink.reader ();
+    /// let mut cursor = LogCursor::default ();
+    ///
+    /// let mut logger = Logger::empty ();
+    /// logger.add_sink (sink);
+    /// logger.begin_log ();
+    ///
+    /// logger.log (LogLevel::Info, "First");
+    /// assert_eq! (reader.read_since (&mut cursor).len (), 1);
+    ///
+    /// logger.log (LogLevel::Info, "Second");
+    /// assert_eq! (reader.read_since (&mut cursor)[0].message, "Second");
+    /// ```
+    pub fn read_since (&self, cursor : &mut LogCursor) -> Vec <LogRecord> {
+
+        let buffer = self.lock ();
+        let skip   = buffer.index_of (*cursor);
+
+        cursor.sequence = buffer.next_sequence;
+        buffer.records.iter ().skip (skip).cloned ().collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of records logged since a cursor which have already been dropped
+    /// from the buffer, and so can't be read.
+    pub fn missed_since (&self, cursor : LogCursor) -> u64 {
+
+        let buffer = self.lock ();
+        buffer.oldest_sequence ().saturating_sub (cursor.sequence)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of held records.
+    pub fn len (&self) -> usize {
+
+        self.lock ().records.len ()
+    }
+
+/*================================================================================================*/
+
+    /// Checks if no records are held.
+    pub fn is_empty (&self) -> bool {
+
+        self.lock ().records.is_empty ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the maximum number of records held.
+    pub fn capacity (&self) -> usize {
+
+        self.lock ().capacity
+    }
+
+/*================================================================================================*/
+
+    /// Removes all held records.
+    ///
+    /// Cursors remain valid, and only see records logged after the clear.
+    pub fn clear (&self) {
+
+        self.lock ().records.clear ();
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Locks the buffer, ignoring poisoning as the records are always left in a valid state
+    fn lock (&self) -> MutexGuard <'_, RingBuffer> {
+
+        self.buffer.lock ().unwrap_or_else (|error| error.into_inner ())
+    }
+}
+
+/*================================================================================================*/
+
+impl LogQuery {
+
+    /// Checks if a record matches the query, ignoring the cursor.
+    pub fn matches (&self, record : &LogRecord) -> bool {
+
+        let level_ok  = self.min_level.is_none_or (|level| record.level >= level);
+
+        let target_ok = self.target.as_ref ().is_none_or (|target| {
+            target_matches (target, &record.target)
+        });
+
+        let text_ok   = self.text.as_ref ().is_none_or (|text| {
+            record.message.to_lowercase ().contains (&text.to_lowercase ())
+        });
+
+        level_ok && target_ok && text_ok
+    }
+}
+
+/*================================================================================================*/
+
+impl RingBuffer {
+
+    // Returns the sequence number of the oldest held record
+    fn oldest_sequence (&self) -> u64 {
+
+        self.next_sequence - self.records.len () as u64
+    }
+
+/*================================================================================================*/
+
+    // Returns the index of the first held record after a cursor
+    fn index_of (&self, cursor : LogCursor) -> usize {
+
+        let skipped = cursor.sequence.saturating_sub (self.oldest_sequence ());
+        skipped.min (self.records.len () as u64) as usize
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogSink for MemorySink {
+
+    // Adds the record to the buffer, dropping the oldest record if full
+    fn write (&mut self, record : &LogRecord) -> io::Result <()> {
+
+        let mut buffer = self.buffer.lock ().unwrap_or_else (|error| error.into_inner ());
+
+        if buffer.capacity > 0 {
+
+            if buffer.records.len () == buffer.capacity {
+                buffer.records.pop_front ();
+            }
+
+            buffer.records.push_back (record.clone ());
+            buffer.next_sequence += 1;
+        }
+
+        Ok (())
+    }
+}
@@ -0,0 +1,746 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate lz4_flex;
+extern crate xxhash_rust;
+extern crate zstd;
+
+use {Vfs, VfsMetadata, VfsSource};
+use archive_index::ArchiveIndex;
+
+use self::xxhash_rust::xxh3::xxh3_64;
+
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, SeekFrom};
+use std::io::prelude::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The pack writer struct
+///
+/// It builds a TriVert pack file, which is an archive designed for fast random access from a
+/// virtual file system. A pack file is laid out as:
+///
+/// * A header, holding the format version and the position and hash of the table of contents
+/// * The data of each file, compressed on its own so any file can be read without the others
+/// * The table of contents, holding the path, position, sizes, compression, modification time
+///   and content hash of each file
+///
+/// All numbers are little endian, and hashes are 64 bit XXH3 hashes. Files can be stored
+/// as is, or compressed with LZ4 (fast) or zstd (small). Files which don't get smaller are
+/// stored as is.
+///
+/// # Examples
+/// ```no_run
+/// # use trivert_utility::{PackCompression, PackWriter};
+/// let mut writer = PackWriter::create ("data.pack").unwrap ();
+///
+/// writer.add ("config/default.toml", b"[video]\nvsync = true\n", PackCompression::None)
+///       .unwrap ();
+/// writer.add_directory ("assets", PackCompression::Zstd).unwrap ();
+/// writer.finish ().unwrap ();
+/// ```
+pub struct PackWriter {
+
+    // Private
+    file       : BufWriter <File>,
+    entries    : Vec <PackEntry>,
+    paths      : ArchiveIndex <()>,
+    offset     : u64,
+    zstd_level : i32
+}
+
+/*================================================================================================*/
+
+/// The pack source struct
+///
+/// It provides the files in a TriVert pack file to a virtual file system. The table of
+/// contents is read and checked when the pack is opened, and the pack is kept open to read
+/// files from.
+///
+/// The content hash of each file is checked whenever it is read, so corrupted data is
+/// reported as an error rather than returned.
+pub struct PackSource {
+
+    // Private
+    path  : PathBuf,
+    file  : Mutex <File>,
+    index : ArchiveIndex <PackEntry>
+}
+
+/*================================================================================================*/
+
+/// The pack entry struct
+///
+/// It describes a file in a pack, as listed in its table of contents.
+#[derive (Clone, Debug, PartialEq, Eq)]
+pub struct PackEntry {
+
+    // Public
+    /// The virtual path of the file.
+    pub path        : String,
+    /// How the file is compressed.
+    pub compression : PackCompression,
+    /// The position of the file's data in the pack.
+    pub offset      : u64,
+    /// The size of the file's data in the pack.
+    pub stored_size : u64,
+    /// The size of the file once decompressed.
+    pub size        : u64,
+    /// The XXH3 hash of the decompressed file.
+    pub hash        : u64,
+    /// When the file was last modified, if known.
+    pub modified    : Option <SystemTime>
+}
+
+/*================================================================================================*/
+
+// Reads little endian numbers from a slice, failing if it runs out
+struct ByteReader<'a> {
+
+    data     : &'a [u8],
+    position : usize
+}
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The pack compression enum
+///
+/// It sets how a file is compressed in a pack.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PackCompression {
+
+    /// Stored as is
+    None,
+    /// Compressed with LZ4, which is very fast to decompress
+    Lz4,
+    /// Compressed with zstd, which is smaller but slower to decompress
+    Zstd
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The magic number at the start of every pack
+const MAGIC : [u8; 4] = *b"TVPK";
+
+// The version of the format written
+const VERSION : u16 = 1;
+
+// The size of the header, which is followed by the file data
+const HEADER_SIZE : u64 = 36;
+
+// The most LZ4 can expand data by
+const LZ4_MAX_RATIO : u64 = 255;
+
+// The most zstd can expand data by, as a block of at least four bytes gives at most 128KiB
+const ZSTD_MAX_RATIO : u64 = 32768;
+
+// The modification time written when it isn't known
+const UNKNOWN_TIME : u64 = u64::MAX;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl PackWriter {
+
+    /// Creates a new pack file, replacing any existing file.
+    ///
+    /// The pack isn't valid until it is finished.
+    pub fn create<P : AsRef <Path>> (path : P) -> io::Result <PackWriter> {
+
+        let mut file = BufWriter::new (File::create (path)?);
+
+        file.write_all (&[0; HEADER_SIZE as usize])?;
+
+        Ok (PackWriter {file,
+                        entries    : Vec::new (),
+                        paths      : ArchiveIndex::new (),
+                        offset     : HEADER_SIZE,
+                        zstd_level : 19})
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets the zstd compression level, from 1 (fastest) to 22 (smallest). Defaults to 19.
+    pub fn set_zstd_level (&mut self, level : i32) {
+
+        self.zstd_level = level;
+    }
+
+/*================================================================================================*/
+
+    /// Adds a file to the pack, with an unknown modification time.
+    pub fn add (&mut self,
+                path        : &str,
+                data        : &[u8],
+                compression : PackCompression) -> io::Result <()> {
+
+        self.add_with_time (path, data, compression, None)
+    }
+
+/*================================================================================================*/
+
+    /// Adds a file to the pack.
+    ///
+    /// The path is normalized as a virtual path. It must not already be in the pack, and a file
+    /// can't share its path with a directory of another file (such as `a` and `a/b`).
+    pub fn add_with_time (&mut self,
+                          path        : &str,
+                          data        : &[u8],
+                          compression : PackCompression,
+                          modified    : Option <SystemTime>) -> io::Result <()> {
+
+        let path = match Vfs::normalize (path) {
+            Ok (ref path) if path.is_empty () => return Err (invalid_input ("empty pack path")),
+            Ok (path)                         => path,
+            Err (error)                       => return Err (invalid_input (error.to_string ()))
+        };
+
+        if path.len () > u16::MAX as usize {
+            return Err (invalid_input ("pack path is too long"));
+        }
+
+        let metadata = VfsMetadata {is_dir   : false,
+                                    size     : data.len () as u64,
+                                    modified};
+
+        self.paths.insert (path.clone (), Some (()), metadata)?;
+
+        let compressed = match compression {
+            PackCompression::None => None,
+            PackCompression::Lz4  => Some (lz4_flex::compress (data)),
+            PackCompression::Zstd => Some (zstd::bulk::compress (data, self.zstd_level)?)
+        };
+
+        let (compression, stored) = match compressed {
+
+            Some (ref compressed) if compressed.len () < data.len () => {
+                (compression, &compressed[..])
+            },
+
+            _ => (PackCompression::None, data)
+        };
+
+        self.file.write_all (stored)?;
+
+        self.entries.push (PackEntry {path,
+                                      compression,
+                                      offset      : self.offset,
+                                      stored_size : stored.len () as u64,
+                                      size        : data.len () as u64,
+                                      hash        : xxh3_64 (data),
+                                      modified});
+
+        self.offset += stored.len () as u64;
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    /// Adds a file from disk to the pack, at the given virtual path.
+    pub fn add_file<P : AsRef <Path>> (&mut self,
+                                       path        : &str,
+                                       real_path   : P,
+                                       compression : PackCompression) -> io::Result <()> {
+
+        let real_path = real_path.as_ref ();
+        let modified  = fs::metadata (real_path)?.modified ().ok ();
+        let data      = fs::read (real_path)?;
+
+        self.add_with_time (path, &data, compression, modified)
+    }
+
+/*================================================================================================*/
+
+    /// Adds every file in a directory on disk to the pack, including its subdirectories.
+    ///
+    /// The files are added in order of their paths relative to the directory, which are used
+    /// as their virtual paths. Returns the number of files added.
+    pub fn add_directory<P : AsRef <Path>> (&mut self,
+                                            directory   : P,
+                                            compression : PackCompression) -> io::Result <usize> {
+
+        let mut files = Vec::new ();
+
+        find_files (directory.as_ref (), String::new (), &mut files)?;
+        files.sort ();
+
+        for (path, real_path) in &files {
+            self.add_file (path, real_path, compression)?;
+        }
+
+        Ok (files.len ())
+    }
+
+/*================================================================================================*/
+
+    /// Writes the table of contents and header, completing the pack.
+    ///
+    /// Returns the entries of every file in the pack.
+    pub fn finish (mut self) -> io::Result <Vec <PackEntry>> {
+
+        let mut contents = Vec::new ();
+
+        for entry in &self.entries {
+
+            let modified = entry.modified
+                                .and_then (|time| time.duration_since (UNIX_EPOCH).ok ())
+                                .map_or (UNKNOWN_TIME, |time| time.as_secs ());
+
+            contents.extend_from_slice (&(entry.path.len () as u16).to_le_bytes ());
+            contents.extend_from_slice (entry.path.as_bytes ());
+            contents.push (entry.compression.code ());
+            contents.extend_from_slice (&entry.offset.to_le_bytes ());
+            contents.extend_from_slice (&entry.stored_size.to_le_bytes ());
+            contents.extend_from_slice (&entry.size.to_le_bytes ());
+            contents.extend_from_slice (&entry.hash.to_le_bytes ());
+            contents.extend_from_slice (&modified.to_le_bytes ());
+        }
+
+        self.file.write_all (&contents)?;
+
+        let mut header = Vec::with_capacity (HEADER_SIZE as usize);
+
+        header.extend_from_slice (&MAGIC);
+        header.extend_from_slice (&VERSION.to_le_bytes ());
+        header.extend_from_slice (&0u16.to_le_bytes ());
+        header.extend_from_slice (&(self.entries.len () as u32).to_le_bytes ());
+        header.extend_from_slice (&self.offset.to_le_bytes ());
+        header.extend_from_slice (&(contents.len () as u64).to_le_bytes ());
+        header.extend_from_slice (&xxh3_64 (&contents).to_le_bytes ());
+
+        self.file.seek (SeekFrom::Start (0))?;
+        self.file.write_all (&header)?;
+        self.file.flush ()?;
+
+        Ok (self.entries)
+    }
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl PackSource {
+
+    /// Opens a pack file, reading and checking its table of contents.
+    pub fn new<P : AsRef <Path>> (path : P) -> io::Result <PackSource> {
+
+        let path       = path.as_ref ();
+        let mut file   = File::open (path)?;
+        let length     = file.metadata ()?.len ();
+        let mut header = [0; HEADER_SIZE as usize];
+
+        file.read_exact (&mut header).map_err (|_| invalid_data ("not a pack file"))?;
+
+        let mut reader = ByteReader::new (&header);
+
+        if reader.bytes (4)? != MAGIC {
+            return Err (invalid_data ("not a pack file"));
+        }
+
+        if reader.u16 ()? != VERSION {
+            return Err (invalid_data ("unsupported pack version"));
+        }
+
+        let _flags        = reader.u16 ()?;
+        let count         = reader.u32 ()?;
+        let contents_at   = reader.u64 ()?;
+        let contents_size = reader.u64 ()?;
+        let contents_hash = reader.u64 ()?;
+
+        if contents_at < HEADER_SIZE || contents_at.checked_add (contents_size) != Some (length) {
+            return Err (invalid_data ("pack file is truncated"));
+        }
+
+        let mut contents = vec! [0; to_usize (contents_size)?];
+
+        file.seek (SeekFrom::Start (contents_at))?;
+        file.read_exact (&mut contents)?;
+
+        if xxh3_64 (&contents) != contents_hash {
+            return Err (invalid_data ("pack table of contents is corrupt"));
+        }
+
+        let mut reader = ByteReader::new (&contents);
+        let mut index  = ArchiveIndex::new ();
+
+        for _ in 0..count {
+
+            let entry = read_entry (&mut reader)?;
+
+            if entry.offset < HEADER_SIZE ||
+               entry.offset.checked_add (entry.stored_size).is_none_or (|end| end > contents_at) {
+                return Err (invalid_data ("pack entry is out of bounds"));
+            }
+
+            // The hash doesn't stop a crafted size, which would be allocated when reading
+            if !entry.has_plausible_size () {
+                return Err (invalid_data ("pack entry has an invalid size"));
+            }
+
+            let metadata = VfsMetadata {is_dir   : false,
+                                        size     : entry.size,
+                                        modified : entry.modified};
+
+            index.insert (entry.path.clone (), Some (entry), metadata)
+                 .map_err (|error| invalid_data (error.to_string ()))?;
+        }
+
+        Ok (PackSource {path  : path.to_path_buf (),
+                        file  : Mutex::new (file),
+                        index})
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Returns the path of the pack.
+    pub fn path (&self) -> &Path {
+
+        &self.path
+    }
+
+/*================================================================================================*/
+
+    /// Returns the entries of every file in the pack, in order of their paths.
+    pub fn entries (&self) -> Vec <&PackEntry> {
+
+        self.index.files ().map (|(_, entry, _)| entry).collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Reads and decompresses a file, checking its content hash.
+    pub fn read_entry (&self, entry : &PackEntry) -> io::Result <Vec <u8>> {
+
+        let mut stored = vec! [0; to_usize (entry.stored_size)?];
+
+        {
+            let mut file = self.lock ();
+
+            file.seek (SeekFrom::Start (entry.offset))?;
+            file.read_exact (&mut stored)?;
+        }
+
+        let data = match entry.compression {
+
+            PackCompression::None => stored,
+
+            PackCompression::Lz4 => lz4_flex::decompress (&stored, to_usize (entry.size)?)
+                                        .map_err (|error| invalid_data (error.to_string ()))?,
+
+            // The size written in the frame has to match, as it is allocated up front
+            PackCompression::Zstd => {
+
+                let frame_size = zstd::zstd_safe::get_frame_content_size (&stored).ok ()
+                                                                                  .flatten ();
+
+                if frame_size != Some (entry.size) {
+                    return Err (invalid_data (format! ("\"{}\" failed its integrity check",
+                                                       entry.path)));
+                }
+
+                zstd::bulk::decompress (&stored, to_usize (entry.size)?)?
+            }
+        };
+
+        if data.len () as u64 != entry.size || xxh3_64 (&data) != entry.hash {
+
+            return Err (invalid_data (format! ("\"{}\" failed its integrity check",
+                                               entry.path)));
+        }
+
+        Ok (data)
+    }
+
+/*================================================================================================*/
+
+    /// Reads every file in the pack, checking their content hashes.
+    ///
+    /// Returns the number of files checked, or the first error found.
+    pub fn verify (&self) -> io::Result <usize> {
+
+        let entries = self.entries ();
+
+        for entry in &entries {
+            self.read_entry (entry)?;
+        }
+
+        Ok (entries.len ())
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Locks the file, ignoring poisoning as reading never leaves it in an invalid state
+    fn lock (&self) -> MutexGuard <'_, File> {
+
+        self.file.lock ().unwrap_or_else (|error| error.into_inner ())
+    }
+}
+
+/*================================================================================================*/
+
+impl PackEntry {
+
+    // Checks if the decompressed size is possible for the stored size, as stored files aren't
+    // compressed at all, and neither LZ4 nor zstd can expand data past a fixed ratio
+    fn has_plausible_size (&self) -> bool {
+
+        if usize::try_from (self.size).is_err () || usize::try_from (self.stored_size).is_err () {
+            return false;
+        }
+
+        match self.compression {
+
+            PackCompression::None => self.size == self.stored_size,
+            PackCompression::Lz4  => self.size <= self.stored_size.saturating_mul (LZ4_MAX_RATIO),
+            PackCompression::Zstd => self.size <= self.stored_size.saturating_mul (ZSTD_MAX_RATIO)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl PackCompression {
+
+    // Returns the code of the compression in the table of contents
+    fn code (self) -> u8 {
+
+        match self {
+            PackCompression::None => 0,
+            PackCompression::Lz4  => 1,
+            PackCompression::Zstd => 2
+        }
+    }
+
+/*================================================================================================*/
+
+    // Returns the compression with the given code
+    fn from_code (code : u8) -> Option <PackCompression> {
+
+        match code {
+            0 => Some (PackCompression::None),
+            1 => Some (PackCompression::Lz4),
+            2 => Some (PackCompression::Zstd),
+            _ => None
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> ByteReader<'a> {
+
+    // Creates a reader at the start of a slice
+    fn new (data : &'a [u8]) -> ByteReader<'a> {
+
+        ByteReader {data,
+                    position : 0}
+    }
+
+/*================================================================================================*/
+
+    // Reads the given number of bytes
+    fn bytes (&mut self, count : usize) -> io::Result <&'a [u8]> {
+
+        let end = self.position.checked_add (count)
+                               .filter (|&end| end <= self.data.len ())
+                               .ok_or_else (|| invalid_data ("pack data ended early"))?;
+
+        let bytes     = &self.data[self.position..end];
+        self.position = end;
+
+        Ok (bytes)
+    }
+
+/*================================================================================================*/
+
+    // Reads a byte
+    fn u8 (&mut self) -> io::Result <u8> {
+
+        Ok (self.bytes (1)?[0])
+    }
+
+/*================================================================================================*/
+
+    // Reads a 16 bit number
+    fn u16 (&mut self) -> io::Result <u16> {
+
+        let mut bytes = [0; 2];
+
+        bytes.copy_from_slice (self.bytes (2)?);
+        Ok (u16::from_le_bytes (bytes))
+    }
+
+/*================================================================================================*/
+
+    // Reads a 32 bit number
+    fn u32 (&mut self) -> io::Result <u32> {
+
+        let mut bytes = [0; 4];
+
+        bytes.copy_from_slice (self.bytes (4)?);
+        Ok (u32::from_le_bytes (bytes))
+    }
+
+/*================================================================================================*/
+
+    // Reads a 64 bit number
+    fn u64 (&mut self) -> io::Result <u64> {
+
+        let mut bytes = [0; 8];
+
+        bytes.copy_from_slice (self.bytes (8)?);
+        Ok (u64::from_le_bytes (bytes))
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl VfsSource for PackSource {
+
+    // Reads the file from the pack
+    fn read (&self, path : &str) -> io::Result <Vec <u8>> {
+
+        match self.index.file (path) {
+            Some (entry) => self.read_entry (entry),
+            None         => Err (io::Error::new (io::ErrorKind::NotFound, "file not in pack"))
+        }
+    }
+
+/*================================================================================================*/
+
+    // Returns the indexed metadata
+    fn metadata (&self, path : &str) -> Option <VfsMetadata> {
+
+        self.index.metadata (path)
+    }
+
+/*================================================================================================*/
+
+    // Returns the indexed entries directly inside the directory
+    fn list (&self, path : &str) -> io::Result <Vec <(String, VfsMetadata)>> {
+
+        self.index.list (path)
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Converts a size from a pack to a buffer size, failing if it doesn't fit
+fn to_usize (size : u64) -> io::Result <usize> {
+
+    usize::try_from (size).map_err (|_| invalid_data ("pack entry is too large"))
+}
+
+/*================================================================================================*/
+
+// Reads an entry from the table of contents
+fn read_entry (reader : &mut ByteReader) -> io::Result <PackEntry> {
+
+    let length = reader.u16 ()? as usize;
+    let path   = String::from_utf8 (reader.bytes (length)?.to_vec ())
+                        .map_err (|_| invalid_data ("pack path is not valid unicode"))?;
+
+    if Vfs::normalize (&path).ok ().as_ref () != Some (&path) || path.is_empty () {
+        return Err (invalid_data ("pack path is invalid"));
+    }
+
+    let compression = PackCompression::from_code (reader.u8 ()?)
+                                      .ok_or_else (|| invalid_data ("unknown pack compression"))?;
+    let offset      = reader.u64 ()?;
+    let stored_size = reader.u64 ()?;
+    let size        = reader.u64 ()?;
+    let hash        = reader.u64 ()?;
+
+    let modified = match reader.u64 ()? {
+        UNKNOWN_TIME => None,
+        seconds      => UNIX_EPOCH.checked_add (Duration::from_secs (seconds))
+    };
+
+    Ok (PackEntry {path,
+                   compression,
+                   offset,
+                   stored_size,
+                   size,
+                   hash,
+                   modified})
+}
+
+/*================================================================================================*/
+
+// Finds the files in a directory and its subdirectories, with their relative virtual paths
+fn find_files (directory : &Path,
+               prefix    : String,
+               files     : &mut Vec <(String, PathBuf)>) -> io::Result <()> {
+
+    for entry in fs::read_dir (directory)? {
+
+        let entry = entry?;
+        let name  = entry.file_name ().into_string ().map_err (|name| {
+            invalid_data (format! ("{:?} is not valid unicode", name))
+        })?;
+
+        let path = format! ("{}{}", prefix, name);
+
+        if entry.file_type ()?.is_dir () {
+            find_files (&entry.path (), format! ("{}/", path), files)?;
+        } else {
+            files.push ((path, entry.path ()));
+        }
+    }
+
+    Ok (())
+}
+
+/*================================================================================================*/
+
+// Creates an error for malformed pack data
+fn invalid_data<E : Into <String>> (message : E) -> io::Error {
+
+    io::Error::new (io::ErrorKind::InvalidData, message.into ())
+}
+
+/*================================================================================================*/
+
+// Creates an error for a bad argument
+fn invalid_input<E : Into <String>> (message : E) -> io::Error {
+
+    io::Error::new (io::ErrorKind::InvalidInput, message.into ())
+}
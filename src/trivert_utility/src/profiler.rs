@@ -0,0 +1,836 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use formatter::write_json_string;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io;
+use std::io::prelude::Write;
+use std::marker::PhantomData;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The profiler struct
+///
+/// It is an instrumentation profiler, which times named scopes of code. Scopes nest, and are
+/// gathered into a call tree for each thread, where calls to the same scope under the same
+/// parent are merged together.
+///
+/// The call trees are collected into a frame each time `end_frame` is called, and the most
+/// recent frames are kept to calculate min/avg/max statistics, or to be exported as Chrome
+/// Trace Event JSON (which can be opened in `chrome://tracing` or Perfetto).
+///
+/// Profilers can be freely cloned and sent between threads, with every clone sharing the same
+/// frames. Each thread records its scopes into a buffer of its own, which are only gathered
+/// when a frame ends, so profiling several threads doesn't make them wait on each other.
+#[derive (Clone)]
+pub struct Profiler {
+
+    // Private
+    state : Arc <ProfilerState>
+}
+
+/*================================================================================================*/
+
+/// The profile scope struct
+///
+/// It times a scope of code from when it is created until it is dropped. Scopes must be
+/// dropped in the reverse order they were created in, which happens naturally when they are
+/// kept in local variables.
+pub struct ProfileScope {
+
+    // Private
+    buffer  : Option <Arc <Mutex <ThreadState>>>,
+    depth   : usize,
+    phantom : PhantomData <*const ()> // Scopes belong to a thread, so can't be sent
+}
+
+/*================================================================================================*/
+
+/// The profile frame struct
+///
+/// It holds everything which was profiled during a single frame.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ProfileFrame {
+
+    // Public
+    /// The number of the frame, counting from zero.
+    pub index    : u64,
+    /// The time the frame started, since the profiler was created.
+    pub start    : Duration,
+    /// The length of the frame.
+    pub duration : Duration,
+    /// The threads which finished scopes during the frame.
+    pub threads  : Vec <ThreadProfile>
+}
+
+/*================================================================================================*/
+
+/// The thread profile struct
+///
+/// It holds the scopes a single thread finished during a frame.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ThreadProfile {
+
+    // Public
+    /// The index of the thread within the profiler, which is never reused for another thread.
+    pub thread : usize,
+    /// The name of the thread.
+    pub name   : String,
+    /// The root scopes of the call tree.
+    pub roots  : Vec <ProfileNode>,
+    /// Every finished scope, in the order they finished.
+    pub events : Vec <ProfileEvent>
+}
+
+/*================================================================================================*/
+
+/// The profile node struct
+///
+/// It is a scope within a call tree, with every call to it from the same parent merged.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ProfileNode {
+
+    // Public
+    /// The name of the scope.
+    pub name     : String,
+    /// The number of times the scope finished.
+    pub calls    : u32,
+    /// The total time spent in the scope.
+    pub total    : Duration,
+    /// The scopes entered from within this scope.
+    pub children : Vec <ProfileNode>
+}
+
+/*================================================================================================*/
+
+/// The profile event struct
+///
+/// It is a single call to a scope.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ProfileEvent {
+
+    // Public
+    /// The name of the scope.
+    pub name     : String,
+    /// The number of scopes the scope was entered in.
+    pub depth    : usize,
+    /// The time the scope was entered, since the profiler was created.
+    pub start    : Duration,
+    /// The time spent in the scope.
+    pub duration : Duration
+}
+
+/*================================================================================================*/
+
+/// The profile stats struct
+///
+/// It holds the statistics of a scope across the frames kept by a profiler. Frames which
+/// didn't finish the scope are left out.
+#[derive (Clone, Debug, PartialEq)]
+pub struct ProfileStats {
+
+    // Public
+    /// The name of the thread, which is empty for the frame stats.
+    pub thread : String,
+    /// The path of the scope in the call tree (e.g. `update/physics`).
+    pub path   : String,
+    /// The number of frames the scope finished in.
+    pub frames : usize,
+    /// The total number of calls to the scope.
+    pub calls  : u64,
+    /// The shortest time spent in the scope in a frame.
+    pub min    : Duration,
+    /// The average time spent in the scope in a frame.
+    pub avg    : Duration,
+    /// The longest time spent in the scope in a frame.
+    pub max    : Duration
+}
+
+/*================================================================================================*/
+
+// The state shared by the clones of a profiler
+struct ProfilerState {
+
+    id          : u64,
+    enabled     : AtomicBool,
+    epoch       : Instant,
+    next_thread : AtomicUsize,
+    threads     : Mutex <Vec <Arc <Mutex <ThreadState>>>>,
+    frames      : Mutex <FrameHistory>
+}
+
+/*================================================================================================*/
+
+// The frames kept by a profiler, along with the frame in progress
+struct FrameHistory {
+
+    frame_index : u64,
+    frame_start : Instant,
+    history     : VecDeque <ProfileFrame>,
+    capacity    : usize
+}
+
+/*================================================================================================*/
+
+// The scopes of a thread during the current frame, which only the thread itself records into
+struct ThreadState {
+
+    index  : usize,
+    name   : String,
+    epoch  : Instant,
+    stack  : Vec <OpenScope>,
+    roots  : Vec <ProfileNode>,
+    events : Vec <ProfileEvent>
+}
+
+/*================================================================================================*/
+
+// A scope which has been entered, but not yet finished
+struct OpenScope {
+
+    name  : String,
+    start : Instant
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The id given to the next profiler created, to find its buffers
+static NEXT_ID : AtomicU64 = AtomicU64::new (0);
+
+thread_local! {
+
+    // The buffers of the current thread, along with the ids of the profilers they belong to
+    static BUFFERS : RefCell <Vec <(u64, Arc <Mutex <ThreadState>>)>> = const {
+        RefCell::new (Vec::new ())
+    };
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Profiler {
+
+    /// Creates a new profiler, which keeps up to `history` frames.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::Profiler;
+    /// let profiler = Profiler::new (300);
+    ///
+    /// {
+    ///     let _update = profiler.scope ("update");
+    ///     let _physics = profiler.scope ("physics");
+    ///
+    ///     // ...
+    /// }
+    ///
+    /// profiler.end_frame ();
+    ///
+    /// for stats in profiler.stats () {
+    ///     println! ("{}", stats);
+    /// }
+    /// ```
+    pub fn new (history : usize) -> Profiler {
+
+        let now      = Instant::now ();
+        let capacity = history.max (1);
+
+        let frames = FrameHistory {frame_index : 0,
+                                   frame_start : now,
+                                   history     : VecDeque::with_capacity (capacity),
+                                   capacity};
+
+        let state = ProfilerState {id          : NEXT_ID.fetch_add (1, Ordering::Relaxed),
+                                   enabled     : AtomicBool::new (true),
+                                   epoch       : now,
+                                   next_thread : AtomicUsize::new (0),
+                                   threads     : Mutex::new (Vec::new ()),
+                                   frames      : Mutex::new (frames)};
+
+        Profiler {state : Arc::new (state)}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Enters a named scope on the current thread, until the returned guard is dropped.
+    ///
+    /// Nothing is timed while the profiler is disabled.
+    pub fn scope (&self, name : &str) -> ProfileScope {
+
+        if !self.is_enabled () {
+
+            return ProfileScope {buffer  : None,
+                                 depth   : 0,
+                                 phantom : PhantomData};
+        }
+
+        let buffer = self.thread_buffer ();
+        let depth  = {
+
+            let mut thread = lock (&buffer);
+            let depth      = thread.stack.len ();
+
+            thread.stack.push (OpenScope {name  : name.to_string (),
+                                          start : Instant::now ()});
+            depth
+        };
+
+        ProfileScope {buffer  : Some (buffer),
+                      depth,
+                      phantom : PhantomData}
+    }
+
+/*================================================================================================*/
+
+    /// Ends the current frame, and starts the next one.
+    ///
+    /// The scopes finished since the last frame ended are collected into a new frame, dropping
+    /// the oldest frame if the history is full. Scopes still open are counted in the frame
+    /// they finish in.
+    pub fn end_frame (&self) {
+
+        let now         = Instant::now ();
+        let mut frames  = lock (&self.state.frames);
+        let mut buffers = lock (&self.state.threads);
+
+        if self.is_enabled () {
+
+            let threads = buffers.iter ().filter_map (|thread| lock (thread).take ()).collect ();
+
+            let frame = ProfileFrame {index    : frames.frame_index,
+                                      start    : frames.frame_start
+                                                       .duration_since (self.state.epoch),
+                                      duration : now.duration_since (frames.frame_start),
+                                      threads};
+
+            if frames.history.len () == frames.capacity {
+                frames.history.pop_front ();
+            }
+
+            frames.history.push_back (frame);
+        }
+
+        // Drop the buffers of threads which have exited, once everything they recorded is taken
+        buffers.retain (|thread| Arc::strong_count (thread) > 1 || !lock (thread).is_empty ());
+
+        frames.frame_index += 1;
+        frames.frame_start  = now;
+    }
+
+/*================================================================================================*/
+
+    /// Enables or disables the profiler.
+    ///
+    /// Frames ended while disabled aren't kept.
+    pub fn set_enabled (&self, enabled : bool) {
+
+        self.state.enabled.store (enabled, Ordering::Relaxed);
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the profiler is enabled.
+    pub fn is_enabled (&self) -> bool {
+
+        self.state.enabled.load (Ordering::Relaxed)
+    }
+
+/*================================================================================================*/
+
+    /// Returns the frames kept by the profiler, from oldest to newest.
+    pub fn frames (&self) -> Vec <ProfileFrame> {
+
+        lock (&self.state.frames).history.iter ().cloned ().collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the most recently ended frame.
+    pub fn last_frame (&self) -> Option <ProfileFrame> {
+
+        lock (&self.state.frames).history.back ().cloned ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the statistics of every scope across the frames kept, sorted by thread and path.
+    pub fn stats (&self) -> Vec <ProfileStats> {
+
+        let frames     = lock (&self.state.frames);
+        let mut totals = BTreeMap::new ();
+
+        for thread in frames.history.iter ().flat_map (|frame| frame.threads.iter ()) {
+            for node in &thread.roots {
+                collect_stats (&mut totals, &thread.name, "", node);
+            }
+        }
+
+        totals.into_values ().map (average).collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the statistics of the frame lengths, or `None` if no frames have been kept.
+    ///
+    /// The path of the stats is `frame`.
+    pub fn frame_stats (&self) -> Option <ProfileStats> {
+
+        let frames = lock (&self.state.frames);
+
+        if frames.history.is_empty () {
+            return None;
+        }
+
+        let mut stats = new_stats ("", "frame");
+
+        for frame in &frames.history {
+            add_sample (&mut stats, 1, frame.duration);
+        }
+
+        Some (average (stats))
+    }
+
+/*================================================================================================*/
+
+    /// Removes every frame kept, along with the scopes finished during the current frame.
+    pub fn clear (&self) {
+
+        lock (&self.state.frames).history.clear ();
+
+        for thread in lock (&self.state.threads).iter () {
+
+            let mut thread = lock (thread);
+
+            thread.roots.clear ();
+            thread.events.clear ();
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Writes the frames kept as Chrome Trace Event JSON.
+    ///
+    /// Each scope is written as a complete event on its thread, and each frame as a complete
+    /// event on a separate `Frames` track.
+    pub fn write_chrome_trace<W : Write> (&self, writer : &mut W) -> io::Result<()> {
+
+        let trace = self.chrome_trace ();
+        writer.write_all (trace.as_bytes ())
+    }
+
+/*================================================================================================*/
+
+    /// Saves the frames kept to a Chrome Trace Event JSON file.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::Profiler;
+    /// let profiler = Profiler::new (300);
+    ///
+    /// // ...
+    ///
+    /// profiler.save_chrome_trace ("profile.json").unwrap ();
+    /// ```
+    pub fn save_chrome_trace<P : AsRef <Path>> (&self, path : P) -> io::Result<()> {
+
+        let mut file = File::create (path)?;
+        self.write_chrome_trace (&mut file)
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Returns the current thread's buffer, creating it if the thread hasn't been profiled before
+    fn thread_buffer (&self) -> Arc <Mutex <ThreadState>> {
+
+        BUFFERS.with (|buffers| {
+
+            let mut buffers = buffers.borrow_mut ();
+
+            if let Some ((_, buffer)) = buffers.iter ().find (|&&(id, _)| id == self.state.id) {
+                return buffer.clone ();
+            }
+
+            // Drop the buffers of profilers which no longer exist
+            buffers.retain (|(_, buffer)| Arc::strong_count (buffer) > 1);
+
+            let current = thread::current ();
+            let name    = match current.name () {
+                Some (name) => name.to_string (),
+                None        => format! ("{:?}", current.id ())
+            };
+
+            let index  = self.state.next_thread.fetch_add (1, Ordering::Relaxed);
+            let buffer = Arc::new (Mutex::new (ThreadState {index,
+                                                            name,
+                                                            epoch  : self.state.epoch,
+                                                            stack  : Vec::new (),
+                                                            roots  : Vec::new (),
+                                                            events : Vec::new ()}));
+
+            lock (&self.state.threads).push (buffer.clone ());
+            buffers.push ((self.state.id, buffer.clone ()));
+
+            buffer
+        })
+    }
+
+/*================================================================================================*/
+
+    // Builds the Chrome Trace Event JSON of the frames kept
+    fn chrome_trace (&self) -> String {
+
+        let frames     = lock (&self.state.frames);
+        let mut names  = BTreeMap::new ();
+        let mut events = vec! [metadata_event (0, "Frames")];
+
+        for thread in frames.history.iter ().flat_map (|frame| &frame.threads) {
+            names.insert (thread.thread, &thread.name);
+        }
+
+        for (index, name) in names {
+            events.push (metadata_event (index + 1, name));
+        }
+
+        for frame in &frames.history {
+
+            let name = format! ("Frame {}", frame.index);
+            events.push (complete_event (&name, "frame", 0, frame.start, frame.duration));
+
+            for thread in &frame.threads {
+                for event in &thread.events {
+                    events.push (complete_event (&event.name,
+                                                 "scope",
+                                                 thread.thread + 1,
+                                                 event.start,
+                                                 event.duration));
+                }
+            }
+        }
+
+        format! ("{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n", events.join (",\n"))
+    }
+}
+
+/*================================================================================================*/
+
+impl ProfileNode {
+
+    /// Returns the time spent in the scope itself, and not in its children.
+    pub fn self_time (&self) -> Duration {
+
+        let children = self.children.iter ().map (|child| child.total).sum ();
+        self.total.saturating_sub (children)
+    }
+}
+
+/*================================================================================================*/
+
+impl ProfileFrame {
+
+    /// Returns the total time spent in a scope during the frame across every thread, given its
+    /// path in the call tree (e.g. `update/physics`).
+    pub fn total (&self, path : &str) -> Duration {
+
+        self.threads.iter ()
+                    .filter_map (|thread| find_node (&thread.roots, path))
+                    .map (|node| node.total)
+                    .sum ()
+    }
+}
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl ThreadState {
+
+    // Finishes the scope at the given depth, along with any scopes still open inside it
+    fn close (&mut self, depth : usize, end : Instant) {
+
+        while self.stack.len () > depth {
+
+            let scope = match self.stack.pop () {
+                Some (scope) => scope,
+                None         => break
+            };
+
+            let duration = end.duration_since (scope.start);
+
+            let mut path : Vec <&str> = self.stack.iter ()
+                                               .map (|open| open.name.as_str ())
+                                               .collect ();
+            path.push (&scope.name);
+
+            let node = path_node (&mut self.roots, &path);
+
+            node.calls += 1;
+            node.total += duration;
+
+            self.events.push (ProfileEvent {name     : scope.name,
+                                            depth    : self.stack.len (),
+                                            start    : scope.start.duration_since (self.epoch),
+                                            duration});
+        }
+    }
+
+/*================================================================================================*/
+
+    // Takes the scopes finished during the frame, if there are any
+    fn take (&mut self) -> Option <ThreadProfile> {
+
+        if self.events.is_empty () {
+            return None;
+        }
+
+        Some (ThreadProfile {thread : self.index,
+                             name   : self.name.clone (),
+                             roots  : mem::take (&mut self.roots),
+                             events : mem::take (&mut self.events)})
+    }
+
+/*================================================================================================*/
+
+    // Returns whether the thread has nothing recorded, and no scopes open
+    fn is_empty (&self) -> bool {
+
+        self.stack.is_empty () && self.roots.is_empty () && self.events.is_empty ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Drop for ProfileScope {
+
+    // Finishes the scope, if it was entered while the profiler was enabled
+    fn drop (&mut self) {
+
+        if let Some (ref buffer) = self.buffer {
+
+            let end = Instant::now ();
+            lock (buffer).close (self.depth, end);
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl fmt::Display for ProfileStats {
+
+    // Writes the stats as a single line, with times in milliseconds
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f, "{}", self.path)?;
+
+        if !self.thread.is_empty () {
+            write! (f, " [{}]", self.thread)?;
+        }
+
+        write! (f,
+                ": min {:.3} ms, avg {:.3} ms, max {:.3} ms ({} frames, {} calls)",
+                milliseconds (self.min),
+                milliseconds (self.avg),
+                milliseconds (self.max),
+                self.frames,
+                self.calls)
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Locks a mutex, ignoring poisoning as the profiler's state is always left valid
+fn lock<T> (mutex : &Mutex <T>) -> MutexGuard <'_, T> {
+
+    mutex.lock ().unwrap_or_else (|error| error.into_inner ())
+}
+
+/*================================================================================================*/
+
+// Returns the node at a path in a call tree, adding any nodes which don't exist
+fn path_node<'a> (nodes : &'a mut Vec <ProfileNode>, path : &[&str]) -> &'a mut ProfileNode {
+
+    let (name, rest) = match path.split_first () {
+        Some ((name, rest)) => (*name, rest),
+        None                => panic! ("Node paths are never empty")
+    };
+
+    let index = match nodes.iter ().position (|node| node.name == name) {
+
+        Some (index) => index,
+
+        None => {
+
+            nodes.push (ProfileNode {name     : name.to_string (),
+                                     calls    : 0,
+                                     total    : Duration::from_secs (0),
+                                     children : Vec::new ()});
+            nodes.len () - 1
+        }
+    };
+
+    if rest.is_empty () {
+        &mut nodes[index]
+    } else {
+        path_node (&mut nodes[index].children, rest)
+    }
+}
+
+/*================================================================================================*/
+
+// Finds a node given its path in a call tree
+fn find_node<'a> (nodes : &'a [ProfileNode], path : &str) -> Option <&'a ProfileNode> {
+
+    let mut names = path.split ('/');
+    let first     = names.next ()?;
+    let mut node  = nodes.iter ().find (|node| node.name == first)?;
+
+    for name in names {
+        node = node.children.iter ().find (|node| node.name == name)?;
+    }
+
+    Some (node)
+}
+
+/*================================================================================================*/
+
+// Adds the nodes of a call tree to the stats of each path
+fn collect_stats (totals : &mut BTreeMap <(String, String), ProfileStats>,
+                  thread : &str,
+                  parent : &str,
+                  node   : &ProfileNode) {
+
+    let path = if parent.is_empty () {
+        node.name.clone ()
+    } else {
+        format! ("{}/{}", parent, node.name)
+    };
+
+    if node.calls > 0 {
+
+        let stats = totals.entry ((thread.to_string (), path.clone ()))
+                          .or_insert_with (|| new_stats (thread, &path));
+
+        add_sample (stats, node.calls, node.total);
+    }
+
+    for child in &node.children {
+        collect_stats (totals, thread, &path, child);
+    }
+}
+
+/*================================================================================================*/
+
+// Returns stats without any frames
+fn new_stats (thread : &str, path : &str) -> ProfileStats {
+
+    ProfileStats {thread : thread.to_string (),
+                  path   : path.to_string (),
+                  frames : 0,
+                  calls  : 0,
+                  min    : Duration::from_secs (0),
+                  avg    : Duration::from_secs (0),
+                  max    : Duration::from_secs (0)}
+}
+
+/*================================================================================================*/
+
+// Adds a frame's time to some stats, summing the times in the average until it is divided
+fn add_sample (stats : &mut ProfileStats, calls : u32, duration : Duration) {
+
+    stats.min     = if stats.frames == 0 {duration} else {stats.min.min (duration)};
+    stats.max     = stats.max.max (duration);
+    stats.avg    += duration;
+    stats.frames += 1;
+    stats.calls  += calls as u64;
+}
+
+/*================================================================================================*/
+
+// Divides the summed times of some stats by the number of frames
+fn average (mut stats : ProfileStats) -> ProfileStats {
+
+    stats.avg /= stats.frames as u32;
+    stats
+}
+
+/*================================================================================================*/
+
+// Returns a duration in milliseconds
+fn milliseconds (duration : Duration) -> f64 {
+
+    duration.as_secs_f64 () * 1000.0
+}
+
+/*================================================================================================*/
+
+// Returns a Chrome Trace metadata event naming a thread
+fn metadata_event (thread : usize, name : &str) -> String {
+
+    let mut event = String::new ();
+
+    let _ = write! (event, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},", thread);
+    event.push_str ("\"args\":{\"name\":");
+    write_json_string (&mut event, name);
+    event.push_str ("}}");
+
+    event
+}
+
+/*================================================================================================*/
+
+// Returns a Chrome Trace complete event, with times in microseconds
+fn complete_event (name     : &str,
+                   category : &str,
+                   thread   : usize,
+                   start    : Duration,
+                   duration : Duration) -> String {
+
+    let mut event = String::from ("{\"name\":");
+
+    write_json_string (&mut event, name);
+
+    let _ = write! (event,
+                    ",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},",
+                    category,
+                    start.as_secs_f64 () * 1000000.0,
+                    duration.as_secs_f64 () * 1000000.0);
+    let _ = write! (event, "\"pid\":1,\"tid\":{}}}", thread);
+
+    event
+}
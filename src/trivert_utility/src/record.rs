@@ -0,0 +1,234 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate time;
+
+use {LogContext, LogLevel, Value};
+use context;
+
+use std::fmt;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log record struct
+///
+/// It holds a single logged message, along with everything known about where and when it was
+/// logged. Records are created by the logger and handed to each of its sinks.
+#[derive (Clone, Debug, PartialEq)]
+pub struct LogRecord {
+
+    // Public
+    /// The severity of the message
+    pub level       : LogLevel,
+    /// The target the message was logged for (empty if none)
+    pub target      : String,
+    /// The formatted message
+    pub message     : String,
+    /// The time the message was logged at
+    pub time        : time::Timespec,
+    /// The time since logging started (when the first logger or record was created)
+    pub uptime      : Duration,
+    /// The module the message was logged from, if known
+    pub module_path : Option <String>,
+    /// The source file the message was logged from, if known
+    pub file        : Option <String>,
+    /// The line the message was logged from, if known
+    pub line        : Option <u32>,
+    /// The name of the thread the message was logged from, if it has one
+    pub thread_name : Option <String>,
+    /// The id of the thread the message was logged from
+    pub thread_id   : thread::ThreadId,
+    /// The structured fields of the record, starting with those of any entered contexts and
+    /// the path of any entered spans (as `span`)
+    pub fields      : Vec <(String, Value)>
+}
+
+/*================================================================================================*/
+
+/// The log location struct
+///
+/// It holds the place in the source code a message was logged from.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LogLocation {
+
+    // Public
+    /// The module path (from `module_path!`)
+    pub module_path : &'static str,
+    /// The source file (from `file!`)
+    pub file        : &'static str,
+    /// The line (from `line!`)
+    pub line        : u32
+}
+
+/*================================================================================================*/
+
+// Displays everything in a record after its level
+// (e.g. "(time) [thread] target: message key=value")
+pub struct RecordBody<'a> (pub &'a LogRecord);
+
+/*================================================================================================*/
+
+// Displays the name of a record's thread, or its id if unnamed
+pub struct ThreadName<'a> (pub &'a LogRecord);
+
+/*================================================================================================*/
+
+// Displays structured fields as space separated "key=value" pairs, with strings quoted
+pub struct Fields<'a> (pub &'a [(String, Value)]);
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogRecord {
+
+    /// Creates a new log record, timestamped with the current time and tagged with the
+    /// current thread. The record starts with the fields of the thread's entered contexts, and
+    /// the path of its entered spans.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogLevel, LogRecord};
+    /// let record = LogRecord::new (LogLevel::Info, "renderer", "Loaded 12 textures");
+    /// ```
+    pub fn new (level : LogLevel, target : &str, message : &str) -> LogRecord {
+
+        let thread     = thread::current ();
+        let mut fields = LogContext::fields ();
+
+        if let Some (path) = context::span_path () {
+            fields.push (("span".to_string (), Value::Str (path)));
+        }
+
+        LogRecord {level,
+                   target      : target.to_string (),
+                   message     : message.to_string (),
+                   time        : time::get_time (),
+                   uptime      : uptime (),
+                   module_path : None,
+                   file        : None,
+                   line        : None,
+                   thread_name : thread.name ().map (|name| name.to_string ()),
+                   thread_id   : thread.id (),
+                   fields}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Sets the module path, file and line the record was logged from.
+    pub fn set_location (&mut self, location : &LogLocation) {
+
+        self.module_path = Some (location.module_path.to_string ());
+        self.file        = Some (location.file.to_string ());
+        self.line        = Some (location.line);
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for LogRecord {
+
+    // Formats the record as a single log line, without a trailing newline
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        write! (f, "{} {}", self.level.label (), RecordBody (self))
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> fmt::Display for RecordBody<'a> {
+
+    // Formats the record without its level
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        let record = self.0;
+
+        write! (f, "({}) ", time::at (record.time).asctime ())?;
+
+        write! (f, "[{}] ", ThreadName (record))?;
+
+        if !record.target.is_empty () {
+            write! (f, "{}: ", record.target)?;
+        }
+
+        write! (f, "{}", record.message)?;
+
+        if !record.fields.is_empty () {
+            write! (f, " {}", Fields (&record.fields))?;
+        }
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> fmt::Display for ThreadName<'a> {
+
+    // Formats the thread name or id
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match self.0.thread_name {
+            Some (ref name) => write! (f, "{}", name),
+            None            => write! (f, "{:?}", self.0.thread_id)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> fmt::Display for Fields<'a> {
+
+    // Formats the fields
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        for (index, (key, value)) in self.0.iter ().enumerate () {
+
+            if index > 0 {
+                write! (f, " ")?;
+            }
+
+            match *value {
+                Value::Str (ref string) => write! (f, "{}={:?}", key, string)?,
+                _                       => write! (f, "{}={}", key, value)?
+            }
+        }
+
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns the time since logging started, starting the clock on first use
+pub fn uptime () -> Duration {
+
+    static START : OnceLock <Instant> = OnceLock::new ();
+
+    START.get_or_init (Instant::now).elapsed ()
+}
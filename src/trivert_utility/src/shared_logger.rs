@@ -0,0 +1,321 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {LogFilter, LogLevel, LogLocation, LogRecord, LogSink, Logger, Value};
+use logger::build_record;
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The shared logger struct
+///
+/// It is a handle to a logger which can be cloned and sent between threads, so the same
+/// logger can be used from anywhere in the program. Every clone refers to the same logger,
+/// with access synchronized internally.
+///
+/// Each record is tagged with the name and id of the thread it was logged from. The
+/// `trivert_log!` family of macros accept a shared logger in place of a logger.
+///
+/// Every shared logger is registered with the crash handler, so it can be flushed if the
+/// program panics.
+///
+/// # Examples
+/// ```no_run
+/// #[macro_use] extern crate trivert_utility;
+/// use trivert_utility::{Logger, SharedLogger};
+/// use std::thread;
+///
+/// # fn main () {
+/// let logger = SharedLogger::new (Logger::new ("log.txt").unwrap ());
+/// logger.begin_log ();
+///
+/// let audio_logger = logger.clone ();
+/// let audio        = thread::Builder::new ().name ("audio".to_string ()).spawn (move || {
+///     trivert_info! (audio_logger, "Audio device opened");
+/// }).unwrap ();
+///
+/// audio.join ().unwrap ();
+/// logger.end_log ();
+/// # }
+/// ```
+#[derive (Clone)]
+pub struct SharedLogger {
+
+    // Private
+    logger : Arc <Mutex <Logger>>
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// How long logging to every logger waits in total for loggers locked by other threads
+const LOCK_TIMEOUT : Duration = Duration::from_millis (100);
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl SharedLogger {
+
+    /// Creates a new shared logger handle, taking ownership of a logger.
+    pub fn new (logger : Logger) -> SharedLogger {
+
+        let logger = Arc::new (Mutex::new (logger));
+
+        let mut registry = lock_registry ();
+
+        registry.retain (|entry| entry.strong_count () > 0);
+        registry.push (Arc::downgrade (&logger));
+
+        SharedLogger {logger}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Locks the logger for direct access.
+    ///
+    /// Nothing can be logged through any other handle until the guard is dropped.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use trivert_utility::{FailurePolicy, Logger, SharedLogger};
+    /// let logger = SharedLogger::new (Logger::new ("log.txt").unwrap ());
+    /// logger.lock ().set_failure_policy (FailurePolicy::Drop);
+    /// ```
+    pub fn lock (&self) -> MutexGuard <'_, Logger> {
+
+        // Sinks are never left half written, so the logger is still usable after a panic
+        self.logger.lock ().unwrap_or_else (|error| error.into_inner ())
+    }
+
+/*================================================================================================*/
+
+    /// Starts logging (see `Logger::begin_log`).
+    pub fn begin_log (&self) {
+
+        self.lock ().begin_log ();
+    }
+
+/*================================================================================================*/
+
+    /// Attaches a sink (see `Logger::add_sink`).
+    pub fn add_sink<S : LogSink + 'static> (&self, sink : S) {
+
+        self.lock ().add_sink (sink);
+    }
+
+/*================================================================================================*/
+
+    /// Attaches a sink with its own filter (see `Logger::add_sink_with_filter`).
+    pub fn add_sink_with_filter<S : LogSink + 'static> (&self, sink : S, filter : LogFilter) {
+
+        self.lock ().add_sink_with_filter (sink, filter);
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a message of the given level and target would be logged.
+    pub fn is_enabled (&self, log_level : LogLevel, target : &str) -> bool {
+
+        self.lock ().is_enabled (log_level, target)
+    }
+
+/*================================================================================================*/
+
+    /// Replaces the filter used to decide which messages are logged.
+    pub fn set_filter (&self, filter : LogFilter) {
+
+        self.lock ().set_filter (filter);
+    }
+
+/*================================================================================================*/
+
+    /// Sets the minimum level logged for targets without their own filter level.
+    pub fn set_level (&self, log_level : Option <LogLevel>) {
+
+        self.lock ().set_level (log_level);
+    }
+
+/*================================================================================================*/
+
+    /// Checks if every sink has written successfully since the last error was cleared.
+    pub fn is_healthy (&self) -> bool {
+
+        self.lock ().is_healthy ()
+    }
+
+/*================================================================================================*/
+
+    /// Logs a message (see `Logger::log`).
+    pub fn log (&self, log_level : LogLevel, message : &str) {
+
+        self.lock ().log (log_level, message);
+    }
+
+/*================================================================================================*/
+
+    /// Logs a formatted message for a target (see `Logger::log_fmt`).
+    pub fn log_fmt (&self, log_level : LogLevel, target : &str, args : fmt::Arguments) {
+
+        self.log_fields (log_level, target, &[], args);
+    }
+
+/*================================================================================================*/
+
+    /// Logs a formatted message for a target, with structured fields (see
+    /// `Logger::log_fields`).
+    pub fn log_fields (&self,
+                       log_level : LogLevel,
+                       target    : &str,
+                       fields    : &[(&str, Value)],
+                       args      : fmt::Arguments) {
+
+        // The record is built before locking, so other threads aren't kept waiting on it
+        if self.is_enabled (log_level, target) {
+            self.lock ().log_record (&build_record (log_level, target, None, fields, args));
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Logs a formatted message for a target, with structured fields and its source location
+    /// (see `Logger::log_at`).
+    pub fn log_at (&self,
+                   log_level : LogLevel,
+                   target    : &str,
+                   location  : &LogLocation,
+                   fields    : &[(&str, Value)],
+                   args      : fmt::Arguments) {
+
+        if self.is_enabled (log_level, target) {
+
+            let record = build_record (log_level, target, Some (location), fields, args);
+            self.lock ().log_record (&record);
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Logs an already built record (see `Logger::log_record`).
+    pub fn log_record (&self, record : &LogRecord) {
+
+        self.lock ().log_record (record);
+    }
+
+/*================================================================================================*/
+
+    /// Flushes any buffered messages in every sink.
+    pub fn flush (&self) {
+
+        self.lock ().flush ();
+    }
+
+/*================================================================================================*/
+
+    /// Stops logging (see `Logger::end_log`).
+    pub fn end_log (&self) {
+
+        self.lock ().end_log ();
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns the registry of every shared logger still alive
+fn lock_registry () -> MutexGuard <'static, Vec <Weak <Mutex <Logger>>>> {
+
+    static REGISTRY : Mutex <Vec <Weak <Mutex <Logger>>>> = Mutex::new (Vec::new ());
+
+    REGISTRY.lock ().unwrap_or_else (|error| error.into_inner ())
+}
+
+/*================================================================================================*/
+
+// Logs a record to every shared logger still alive, then flushes them
+//
+// Loggers which are locked are retried until a short timeout, so ones briefly held by other
+// threads are still reached, then skipped (as they may be held by the panicking thread).
+// Returns the number of loggers flushed.
+pub fn log_to_all (record : &LogRecord) -> usize {
+
+    // Collect the loggers first, so the registry isn't locked while logging
+    let loggers : Vec <_> = lock_registry ().iter ()
+                                            .filter_map (|entry| entry.upgrade ())
+                                            .collect ();
+    let mut flushed       = 0;
+    let deadline          = Instant::now () + LOCK_TIMEOUT;
+
+    for logger in loggers {
+
+        let mut logger = match lock_before (&logger, deadline) {
+            Some (logger) => logger,
+            None          => continue
+        };
+
+        logger.log_record (record);
+        logger.flush ();
+
+        flushed += 1;
+    }
+
+    flushed
+}
+
+/*================================================================================================*/
+
+// Locks a logger, retrying until a deadline if another thread holds it
+fn lock_before (logger : &Mutex <Logger>, deadline : Instant) -> Option <MutexGuard <'_, Logger>> {
+
+    loop {
+
+        match logger.try_lock () {
+
+            Ok (logger)                          => return Some (logger),
+            Err (TryLockError::Poisoned (error)) => return Some (error.into_inner ()),
+            Err (TryLockError::WouldBlock)       => {}
+        }
+
+        if Instant::now () >= deadline {
+            return None;
+        }
+
+        thread::sleep (Duration::from_millis (1));
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl From <Logger> for SharedLogger {
+
+    // Wraps the logger in a shared handle
+    fn from (logger : Logger) -> SharedLogger {
+
+        SharedLogger::new (logger)
+    }
+}
@@ -0,0 +1,98 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use LogRecord;
+
+use std::io;
+
+/*================================================================================================*/
+/*------TRAITS------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The log sink trait
+///
+/// A sink is a destination for log records, such as a file or the console.
+/// Any number of sinks can be attached to a logger, each with their own filter.
+///
+/// Errors returned by a sink are handled by the logger, according to its failure policy.
+pub trait LogSink : Send {
+
+    /// Writes a single record to the sink.
+    fn write (&mut self, record : &LogRecord) -> io::Result <()>;
+
+    /// Flushes any buffered records.
+    fn flush (&mut self) -> io::Result <()> {
+        Ok (())
+    }
+
+    /// Called when the logger starts logging.
+    fn begin (&mut self) -> io::Result <()> {
+        Ok (())
+    }
+
+    /// Called when the logger stops logging.
+    fn end (&mut self) -> io::Result <()> {
+        Ok (())
+    }
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The callback sink struct
+///
+/// It passes every record it receives to a closure, allowing records to be sent anywhere
+/// without implementing `LogSink` by hand.
+pub struct CallbackSink {
+
+    // Private
+    callback : Box <dyn FnMut (&LogRecord) + Send>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl CallbackSink {
+
+    /// Creates a new callback sink.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::CallbackSink;
+    /// let sink = CallbackSink::new (|record| println! ("{}", record.message));
+    /// ```
+    pub fn new<F> (callback : F) -> CallbackSink
+        where F : FnMut (&LogRecord) + Send + 'static {
+
+        CallbackSink {callback : Box::new (callback)}
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl LogSink for CallbackSink {
+
+    // Passes the record to the callback
+    fn write (&mut self, record : &LogRecord) -> io::Result <()> {
+
+        (self.callback) (record);
+        Ok (())
+    }
+}
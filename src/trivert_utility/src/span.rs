@@ -0,0 +1,268 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {LogLevel, Logger, LogRecord, SharedLogger, Value};
+use context;
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The span struct
+///
+/// It marks a named region of code on the current thread, such as loading a level, from when
+/// it is created until it is dropped. An enter record is logged when the span is created,
+/// and an exit record with the time taken (as the `elapsed_ms` field) when it is dropped.
+///
+/// Spans nest, and every record logged from the thread while in a span gets a `span` field
+/// with the path of the spans it was logged in (e.g. `load_level/decode_textures`).
+///
+/// Spans created through a shared logger leave it free to log other messages while they are
+/// entered (see `LoggerSpan` for spans on a plain logger).
+pub struct Span {
+
+    // Private
+    logger  : SharedLogger,
+    name    : String,
+    target  : String,
+    level   : LogLevel,
+    depth   : usize,
+    start   : Instant,
+    phantom : PhantomData <*const ()> // Spans belong to a thread, so can't be sent
+}
+
+/*================================================================================================*/
+
+/// The logger span struct
+///
+/// It is a span (see `Span`) entered on a plain logger, which it borrows until it is dropped.
+/// The span dereferences to the logger, so messages (and nested spans) are logged through it
+/// while it is entered.
+pub struct LoggerSpan<'a> {
+
+    // Private
+    logger  : &'a mut Logger,
+    name    : String,
+    target  : String,
+    level   : LogLevel,
+    depth   : usize,
+    start   : Instant,
+    phantom : PhantomData <*const ()> // Spans belong to a thread, so can't be sent
+}
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Logger {
+
+    /// Enters a named span, until the returned guard is dropped.
+    ///
+    /// The enter and exit records are logged at the given level and target.
+    /// This is normally called through the `trivert_span!` macro.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogLevel, Logger};
+    /// let mut logger = Logger::empty ();
+    ///
+    /// {
+    ///     let mut span = logger.span (LogLevel::Debug, "world", "load_level");
+    ///
+    ///     span.log (LogLevel::Info, "Loading textures");
+    ///     let _nested = span.span (LogLevel::Debug, "world", "decode_textures");
+    /// }
+    /// ```
+    pub fn span (&mut self, level : LogLevel, target : &str, name : &str) -> LoggerSpan <'_> {
+
+        let depth = context::push_span (name);
+
+        if self.is_enabled (level, target) {
+            self.log_record (&enter_record (level, target, name));
+        }
+
+        LoggerSpan {logger  : self,
+                    name    : name.to_string (),
+                    target  : target.to_string (),
+                    level,
+                    depth,
+                    start   : Instant::now (),
+                    phantom : PhantomData}
+    }
+}
+
+/*================================================================================================*/
+
+impl SharedLogger {
+
+    /// Enters a named span, until the returned guard is dropped.
+    ///
+    /// The enter and exit records are logged at the given level and target.
+    /// This is normally called through the `trivert_span!` macro.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::{LogLevel, Logger, SharedLogger};
+    /// let logger = SharedLogger::new (Logger::empty ());
+    ///
+    /// {
+    ///     let _span = logger.span (LogLevel::Debug, "world", "load_level");
+    ///
+    ///     // ...
+    /// }
+    /// ```
+    pub fn span (&self, level : LogLevel, target : &str, name : &str) -> Span {
+
+        let depth = context::push_span (name);
+
+        if self.is_enabled (level, target) {
+            self.log_record (&enter_record (level, target, name));
+        }
+
+        Span {logger  : self.clone (),
+              name    : name.to_string (),
+              target  : target.to_string (),
+              level,
+              depth,
+              start   : Instant::now (),
+              phantom : PhantomData}
+    }
+}
+
+/*================================================================================================*/
+
+impl Span {
+
+    /// Returns the name of the span.
+    pub fn name (&self) -> &str {
+
+        &self.name
+    }
+
+/*================================================================================================*/
+
+    /// Returns the time since the span was entered.
+    pub fn elapsed (&self) -> Duration {
+
+        self.start.elapsed ()
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> LoggerSpan<'a> {
+
+    /// Returns the name of the span.
+    pub fn name (&self) -> &str {
+
+        &self.name
+    }
+
+/*================================================================================================*/
+
+    /// Returns the time since the span was entered.
+    pub fn elapsed (&self) -> Duration {
+
+        self.start.elapsed ()
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Drop for Span {
+
+    // Logs the exit record, then leaves the span
+    fn drop (&mut self) {
+
+        if self.logger.is_enabled (self.level, &self.target) {
+
+            let record = exit_record (self.level, &self.target, &self.name, self.elapsed ());
+            self.logger.log_record (&record);
+        }
+
+        context::pop_span (self.depth);
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Drop for LoggerSpan<'a> {
+
+    // Logs the exit record, then leaves the span
+    fn drop (&mut self) {
+
+        if self.logger.is_enabled (self.level, &self.target) {
+
+            let record = exit_record (self.level, &self.target, &self.name, self.elapsed ());
+            self.logger.log_record (&record);
+        }
+
+        context::pop_span (self.depth);
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> Deref for LoggerSpan<'a> {
+
+    type Target = Logger;
+
+    // Returns the logger the span was entered on
+    fn deref (&self) -> &Logger {
+
+        self.logger
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> DerefMut for LoggerSpan<'a> {
+
+    // Returns the logger the span was entered on
+    fn deref_mut (&mut self) -> &mut Logger {
+
+        self.logger
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Creates the record logged when a span is entered
+fn enter_record (level : LogLevel, target : &str, name : &str) -> LogRecord {
+
+    LogRecord::new (level, target, &format! ("enter {}", name))
+}
+
+/*================================================================================================*/
+
+// Creates the record logged when a span is left, with the time taken as a field
+fn exit_record (level : LogLevel, target : &str, name : &str, elapsed : Duration) -> LogRecord {
+
+    let elapsed_ms = elapsed.as_micros () as f64 / 1000.0;
+    let message    = format! ("exit {} ({:.3} ms)", name, elapsed_ms);
+    let mut record = LogRecord::new (level, target, &message);
+
+    record.fields.push (("elapsed_ms".to_string (), Value::Float (elapsed_ms)));
+    record
+}
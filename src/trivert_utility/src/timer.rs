@@ -0,0 +1,261 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::time::Duration;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The timer struct
+///
+/// It counts up to a duration as it is ticked with frame deltas, so it follows game time
+/// (including time scaling and pausing) when ticked with a clock's delta.
+///
+/// A timer can either finish once, or repeat, finishing each time the duration passes.
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use trivert_utility::{Timer, TimerMode};
+/// let mut spawn_timer = Timer::new (Duration::from_millis (500), TimerMode::Repeating);
+///
+/// // Finishes twice over a 1.2 second frame, with 0.2 seconds carried over
+/// assert_eq! (spawn_timer.tick (Duration::from_millis (1200)), 2);
+/// assert_eq! (spawn_timer.elapsed (), Duration::from_millis (200));
+/// ```
+#[derive (Copy, Clone, Debug, PartialEq)]
+pub struct Timer {
+
+    // Private
+    duration       : Duration,
+    elapsed        : Duration,
+    mode           : TimerMode,
+    finished       : bool,
+    times_finished : u32,
+    paused         : bool
+}
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The timer mode enum
+///
+/// It sets what a timer does once its duration has passed.
+#[derive (Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TimerMode {
+
+    /// Finishes once, staying finished until reset.
+    Once,
+    /// Finishes each time the duration passes, carrying over any extra time.
+    Repeating
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Timer {
+
+    /// Creates a new timer for the given duration.
+    pub fn new (duration : Duration, mode : TimerMode) -> Timer {
+
+        Timer {duration,
+               elapsed        : Duration::from_secs (0),
+               mode,
+               finished       : false,
+               times_finished : 0,
+               paused         : false}
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Advances the timer, returning the number of times it finished during the tick.
+    ///
+    /// A repeating timer can finish several times in a single long tick, while a timer with
+    /// a duration of zero finishes once per tick. Paused timers don't advance.
+    pub fn tick (&mut self, delta : Duration) -> u32 {
+
+        self.times_finished = 0;
+
+        if self.paused {
+            return 0;
+        }
+
+        match self.mode {
+
+            TimerMode::Once => {
+
+                if !self.finished {
+
+                    self.elapsed = (self.elapsed + delta).min (self.duration);
+
+                    if self.elapsed == self.duration {
+
+                        self.finished       = true;
+                        self.times_finished = 1;
+                    }
+                }
+            },
+
+            TimerMode::Repeating => {
+
+                self.elapsed += delta;
+
+                if self.duration == Duration::from_secs (0) {
+
+                    self.elapsed        = Duration::from_secs (0);
+                    self.times_finished = 1;
+                } else {
+
+                    let elapsed  = self.elapsed.as_nanos ();
+                    let duration = self.duration.as_nanos ();
+
+                    self.times_finished = (elapsed / duration).min (u32::MAX as u128) as u32;
+                    self.elapsed        = Duration::from_nanos ((elapsed % duration) as u64);
+                }
+
+                self.finished = self.times_finished > 0;
+            }
+        }
+
+        self.times_finished
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the timer has finished.
+    ///
+    /// A timer which finishes once stays finished until reset, while a repeating timer is only
+    /// finished on the ticks it finished during.
+    pub fn is_finished (&self) -> bool {
+
+        self.finished
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the timer finished during the last tick.
+    pub fn just_finished (&self) -> bool {
+
+        self.times_finished > 0
+    }
+
+/*================================================================================================*/
+
+    /// Returns the number of times the timer finished during the last tick.
+    pub fn times_finished (&self) -> u32 {
+
+        self.times_finished
+    }
+
+/*================================================================================================*/
+
+    /// Returns the time counted since the timer was started, or last repeated.
+    pub fn elapsed (&self) -> Duration {
+
+        self.elapsed
+    }
+
+/*================================================================================================*/
+
+    /// Returns the time left until the timer next finishes.
+    pub fn remaining (&self) -> Duration {
+
+        self.duration.saturating_sub (self.elapsed)
+    }
+
+/*================================================================================================*/
+
+    /// Returns how far through its duration the timer is, from `0.0` to `1.0`.
+    pub fn fraction (&self) -> f64 {
+
+        if self.duration == Duration::from_secs (0) {
+            return 1.0;
+        }
+
+        self.elapsed.as_secs_f64 () / self.duration.as_secs_f64 ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the duration of the timer.
+    pub fn duration (&self) -> Duration {
+
+        self.duration
+    }
+
+/*================================================================================================*/
+
+    /// Sets the duration of the timer, keeping the time counted so far.
+    pub fn set_duration (&mut self, duration : Duration) {
+
+        self.duration = duration;
+    }
+
+/*================================================================================================*/
+
+    /// Returns the mode of the timer.
+    pub fn mode (&self) -> TimerMode {
+
+        self.mode
+    }
+
+/*================================================================================================*/
+
+    /// Sets the mode of the timer.
+    pub fn set_mode (&mut self, mode : TimerMode) {
+
+        self.mode = mode;
+    }
+
+/*================================================================================================*/
+
+    /// Clears the time counted, so the timer starts again.
+    pub fn reset (&mut self) {
+
+        self.elapsed        = Duration::from_secs (0);
+        self.finished       = false;
+        self.times_finished = 0;
+    }
+
+/*================================================================================================*/
+
+    /// Pauses the timer, so ticks don't advance it.
+    pub fn pause (&mut self) {
+
+        self.paused = true;
+    }
+
+/*================================================================================================*/
+
+    /// Resumes the timer.
+    pub fn resume (&mut self) {
+
+        self.paused = false;
+    }
+
+/*================================================================================================*/
+
+    /// Checks if the timer is paused.
+    pub fn is_paused (&self) -> bool {
+
+        self.paused
+    }
+}
@@ -0,0 +1,197 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use std::convert::TryFrom;
+use std::fmt;
+
+/*================================================================================================*/
+/*------ENUMS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The value enum
+///
+/// It holds the value of a structured field attached to a log record, or of a config setting.
+/// Values can be created from most primitive types and strings using `From`.
+#[derive (Clone, Debug, PartialEq)]
+pub enum Value {
+
+    /// A boolean
+    Bool (bool),
+    /// A signed integer
+    Int (i64),
+    /// An unsigned integer
+    Uint (u64),
+    /// A floating point number
+    Float (f64),
+    /// A string
+    Str (String)
+}
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Value {
+
+    /// Returns the value as a boolean, if it is one.
+    pub fn as_bool (&self) -> Option <bool> {
+
+        match *self {
+
+            Value::Bool (value) => Some (value),
+            _                   => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value as a signed integer, if it is an integer which fits.
+    pub fn as_int (&self) -> Option <i64> {
+
+        match *self {
+
+            Value::Int (value)  => Some (value),
+            Value::Uint (value) => i64::try_from (value).ok (),
+            _                   => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value as an unsigned integer, if it is an integer which fits.
+    pub fn as_uint (&self) -> Option <u64> {
+
+        match *self {
+
+            Value::Int (value)  => u64::try_from (value).ok (),
+            Value::Uint (value) => Some (value),
+            _                   => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value as a floating point number, if it is a number.
+    pub fn as_float (&self) -> Option <f64> {
+
+        match *self {
+
+            Value::Int (value)   => Some (value as f64),
+            Value::Uint (value)  => Some (value as f64),
+            Value::Float (value) => Some (value),
+            _                    => None
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Returns the value as a string, if it is one.
+    pub fn as_str (&self) -> Option <&str> {
+
+        match *self {
+
+            Value::Str (ref value) => Some (value),
+            _                      => None
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl fmt::Display for Value {
+
+    // Formats the value, with strings written as is
+    fn fmt (&self, f : &mut fmt::Formatter) -> fmt::Result {
+
+        match *self {
+
+            Value::Bool (value)    => write! (f, "{}", value),
+            Value::Int (value)     => write! (f, "{}", value),
+            Value::Uint (value)    => write! (f, "{}", value),
+            Value::Float (value)   => write! (f, "{}", value),
+            Value::Str (ref value) => write! (f, "{}", value)
+        }
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+macro_rules! impl_from {
+
+    ($variant : ident, $target : ty, $($source : ty),+) => {$(
+
+        impl From <$source> for Value {
+
+            // Wraps the value, widening it if needed
+            fn from (value : $source) -> Value {
+
+                Value::$variant (value as $target)
+            }
+        }
+    )+}
+}
+
+impl_from! (Int,   i64, i8, i16, i32, i64, isize);
+impl_from! (Uint,  u64, u8, u16, u32, u64, usize);
+impl_from! (Float, f64, f32, f64);
+
+/*================================================================================================*/
+
+impl From <bool> for Value {
+
+    // Wraps the boolean
+    fn from (value : bool) -> Value {
+
+        Value::Bool (value)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> From <&'a str> for Value {
+
+    // Copies the string
+    fn from (value : &'a str) -> Value {
+
+        Value::Str (value.to_string ())
+    }
+}
+
+/*================================================================================================*/
+
+impl From <String> for Value {
+
+    // Wraps the string
+    fn from (value : String) -> Value {
+
+        Value::Str (value)
+    }
+}
+
+/*================================================================================================*/
+
+impl<'a> From <&'a String> for Value {
+
+    // Copies the string
+    fn from (value : &'a String) -> Value {
+
+        Value::Str (value.clone ())
+    }
+}
@@ -0,0 +1,502 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use {DirectorySource, PackSource, VfsError, VfsSource, ZipSource};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The virtual file system struct
+///
+/// It combines directories and archives into a single tree of files, so assets can be loaded
+/// the same way whether they are loose on disk or packed.
+///
+/// Each source is mounted at a virtual path, with a priority. When several sources have the
+/// same file, the one with the highest priority is used, with later mounts winning ties. This
+/// lets mods override the base game by being mounted with a higher priority.
+///
+/// Virtual paths use `/` as the separator, and are relative to the root. They are normalized
+/// before use, so `\` is also accepted, and `.` and `..` components are resolved (but can't
+/// leave the root).
+///
+/// # Examples
+/// ```no_run
+/// # use trivert_utility::Vfs;
+/// let mut vfs = Vfs::new ();
+///
+/// vfs.mount_pack ("", "data/base.pack", 0).unwrap ();
+/// vfs.mount_directory ("", "mods/hd_textures", 10).unwrap ();
+///
+/// let grass = vfs.read ("textures/grass.png").unwrap ();
+/// ```
+#[derive (Default)]
+pub struct Vfs {
+
+    // Private
+    mounts        : Vec <Mount>,
+    next_sequence : u64
+}
+
+/*================================================================================================*/
+
+/// The VFS metadata struct
+///
+/// It describes a file or directory in a virtual file system.
+#[derive (Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VfsMetadata {
+
+    // Public
+    /// Whether it is a directory.
+    pub is_dir   : bool,
+    /// The size of the file in bytes, or zero for directories.
+    pub size     : u64,
+    /// When it was last modified, if known.
+    pub modified : Option <SystemTime>
+}
+
+/*================================================================================================*/
+
+/// The VFS entry struct
+///
+/// It is an entry in a directory of a virtual file system.
+#[derive (Clone, Debug, PartialEq, Eq)]
+pub struct VfsEntry {
+
+    // Public
+    /// The name of the entry.
+    pub name     : String,
+    /// The full virtual path of the entry.
+    pub path     : String,
+    /// The metadata of the entry.
+    pub metadata : VfsMetadata
+}
+
+/*================================================================================================*/
+
+// A source mounted at a virtual path
+struct Mount {
+
+    point    : String,
+    priority : i32,
+    sequence : u64,
+    source   : Box <dyn VfsSource>
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl Vfs {
+
+    /// Creates a new empty virtual file system.
+    pub fn new () -> Vfs {
+
+        Vfs::default ()
+    }
+
+/*================================================================================================*/
+
+    /// Normalizes a virtual path.
+    ///
+    /// Both `/` and `\` are treated as separators, empty and `.` components are removed, and
+    /// `..` components remove the component before them. Paths which leave the root, or which
+    /// contain a drive or scheme (`:`), are invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// # use trivert_utility::Vfs;
+    /// let path = Vfs::normalize ("/textures\\ui/./../grass.png").unwrap ();
+    ///
+    /// assert_eq! (path, "textures/grass.png");
+    /// assert! (Vfs::normalize ("../secrets.txt").is_err ());
+    /// ```
+    pub fn normalize (path : &str) -> Result <String, VfsError> {
+
+        let mut components : Vec <&str> = Vec::new ();
+
+        for component in path.split (['/', '\\']) {
+
+            match component {
+
+                "" | "." => {},
+
+                ".." => {
+                    if components.pop ().is_none () {
+                        return Err (VfsError::InvalidPath (path.to_string ()));
+                    }
+                },
+
+                _ if component.contains (':') => {
+                    return Err (VfsError::InvalidPath (path.to_string ()));
+                },
+
+                _ => components.push (component)
+            }
+        }
+
+        Ok (components.join ("/"))
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Mounts a source at a virtual path, with the given priority.
+    pub fn mount<S> (&mut self, point : &str, source : S, priority : i32) -> Result <(), VfsError>
+        where S : VfsSource + 'static {
+
+        let point = Vfs::normalize (point)?;
+
+        self.mounts.push (Mount {point,
+                                 priority,
+                                 sequence : self.next_sequence,
+                                 source   : Box::new (source)});
+
+        self.next_sequence += 1;
+
+        // Highest priority first, with later mounts first within a priority
+        self.mounts.sort_by (|a, b| {
+            (b.priority, b.sequence).cmp (&(a.priority, a.sequence))
+        });
+
+        Ok (())
+    }
+
+/*================================================================================================*/
+
+    /// Mounts a directory on disk at a virtual path, with the given priority.
+    pub fn mount_directory<P : AsRef <Path>> (&mut self,
+                                              point     : &str,
+                                              directory : P,
+                                              priority  : i32) -> Result <(), VfsError> {
+
+        let directory = directory.as_ref ();
+        let source    = DirectorySource::new (directory).map_err (|error| {
+            real_error (directory, error)
+        })?;
+
+        self.mount (point, source, priority)
+    }
+
+/*================================================================================================*/
+
+    /// Mounts a zip (or zip based `.pak`) archive at a virtual path, with the given priority.
+    pub fn mount_zip<P : AsRef <Path>> (&mut self,
+                                        point    : &str,
+                                        archive  : P,
+                                        priority : i32) -> Result <(), VfsError> {
+
+        let archive = archive.as_ref ();
+        let source  = ZipSource::new (archive).map_err (|error| real_error (archive, error))?;
+
+        self.mount (point, source, priority)
+    }
+
+/*================================================================================================*/
+
+    /// Mounts a TriVert pack file at a virtual path, with the given priority.
+    pub fn mount_pack<P : AsRef <Path>> (&mut self,
+                                         point    : &str,
+                                         pack     : P,
+                                         priority : i32) -> Result <(), VfsError> {
+
+        let pack   = pack.as_ref ();
+        let source = PackSource::new (pack).map_err (|error| real_error (pack, error))?;
+
+        self.mount (point, source, priority)
+    }
+
+/*================================================================================================*/
+
+    /// Unmounts every source mounted at a virtual path, returning how many were unmounted.
+    pub fn unmount (&mut self, point : &str) -> usize {
+
+        let point = match Vfs::normalize (point) {
+            Ok (point) => point,
+            Err (_)    => return 0
+        };
+
+        let count = self.mounts.len ();
+
+        self.mounts.retain (|mount| mount.point != point);
+        count - self.mounts.len ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the virtual paths sources are mounted at, from highest to lowest priority.
+    pub fn mount_points (&self) -> Vec <&str> {
+
+        self.mounts.iter ().map (|mount| mount.point.as_str ()).collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the mount points of sources backed by the file system, with the real path of
+    /// their root, from highest to lowest priority.
+    pub fn real_mounts (&self) -> Vec <(&str, PathBuf)> {
+
+        self.mounts.iter ()
+                   .filter_map (|mount| {
+                       mount.source.real_path ("").map (|root| (mount.point.as_str (), root))
+                   })
+                   .collect ()
+    }
+
+/*================================================================================================*/
+
+    /// Reads the whole of a file, from the highest priority source which has it.
+    pub fn read (&self, path : &str) -> Result <Vec <u8>, VfsError> {
+
+        let path = Vfs::normalize (path)?;
+
+        for (mount, relative) in self.sources (&path) {
+
+            match mount.source.metadata (relative) {
+
+                Some (metadata) if metadata.is_dir => {
+                    return Err (VfsError::IsDirectory (path.clone ()));
+                },
+
+                Some (_)                           => {},
+                None                               => continue
+            }
+
+            return mount.source.read (relative).map_err (|error| {
+                VfsError::Io {path : path.clone (),
+                              error}
+            });
+        }
+
+        Err (VfsError::NotFound (path))
+    }
+
+/*================================================================================================*/
+
+    /// Reads the whole of a file as a UTF-8 string.
+    pub fn read_to_string (&self, path : &str) -> Result <String, VfsError> {
+
+        let data = self.read (path)?;
+
+        String::from_utf8 (data).map_err (|error| {
+            VfsError::Io {path  : path.to_string (),
+                          error : io::Error::new (io::ErrorKind::InvalidData, error)}
+        })
+    }
+
+/*================================================================================================*/
+
+    /// Checks if a file or directory exists.
+    pub fn exists (&self, path : &str) -> bool {
+
+        self.metadata (path).is_ok ()
+    }
+
+/*================================================================================================*/
+
+    /// Returns the metadata of a file or directory, from the highest priority source which
+    /// has it.
+    ///
+    /// The root, and the directories leading to each mount point, always exist.
+    pub fn metadata (&self, path : &str) -> Result <VfsMetadata, VfsError> {
+
+        let path = Vfs::normalize (path)?;
+
+        let metadata = self.sources (&path)
+                           .filter_map (|(mount, relative)| mount.source.metadata (relative))
+                           .next ();
+
+        match metadata {
+
+            Some (metadata)                      => Ok (metadata),
+            None if self.is_mount_parent (&path) => Ok (VfsMetadata::directory ()),
+            None                                 => Err (VfsError::NotFound (path))
+        }
+    }
+
+/*================================================================================================*/
+
+    /// Lists the entries in a directory, sorted by name.
+    ///
+    /// The entries of every source with the directory are merged, with the highest priority
+    /// source used for entries which are in several.
+    pub fn list (&self, path : &str) -> Result <Vec <VfsEntry>, VfsError> {
+
+        let path = Vfs::normalize (path)?;
+
+        if !self.metadata (&path)?.is_dir {
+            return Err (VfsError::NotFound (path));
+        }
+
+        let mut entries = BTreeMap::new ();
+
+        for (mount, relative) in self.sources (&path) {
+
+            if !mount.source.metadata (relative).is_some_and (|metadata| metadata.is_dir) {
+                continue;
+            }
+
+            let listed = mount.source.list (relative).map_err (|error| {
+                VfsError::Io {path : path.clone (),
+                              error}
+            })?;
+
+            for (name, metadata) in listed {
+                entries.entry (name).or_insert (metadata);
+            }
+        }
+
+        // Mount points inside the directory show up as directories
+        for mount in &self.mounts {
+
+            if let Some (name) = child_name (&path, &mount.point) {
+                entries.entry (name.to_string ()).or_insert (VfsMetadata::directory ());
+            }
+        }
+
+        Ok (entries.into_iter ()
+                   .map (|(name, metadata)| VfsEntry {path : join (&path, &name),
+                                                      name,
+                                                      metadata})
+                   .collect ())
+    }
+
+/*================================================================================================*/
+
+    /// Returns the real path of a file or directory, from the highest priority source which
+    /// has it and is backed by the file system.
+    pub fn real_path (&self, path : &str) -> Option <PathBuf> {
+
+        let path = Vfs::normalize (path).ok ()?;
+
+        let real_path = self.sources (&path)
+                            .filter (|&(mount, relative)| {
+                                mount.source.metadata (relative).is_some ()
+                            })
+                            .filter_map (|(mount, relative)| mount.source.real_path (relative))
+                            .next ();
+        real_path
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Returns the mounts which could contain a path, with the path relative to each mount
+    fn sources<'a> (&'a self, path : &'a str) -> impl Iterator <Item = (&'a Mount, &'a str)> {
+
+        self.mounts.iter ().filter_map (move |mount| {
+            relative_path (&mount.point, path).map (|relative| (mount, relative))
+        })
+    }
+
+/*================================================================================================*/
+
+    // Checks if a path is the root, or leads to a mount point
+    fn is_mount_parent (&self, path : &str) -> bool {
+
+        path.is_empty () || self.mounts.iter ().any (|mount| relative_path (path, &mount.point)
+                                                                 .is_some ())
+    }
+
+}
+
+/*================================================================================================*/
+
+impl VfsMetadata {
+
+    /// Returns the metadata of a directory with no known modification time.
+    pub fn directory () -> VfsMetadata {
+
+        VfsMetadata {is_dir   : true,
+                     size     : 0,
+                     modified : None}
+    }
+}
+
+/*================================================================================================*/
+/*------CONVERSIONS-------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl<'a> From <&'a fs::Metadata> for VfsMetadata {
+
+    // Copies the metadata of a file on disk
+    fn from (metadata : &'a fs::Metadata) -> VfsMetadata {
+
+        VfsMetadata {is_dir   : metadata.is_dir (),
+                     size     : if metadata.is_dir () {0} else {metadata.len ()},
+                     modified : metadata.modified ().ok ()}
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns a path relative to a directory, if it is the directory or inside it
+fn relative_path<'a> (directory : &str, path : &'a str) -> Option <&'a str> {
+
+    if directory.is_empty () {
+        return Some (path);
+    }
+
+    match path.strip_prefix (directory)? {
+        ""   => Some (""),
+        rest => rest.strip_prefix ('/')
+    }
+}
+
+/*================================================================================================*/
+
+// Returns the name of the child of a directory which leads to a mount point
+fn child_name<'a> (directory : &str, point : &'a str) -> Option <&'a str> {
+
+    let rest = relative_path (directory, point)?;
+
+    if rest.is_empty () {
+        return None;
+    }
+
+    rest.split ('/').next ()
+}
+
+/*================================================================================================*/
+
+// Joins a name onto a directory's virtual path
+fn join (directory : &str, name : &str) -> String {
+
+    if directory.is_empty () {
+        name.to_string ()
+    } else {
+        format! ("{}/{}", directory, name)
+    }
+}
+
+/*================================================================================================*/
+
+// Wraps an error from mounting a real path
+fn real_error (path : &Path, error : io::Error) -> VfsError {
+
+    VfsError::Io {path : path.display ().to_string (),
+                  error}
+}
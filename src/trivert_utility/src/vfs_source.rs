@@ -0,0 +1,158 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+use VfsMetadata;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/*================================================================================================*/
+/*------TRAITS------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The VFS source trait
+///
+/// A source provides the files mounted in a virtual file system, such as a directory or an
+/// archive.
+///
+/// Sources are given normalized paths relative to their root, which use `/` as the separator
+/// and never contain `.` or `..` components. The root itself is the empty path.
+pub trait VfsSource : Send + Sync {
+
+    /// Reads the whole of a file.
+    ///
+    /// An error of the kind `io::ErrorKind::NotFound` is returned if the file doesn't exist.
+    fn read (&self, path : &str) -> io::Result <Vec <u8>>;
+
+    /// Returns the metadata of a file or directory, or `None` if it doesn't exist.
+    fn metadata (&self, path : &str) -> Option <VfsMetadata>;
+
+    /// Returns the names and metadata of the entries in a directory.
+    fn list (&self, path : &str) -> io::Result <Vec <(String, VfsMetadata)>>;
+
+    /// Returns the real path of a file or directory, if the source is backed by the file
+    /// system.
+    fn real_path (&self, _path : &str) -> Option <PathBuf> {
+        None
+    }
+}
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The directory source struct
+///
+/// It provides the files in a directory on disk, which is useful for loose assets during
+/// development.
+#[derive (Clone, Debug)]
+pub struct DirectorySource {
+
+    // Private
+    root : PathBuf
+}
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl DirectorySource {
+
+    /// Creates a new directory source, failing if the directory doesn't exist.
+    pub fn new<P : AsRef <Path>> (root : P) -> io::Result <DirectorySource> {
+
+        let root = root.as_ref ();
+
+        if !fs::metadata (root)?.is_dir () {
+            return Err (io::Error::other ("not a directory"));
+        }
+
+        Ok (DirectorySource {root : root.to_path_buf ()})
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Returns the directory the source provides.
+    pub fn root (&self) -> &Path {
+
+        &self.root
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Joins a source path onto the root directory
+    fn join (&self, path : &str) -> PathBuf {
+
+        path.split ('/')
+            .filter (|name| !name.is_empty ())
+            .fold (self.root.clone (), |real_path, name| real_path.join (name))
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl VfsSource for DirectorySource {
+
+    // Reads the file from disk
+    fn read (&self, path : &str) -> io::Result <Vec <u8>> {
+
+        fs::read (self.join (path))
+    }
+
+/*================================================================================================*/
+
+    // Reads the metadata from disk
+    fn metadata (&self, path : &str) -> Option <VfsMetadata> {
+
+        fs::metadata (self.join (path)).ok ().map (|metadata| VfsMetadata::from (&metadata))
+    }
+
+/*================================================================================================*/
+
+    // Reads the directory from disk, skipping entries which aren't valid unicode or can't be
+    // read (such as broken links)
+    fn list (&self, path : &str) -> io::Result <Vec <(String, VfsMetadata)>> {
+
+        let mut entries = Vec::new ();
+
+        for entry in fs::read_dir (self.join (path))? {
+
+            let entry = entry?;
+
+            if let (Ok (name), Ok (metadata)) = (entry.file_name ().into_string (),
+                                                 fs::metadata (entry.path ())) {
+                entries.push ((name, VfsMetadata::from (&metadata)));
+            }
+        }
+
+        Ok (entries)
+    }
+
+/*================================================================================================*/
+
+    // Returns the path within the directory
+    fn real_path (&self, path : &str) -> Option <PathBuf> {
+
+        Some (self.join (path))
+    }
+}
@@ -0,0 +1,197 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate zip;
+
+use {Vfs, VfsMetadata, VfsSource};
+use archive_index::ArchiveIndex;
+
+use self::zip::{DateTime, ZipArchive};
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::io::prelude::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/*================================================================================================*/
+/*------STRUCTS-----------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+/// The zip source struct
+///
+/// It provides the files in a zip archive, including `.pak` files which use the zip format.
+/// The archive is indexed when opened, and kept open to read files from.
+///
+/// Directories which only exist as part of a file's path are still listed. Entries with
+/// paths which leave the root of the archive are ignored, while archives with duplicate paths,
+/// or with files inside other files, are rejected.
+pub struct ZipSource {
+
+    // Private
+    path    : PathBuf,
+    archive : Mutex <ZipArchive <File>>,
+    index   : ArchiveIndex <usize>
+}
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// The most deflate can expand data by, which bounds the size a zip entry can honestly declare
+const DEFLATE_MAX_RATIO : u64 = 1032;
+
+/*================================================================================================*/
+/*------PUBLIC STATIC FUNCTIONS-------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl ZipSource {
+
+    /// Opens a zip archive, and indexes its entries.
+    pub fn new<P : AsRef <Path>> (path : P) -> io::Result <ZipSource> {
+
+        let path        = path.as_ref ();
+        let mut archive = ZipArchive::new (File::open (path)?)?;
+        let mut index   = ArchiveIndex::new ();
+
+        for position in 0..archive.len () {
+
+            let file = archive.by_index_raw (position)?;
+
+            let name = match Vfs::normalize (file.name ()) {
+                Ok (ref name) if name.is_empty () => continue,
+                Ok (name)                         => name,
+                Err (_)                           => continue
+            };
+
+            let metadata = VfsMetadata {is_dir   : file.is_dir (),
+                                        size     : if file.is_dir () {0} else {file.size ()},
+                                        modified : to_system_time (file.last_modified ())};
+
+            let item = if file.is_dir () {None} else {Some (position)};
+
+            if let Err (error) = index.insert (name, item, metadata) {
+                return Err (io::Error::new (io::ErrorKind::InvalidData, error.to_string ()));
+            }
+        }
+
+        Ok (ZipSource {path    : path.to_path_buf (),
+                       archive : Mutex::new (archive),
+                       index})
+    }
+
+/*================================================================================================*/
+/*------PUBLIC FUNCTIONS--------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    /// Returns the path of the archive.
+    pub fn path (&self) -> &Path {
+
+        &self.path
+    }
+
+/*================================================================================================*/
+/*------PRIVATE FUNCTIONS-------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+    // Locks the archive, ignoring poisoning as reading never leaves it in an invalid state
+    fn lock (&self) -> MutexGuard <'_, ZipArchive <File>> {
+
+        self.archive.lock ().unwrap_or_else (|error| error.into_inner ())
+    }
+}
+
+/*================================================================================================*/
+/*------TRAIT IMPLEMENTATIONS---------------------------------------------------------------------*/
+/*================================================================================================*/
+
+impl VfsSource for ZipSource {
+
+    // Decompresses the file from the archive
+    fn read (&self, path : &str) -> io::Result <Vec <u8>> {
+
+        let position = match self.index.file (path) {
+            Some (&position) => position,
+            None             => return Err (io::Error::new (io::ErrorKind::NotFound,
+                                                            "file not in archive"))
+        };
+
+        let mut archive = self.lock ();
+        let mut file    = archive.by_index (position)?;
+
+        let size = match usize::try_from (file.size ()) {
+            Ok (size) => size,
+            Err (_)   => return Err (io::Error::new (io::ErrorKind::InvalidData,
+                                                     "zip entry is too large"))
+        };
+
+        // The declared size is only trusted as far as the stored data could expand to, as a
+        // crafted size would otherwise be allocated up front
+        let limit    = file.compressed_size ().saturating_mul (DEFLATE_MAX_RATIO);
+        let capacity = usize::try_from (limit).map_or (size, |limit| size.min (limit));
+        let mut data = Vec::with_capacity (capacity);
+
+        file.read_to_end (&mut data)?;
+        Ok (data)
+    }
+
+/*================================================================================================*/
+
+    // Returns the indexed metadata
+    fn metadata (&self, path : &str) -> Option <VfsMetadata> {
+
+        self.index.metadata (path)
+    }
+
+/*================================================================================================*/
+
+    // Returns the indexed entries directly inside the directory
+    fn list (&self, path : &str) -> io::Result <Vec <(String, VfsMetadata)>> {
+
+        self.index.list (path)
+    }
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Converts the MS-DOS time of a zip entry, treating it as UTC as zip files don't store a zone
+fn to_system_time (time : DateTime) -> Option <SystemTime> {
+
+    // Days since the epoch of the civil date, from Howard Hinnant's date algorithms
+    let (year, month, day) = (time.year () as i64, time.month () as i64, time.day () as i64);
+
+    if !(1..=12).contains (&month) || day < 1 {
+        return None;
+    }
+
+    let year         = if month <= 2 {year - 1} else {year};
+    let era          = year.div_euclid (400);
+    let year_of_era  = year - era * 400;
+    let day_of_year  = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era   = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days         = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 +
+                  time.hour () as i64 * 3600 +
+                  time.minute () as i64 * 60 +
+                  time.second () as i64;
+
+    u64::try_from (seconds).ok ().map (|seconds| UNIX_EPOCH + Duration::from_secs (seconds))
+}
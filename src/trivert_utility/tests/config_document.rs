@@ -0,0 +1,250 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate trivert_utility;
+
+use trivert_utility::{ConfigDocument, ConfigError, Value};
+
+use std::env;
+use std::fs;
+use std::process;
+
+/*================================================================================================*/
+/*------CONSTANTS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// A document using most of the syntax, along with comments and odd spacing
+const SETTINGS : &str = "\
+# Settings for the game
+name = TriVert Demo   # Shown in the title bar
+; INI style comment
+
+[video]
+vsync      = true
+resolution = \"1920x1080\"
+scale=1.5
+  fov = 90 ; Degrees
+
+[audio.music]
+volume = 0.8
+track  = 'main theme.ogg'
+\"fade in\" = -2
+
+[\"key bindings\"]
+jump = Space # Or W
+quote = it's # An apostrophe in plain text
+";
+
+/*================================================================================================*/
+/*------TESTS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+#[test]
+fn documents_are_written_as_they_were_read () {
+
+    let document = ConfigDocument::parse (SETTINGS).unwrap ();
+
+    assert_eq! (document.to_string (), SETTINGS);
+    assert_eq! (document.len (), 10);
+}
+
+/*================================================================================================*/
+
+#[test]
+fn values_are_parsed () {
+
+    let document = ConfigDocument::parse (SETTINGS).unwrap ();
+    let string   = |text : &str| Some (Value::Str (text.to_string ()));
+
+    assert_eq! (document.get ("name").cloned (), string ("TriVert Demo"));
+    assert_eq! (document.get ("video.vsync").cloned (), Some (Value::Bool (true)));
+    assert_eq! (document.get ("video.resolution").cloned (), string ("1920x1080"));
+    assert_eq! (document.get ("video.scale").cloned (), Some (Value::Float (1.5)));
+    assert_eq! (document.get ("video.fov").cloned (), Some (Value::Int (90)));
+    assert_eq! (document.get ("audio.music.volume").cloned (), Some (Value::Float (0.8)));
+    assert_eq! (document.get ("audio.music.track").cloned (), string ("main theme.ogg"));
+    assert_eq! (document.get ("audio.music.fade in").cloned (), Some (Value::Int (-2)));
+    assert_eq! (document.get ("key bindings.jump").cloned (), string ("Space"));
+    assert_eq! (document.get ("key bindings.quote").cloned (), string ("it's"));
+    assert_eq! (document.get ("video"), None);
+
+    let keys : Vec <&str> = document.iter ().map (|(key, _)| key).collect ();
+
+    assert_eq! (keys, vec! ["name",
+                            "video.vsync",
+                            "video.resolution",
+                            "video.scale",
+                            "video.fov",
+                            "audio.music.volume",
+                            "audio.music.track",
+                            "audio.music.fade in",
+                            "key bindings.jump",
+                            "key bindings.quote"]);
+}
+
+/*================================================================================================*/
+
+#[test]
+fn setting_values_keeps_comments_and_layout () {
+
+    let mut document = ConfigDocument::parse (SETTINGS).unwrap ();
+
+    document.set ("name", "Another # Demo");
+    document.set ("video.fov", 75);
+    document.set ("video.vsync", false);
+    document.set ("audio.music.track", "quote\"d");
+
+    let expected = SETTINGS.replace ("name = TriVert Demo   #",
+                                     "name = \"Another # Demo\"   #")
+                           .replace ("  fov = 90 ;", "  fov = 75 ;")
+                           .replace ("vsync      = true", "vsync      = false")
+                           .replace ("track  = 'main theme.ogg'", "track  = \"quote\\\"d\"");
+
+    assert_eq! (document.to_string (), expected);
+
+    let reparsed = ConfigDocument::parse (&document.to_string ()).unwrap ();
+
+    assert_eq! (reparsed, document);
+    assert_eq! (reparsed.get ("name").cloned (), Some (Value::Str ("Another # Demo".to_string ())));
+    assert_eq! (reparsed.get ("audio.music.track").cloned (),
+                Some (Value::Str ("quote\"d".to_string ())));
+}
+
+/*================================================================================================*/
+
+#[test]
+fn new_settings_go_at_the_end_of_their_section () {
+
+    let mut document = ConfigDocument::parse ("top = 1\n\n[a]\nx = 1 # First\n\n[b]\ny = 2\n")
+                                     .unwrap ();
+
+    document.set ("a.z", 3);
+    document.set ("second", 2);
+    document.set ("c.d.e", "new");
+    document.set ("with space.key", true);
+    document.set ("a.needs quotes", 4);
+
+    assert_eq! (document.to_string (),
+                "top = 1\nsecond = 2\n\n[a]\nx = 1 # First\nz = 3\n\"needs quotes\" = 4\n\n\
+                 [b]\ny = 2\n\n[c.d]\ne = \"new\"\n\n[\"with space\"]\nkey = true\n");
+
+    let reparsed = ConfigDocument::parse (&document.to_string ()).unwrap ();
+
+    assert_eq! (reparsed.get ("c.d.e").cloned (), Some (Value::Str ("new".to_string ())));
+    assert_eq! (reparsed.get ("with space.key").cloned (), Some (Value::Bool (true)));
+    assert_eq! (reparsed.get ("a.needs quotes").cloned (), Some (Value::Int (4)));
+}
+
+/*================================================================================================*/
+
+#[test]
+fn special_values_survive_a_round_trip () {
+
+    let mut document = ConfigDocument::new ();
+    let values       = vec! [("true_text", Value::Str ("true".to_string ())),
+                             ("number_text", Value::Str ("12".to_string ())),
+                             ("padded", Value::Str ("  padded  ".to_string ())),
+                             ("empty", Value::Str (String::new ())),
+                             ("escapes", Value::Str ("tab\tline\nquote\"".to_string ())),
+                             ("comment", Value::Str ("a ; b".to_string ())),
+                             ("large", Value::Uint (u64::MAX)),
+                             ("negative", Value::Int (i64::MIN)),
+                             ("whole", Value::Float (3.0)),
+                             ("infinite", Value::Float (f64::INFINITY)),
+                             ("negative_infinite", Value::Float (f64::NEG_INFINITY))];
+
+    for &(key, ref value) in &values {
+        document.set (key, value.clone ());
+    }
+
+    document.set ("not_a_number", f64::NAN);
+
+    let reparsed = ConfigDocument::parse (&document.to_string ()).unwrap ();
+
+    for &(key, ref value) in &values {
+        assert_eq! (reparsed.get (key), Some (value), "{}", key);
+    }
+
+    assert! (reparsed.get ("not_a_number").and_then (Value::as_float).is_some_and (f64::is_nan));
+}
+
+/*================================================================================================*/
+
+#[test]
+fn removed_settings_take_their_line_with_them () {
+
+    let mut document = ConfigDocument::parse ("[a]\nx = 1 # Gone\ny = 2\n").unwrap ();
+
+    assert_eq! (document.remove ("a.x"), Some (Value::Int (1)));
+    assert_eq! (document.remove ("a.x"), None);
+    assert_eq! (document.to_string (), "[a]\ny = 2\n");
+}
+
+/*================================================================================================*/
+
+#[test]
+fn errors_report_their_line () {
+
+    let cases = [("[video\nvsync = true\n", 1),
+                 ("[video]\nvsync\n", 2),
+                 ("a = 1\nb = \n", 2),
+                 ("a = 1\n\n# Comment\na = 2\n", 4),
+                 ("[a]\nx = 1\n[b]\ny = 1\n[a]\nx = 2\n", 6),
+                 ("name = \"unterminated\n", 1),
+                 ("name = \"bad \\q escape\"\n", 1),
+                 ("[[array]]\n", 1),
+                 ("[a] extra\n", 1),
+                 ("a b = 1\n", 1),
+                 ("\"a = 1\n", 1),
+                 ("ok = 1\n= 2\n", 2)];
+
+    for &(text, line) in &cases {
+
+        match ConfigDocument::parse (text) {
+            Ok (_)      => panic! ("{:?} was parsed", text),
+            Err (error) => assert_eq! (error.line (), line, "{:?}: {}", text, error)
+        }
+    }
+}
+
+/*================================================================================================*/
+
+#[test]
+fn documents_are_saved_and_loaded () {
+
+    let path = env::temp_dir ().join (format! ("trivert_config_{}.toml", process::id ()));
+
+    let mut document = ConfigDocument::parse (SETTINGS).unwrap ();
+
+    document.set ("video.fov", 100);
+    document.save (&path).unwrap ();
+
+    assert_eq! (ConfigDocument::load (&path).unwrap (), document);
+
+    fs::write (&path, "ok = 1\nbroken\n").unwrap ();
+
+    match ConfigDocument::load (&path) {
+        Err (ConfigError::Parse {error, ..}) => assert_eq! (error.line (), 2),
+        other                                => panic! ("unexpected result: {:?}", other)
+    }
+
+    fs::remove_file (&path).unwrap ();
+
+    match ConfigDocument::load (&path) {
+        Err (ConfigError::Io {..}) => {},
+        other                      => panic! ("unexpected result: {:?}", other)
+    }
+}
@@ -0,0 +1,238 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate trivert_utility;
+
+use trivert_utility::{FileChange, FileChangeKind, FileWatcher, WatchBackend};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+/*================================================================================================*/
+/*------TESTS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+#[cfg (unix)]
+#[test]
+fn symlink_loops_are_not_followed () {
+
+    let directory = test_directory ("symlink_loops");
+
+    fs::create_dir (directory.join ("a")).unwrap ();
+    fs::write (directory.join ("a/file.txt"), b"file").unwrap ();
+    std::os::unix::fs::symlink ("..", directory.join ("a/loop")).unwrap ();
+    std::os::unix::fs::symlink ("file.txt", directory.join ("a/link.txt")).unwrap ();
+
+    for mut watcher in [FileWatcher::new (), FileWatcher::polling (Duration::from_secs (0))] {
+
+        watcher.watch ("", &directory).unwrap ();
+        watcher.set_debounce (Duration::from_secs (0));
+
+        fs::write (directory.join ("a/new.txt"), b"new").unwrap ();
+
+        assert_eq! (watcher.poll (), vec! [change ("a/new.txt", FileChangeKind::Created)]);
+
+        fs::remove_file (directory.join ("a/new.txt")).unwrap ();
+        watcher.poll ();
+    }
+
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+#[cfg (target_os = "linux")]
+#[test]
+fn created_then_modified_is_created () {
+
+    let directory   = test_directory ("created_then_modified");
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+    fs::write (directory.join ("file.txt"), b"two").unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Created)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn created_then_removed_is_not_reported () {
+
+    let directory   = test_directory ("created_then_removed");
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+    fs::remove_file (directory.join ("file.txt")).unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! []);
+    assert! (!watcher.has_pending ());
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn removed_then_created_is_modified () {
+
+    let directory = test_directory ("removed_then_created");
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::remove_file (directory.join ("file.txt")).unwrap ();
+    fs::write (directory.join ("file.txt"), b"two").unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Modified)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn modified_then_removed_is_removed () {
+
+    let directory = test_directory ("modified_then_removed");
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::write (directory.join ("file.txt"), b"two").unwrap ();
+    fs::remove_file (directory.join ("file.txt")).unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Removed)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn removed_created_and_removed_again_is_removed () {
+
+    let directory = test_directory ("removed_created_removed");
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::remove_file (directory.join ("file.txt")).unwrap ();
+    fs::write (directory.join ("file.txt"), b"two").unwrap ();
+    fs::remove_file (directory.join ("file.txt")).unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Removed)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn replaced_by_rename_is_modified () {
+
+    let directory = test_directory ("replaced_by_rename");
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+
+    let mut watcher = inotify_watcher (&directory);
+
+    // Editors often save by writing a temporary file, then renaming it over the original
+    fs::write (directory.join ("file.txt.tmp"), b"two").unwrap ();
+    fs::rename (directory.join ("file.txt.tmp"), directory.join ("file.txt")).unwrap ();
+
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Modified)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[cfg (target_os = "linux")]
+#[test]
+fn changes_wait_for_the_debounce () {
+
+    let directory   = test_directory ("debounce");
+    let mut watcher = inotify_watcher (&directory);
+
+    fs::write (directory.join ("file.txt"), b"one").unwrap ();
+
+    assert_eq! (watcher.poll (), vec! []);
+    assert! (watcher.has_pending ());
+    assert_eq! (settle (&mut watcher), vec! [change ("file.txt", FileChangeKind::Created)]);
+    fs::remove_dir_all (&directory).unwrap ();
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Watches a directory through inotify, with a debounce long enough to hold back every change
+#[cfg (target_os = "linux")]
+fn inotify_watcher (directory : &Path) -> FileWatcher {
+
+    let mut watcher = FileWatcher::new ();
+
+    watcher.watch ("", directory).unwrap ();
+    watcher.set_debounce (Duration::from_secs (3600));
+
+    assert_eq! (watcher.backend (), WatchBackend::Inotify);
+    watcher
+}
+
+/*================================================================================================*/
+
+// Returns the changes a watcher has picked up, merged but without waiting for them to settle
+#[cfg (target_os = "linux")]
+fn settle (watcher : &mut FileWatcher) -> Vec <FileChange> {
+
+    let debounce = watcher.debounce ();
+
+    watcher.poll ();
+    watcher.set_debounce (Duration::from_secs (0));
+
+    let changes = watcher.poll ();
+
+    watcher.set_debounce (debounce);
+    changes
+}
+
+/*================================================================================================*/
+
+// Creates an empty directory for a test, removing any left by an earlier run
+fn test_directory (name : &str) -> PathBuf {
+
+    let directory = env::temp_dir ().join (format! ("trivert_watcher_{}_{}", name, process::id ()));
+
+    fs::remove_dir_all (&directory).ok ();
+    fs::create_dir_all (&directory).unwrap ();
+
+    directory
+}
+
+/*================================================================================================*/
+
+// Creates the change expected for a path
+fn change (path : &str, kind : FileChangeKind) -> FileChange {
+
+    FileChange {path : path.to_string (),
+                kind}
+}
@@ -0,0 +1,312 @@
+/*================================================================================================*/
+// Copyright 2016 Kyle Finlay
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+/*================================================================================================*/
+
+extern crate trivert_utility;
+extern crate xxhash_rust;
+
+use trivert_utility::{PackCompression, PackSource, PackWriter, VfsSource};
+use xxhash_rust::xxh3::xxh3_64;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, UNIX_EPOCH};
+
+/*================================================================================================*/
+/*------TYPES-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Damages the data of a whole pack
+type Damage = fn (&mut Vec <u8>);
+
+// Changes the table of contents of a pack
+type Craft = fn (&mut [u8]);
+
+/*================================================================================================*/
+/*------TESTS-------------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+#[test]
+fn files_are_read_back () {
+
+    let path     = test_path ("read_back");
+    let text     = "The quick brown fox jumps over the lazy dog. ".repeat (100);
+    let modified = UNIX_EPOCH + Duration::from_secs (1_600_000_000);
+
+    let mut writer = PackWriter::create (&path).unwrap ();
+
+    writer.add ("stored.txt", text.as_bytes (), PackCompression::None).unwrap ();
+    writer.add ("fast/lz4.txt", text.as_bytes (), PackCompression::Lz4).unwrap ();
+    writer.add_with_time ("small/zstd.txt",
+                          text.as_bytes (),
+                          PackCompression::Zstd,
+                          Some (modified)).unwrap ();
+    writer.add ("tiny.bin", b"xy", PackCompression::Zstd).unwrap ();
+
+    let written = writer.finish ().unwrap ();
+    let pack    = PackSource::new (&path).unwrap ();
+
+    assert_eq! (pack.entries ().len (), 4);
+    assert_eq! (pack.verify ().unwrap (), 4);
+
+    for entry in &written {
+
+        let found = pack.entries ().into_iter ().find (|found| found.path == entry.path);
+        assert_eq! (found, Some (entry));
+    }
+
+    assert_eq! (pack.read ("stored.txt").unwrap (), text.as_bytes ());
+    assert_eq! (pack.read ("fast/lz4.txt").unwrap (), text.as_bytes ());
+    assert_eq! (pack.read ("small/zstd.txt").unwrap (), text.as_bytes ());
+    assert_eq! (pack.read ("tiny.bin").unwrap (), b"xy");
+
+    let compression = |path : &str| {
+        written.iter ().find (|entry| entry.path == path).map (|entry| entry.compression)
+    };
+
+    assert_eq! (compression ("fast/lz4.txt"), Some (PackCompression::Lz4));
+    assert_eq! (compression ("small/zstd.txt"), Some (PackCompression::Zstd));
+    assert_eq! (compression ("tiny.bin"), Some (PackCompression::None)); // Didn't get smaller
+
+    let metadata = pack.metadata ("small/zstd.txt").unwrap ();
+
+    assert! (!metadata.is_dir);
+    assert_eq! (metadata.size, text.len () as u64);
+    assert_eq! (metadata.modified, Some (modified));
+    assert! (pack.metadata ("small").unwrap ().is_dir);
+    assert! (pack.metadata ("missing.txt").is_none ());
+
+    let names : Vec <String> = pack.list ("").unwrap ()
+                                   .into_iter ()
+                                   .map (|(name, _)| name)
+                                   .collect ();
+
+    assert_eq! (names, vec! ["fast", "small", "stored.txt", "tiny.bin"]);
+
+    assert_eq! (pack.read ("missing.txt").unwrap_err ().kind (), io::ErrorKind::NotFound);
+    fs::remove_file (&path).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[test]
+fn colliding_paths_are_rejected_when_writing () {
+
+    let path       = test_path ("colliding_paths");
+    let mut writer = PackWriter::create (&path).unwrap ();
+
+    writer.add ("a", b"file", PackCompression::None).unwrap ();
+    writer.add ("c/d", b"file", PackCompression::None).unwrap ();
+
+    assert_eq! (writer.add ("a", b"again", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::AlreadyExists);
+    assert_eq! (writer.add ("./a", b"again", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::AlreadyExists);
+    assert_eq! (writer.add ("a/b", b"inside", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::AlreadyExists);
+    assert_eq! (writer.add ("c", b"directory", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::AlreadyExists);
+    assert_eq! (writer.add ("", b"empty", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::InvalidInput);
+    assert_eq! (writer.add ("../up", b"outside", PackCompression::None).unwrap_err ().kind (),
+                io::ErrorKind::InvalidInput);
+
+    writer.add ("c/e", b"file", PackCompression::None).unwrap ();
+
+    assert_eq! (writer.finish ().unwrap ().len (), 3);
+    assert_eq! (PackSource::new (&path).unwrap ().verify ().unwrap (), 3);
+    fs::remove_file (&path).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[test]
+fn corrupt_file_data_fails_its_integrity_check () {
+
+    let path = test_path ("corrupt_data");
+    let text = "Some text which is long enough to compress. ".repeat (50);
+
+    let mut writer = PackWriter::create (&path).unwrap ();
+
+    writer.add ("stored.txt", text.as_bytes (), PackCompression::None).unwrap ();
+    writer.add ("zstd.txt", text.as_bytes (), PackCompression::Zstd).unwrap ();
+
+    let entries  = writer.finish ().unwrap ();
+    let mut data = fs::read (&path).unwrap ();
+
+    for entry in &entries {
+        data[(entry.offset + entry.stored_size / 2) as usize] ^= 0xff;
+    }
+
+    fs::write (&path, &data).unwrap ();
+
+    // The table of contents is intact, so the pack still opens
+    let pack = PackSource::new (&path).unwrap ();
+
+    assert_eq! (pack.read ("stored.txt").unwrap_err ().kind (), io::ErrorKind::InvalidData);
+    assert! (pack.read ("zstd.txt").is_err ());
+    assert! (pack.verify ().is_err ());
+    fs::remove_file (&path).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[test]
+fn damaged_packs_are_rejected_when_opened () {
+
+    let path  = test_path ("damaged");
+    let valid = write_pack (&path, &[("a.txt", b"some data"), ("b/c.txt", b"more data")]);
+
+    let cases : Vec <(&str, Damage)> = vec! [
+        ("empty",        |data : &mut Vec <u8>| data.clear ()),
+        ("bad magic",    |data : &mut Vec <u8>| data[0] = b'X'),
+        ("bad version",  |data : &mut Vec <u8>| data[4] = 99),
+        ("truncated",    |data : &mut Vec <u8>| { data.pop (); }),
+        ("extended",     |data : &mut Vec <u8>| data.push (0)),
+        ("corrupt toc",  |data : &mut Vec <u8>| *data.last_mut ().unwrap () ^= 1),
+        ("short header", |data : &mut Vec <u8>| data.truncate (20))
+    ];
+
+    for (name, damage) in cases {
+
+        let mut data = valid.clone ();
+
+        damage (&mut data);
+        fs::write (&path, &data).unwrap ();
+
+        match PackSource::new (&path) {
+            Ok (_)      => panic! ("{} pack was opened", name),
+            Err (error) => assert_eq! (error.kind (), io::ErrorKind::InvalidData, "{}", name)
+        }
+    }
+
+    fs::remove_file (&path).unwrap ();
+}
+
+/*================================================================================================*/
+
+#[test]
+fn crafted_tables_of_contents_are_rejected () {
+
+    let path  = test_path ("crafted");
+    let valid = write_pack (&path, &[("a", b"some data"), ("x/b", b"more data"), ("y/b", b"")]);
+
+    // The first entry is "a", so its fields start after the path length, path and compression
+    let cases : Vec <(&str, Craft)> = vec! [
+
+        // A file inside another file
+        ("inside the file", |contents : &mut [u8]| replace (contents, b"x/b", b"a/b")),
+
+        // The same path twice
+        ("already in", |contents : &mut [u8]| replace (contents, b"y/b", b"x/b")),
+
+        // A path leaving the root
+        ("path is invalid", |contents : &mut [u8]| replace (contents, b"x/b", b"../")),
+
+        // A huge size for a stored file, which would be allocated when read
+        ("invalid size", |contents : &mut [u8]| {
+            contents[20..28].copy_from_slice (&(1u64 << 62).to_le_bytes ());
+        }),
+
+        // Data past the end of the file data
+        ("out of bounds", |contents : &mut [u8]| {
+            contents[4..12].copy_from_slice (&u64::MAX.to_le_bytes ());
+        }),
+
+        // An unknown compression
+        ("unknown pack compression", |contents : &mut [u8]| contents[3] = 7)
+    ];
+
+    for (message, craft) in cases {
+
+        let mut data = valid.clone ();
+
+        rewrite_contents (&mut data, craft);
+        fs::write (&path, &data).unwrap ();
+
+        match PackSource::new (&path) {
+
+            Ok (_) => panic! ("pack was opened instead of failing with \"{}\"", message),
+
+            Err (error) => {
+                assert_eq! (error.kind (), io::ErrorKind::InvalidData);
+                assert! (error.to_string ().contains (message), "{} != {}", error, message);
+            }
+        }
+    }
+
+    // Rewriting the contents without changing them keeps the pack valid
+    let mut data = valid.clone ();
+
+    rewrite_contents (&mut data, |_| ());
+    fs::write (&path, &data).unwrap ();
+
+    assert_eq! (PackSource::new (&path).unwrap ().verify ().unwrap (), 3);
+    fs::remove_file (&path).unwrap ();
+}
+
+/*================================================================================================*/
+/*------FUNCTIONS---------------------------------------------------------------------------------*/
+/*================================================================================================*/
+
+// Returns a path for a test's pack, in the temporary directory
+fn test_path (name : &str) -> PathBuf {
+
+    env::temp_dir ().join (format! ("trivert_pack_{}_{}.pack", name, process::id ()))
+}
+
+/*================================================================================================*/
+
+// Writes a pack of stored files, returning its data
+fn write_pack (path : &PathBuf, files : &[(&str, &[u8])]) -> Vec <u8> {
+
+    let mut writer = PackWriter::create (path).unwrap ();
+
+    for &(name, data) in files {
+        writer.add (name, data, PackCompression::None).unwrap ();
+    }
+
+    writer.finish ().unwrap ();
+    fs::read (path).unwrap ()
+}
+
+/*================================================================================================*/
+
+// Changes the table of contents of a pack, updating its hash so only the change is checked
+fn rewrite_contents<F : FnOnce (&mut [u8])> (data : &mut [u8], change : F) {
+
+    let mut position = [0; 8];
+
+    position.copy_from_slice (&data[12..20]);
+
+    let start = u64::from_le_bytes (position) as usize;
+
+    change (&mut data[start..]);
+
+    let hash = xxh3_64 (&data[start..]);
+    data[28..36].copy_from_slice (&hash.to_le_bytes ());
+}
+
+/*================================================================================================*/
+
+// Replaces the first occurrence of some bytes with others of the same length
+fn replace (data : &mut [u8], from : &[u8], to : &[u8]) {
+
+    let start = data.windows (from.len ()).position (|window| window == from).unwrap ();
+    data[start..start + to.len ()].copy_from_slice (to);
+}